@@ -1,8 +1,20 @@
 use super::{
-    error::AgentBuildError, output::AgentOutputT, AgentExecutor, IntoRunnable, RunnableAgent,
+    error::AgentBuildError, output::AgentOutputT, render_system_prompt, validate_system_prompt,
+    AgentExecutor, HandoffTool, IntoRunnable, PromptError, RunnableAgent,
 };
 use crate::{
-    error::Error, memory::MemoryProvider, protocol::AgentID, runtime::Runtime, tool::ToolT,
+    budget::Budget,
+    checkpoint::CheckpointStore,
+    error::Error,
+    fallback::{FallbackPolicy, NamedLLMProvider},
+    guardrail::Guardrail,
+    memory::MemoryProvider,
+    middleware::Middleware,
+    protocol::AgentID,
+    runtime::{Runtime, Task},
+    tool::{
+        output::ToolOutputLimits, CapabilitySet, SharedTool, ToolRegistry, ToolRegistryError, ToolT,
+    },
 };
 use async_trait::async_trait;
 use autoagents_llm::{chat::StructuredOutputFormat, LLMProvider};
@@ -28,6 +40,14 @@ pub trait AgentDeriveT: Send + Sync + 'static + AgentExecutor + Debug {
 
     /// Get the tools available to this agent
     fn tools(&self) -> Vec<Box<dyn ToolT>>;
+
+    /// Jinja template of the system prompt, rendered for every task.
+    ///
+    /// Agents without a template use their description as the system prompt, see
+    /// [`render_system_prompt`](super::render_system_prompt) for the template variables.
+    fn system_prompt(&self) -> Option<&'static str> {
+        None
+    }
 }
 
 pub struct AgentConfig {
@@ -39,6 +59,8 @@ pub struct AgentConfig {
     pub id: AgentID,
     /// The output schema for the agent
     pub output_schema: Option<StructuredOutputFormat>,
+    /// Store the executor saves task progress to, if the agent checkpoints its tasks
+    pub checkpoint_store: Option<Arc<dyn CheckpointStore>>,
 }
 
 /// Base agent type that wraps an AgentDeriveT implementation with additional runtime components
@@ -52,6 +74,26 @@ pub struct BaseAgent<T: AgentDeriveT> {
     pub id: AgentID,
     /// Optional memory provider
     pub memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+    /// Tools given to the agent in addition to the ones it declares
+    pub extra_tools: Vec<SharedTool>,
+    /// Registry whose tools the agent can call, as registered when a task starts
+    pub tool_registry: Option<ToolRegistry>,
+    /// Optional store for checkpoints of the agent's tasks
+    pub checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    /// Limits on the tokens and cost of each task, unless the task sets its own
+    pub budget: Option<Budget>,
+    /// Policy checks on the prompts, tool results and outputs of the agent's tasks
+    pub guardrails: Vec<Arc<dyn Guardrail>>,
+    /// Limits on the size of tool results, before they reach memory
+    pub tool_output_limits: Option<ToolOutputLimits>,
+    /// Capabilities of the tools the agent may call, every capability if not set
+    pub capabilities: Option<CapabilitySet>,
+    /// Hooks around the tasks, LLM calls and tool calls of the agent
+    pub middleware: Vec<Arc<dyn Middleware>>,
+    /// Providers tried in order when the primary LLM fails
+    pub fallback_llms: Vec<NamedLLMProvider>,
+    /// Failures that move a call on to the next provider
+    pub fallback_policy: FallbackPolicy,
 }
 
 impl<T: AgentDeriveT> Debug for BaseAgent<T> {
@@ -73,6 +115,16 @@ impl<T: AgentDeriveT> BaseAgent<T> {
             id: Uuid::new_v4(),
             llm,
             memory: memory.map(|m| Arc::new(RwLock::new(m))),
+            extra_tools: Vec::new(),
+            tool_registry: None,
+            checkpoint_store: None,
+            budget: None,
+            guardrails: Vec::new(),
+            tool_output_limits: None,
+            capabilities: None,
+            middleware: Vec::new(),
+            fallback_llms: Vec::new(),
+            fallback_policy: FallbackPolicy::default(),
         }
     }
 
@@ -92,7 +144,26 @@ impl<T: AgentDeriveT> BaseAgent<T> {
 
     /// Get the tools as Arc-wrapped references
     pub fn tools(&self) -> Vec<Box<dyn ToolT>> {
-        self.inner.tools()
+        let mut tools = self.inner.tools();
+        tools.extend(SharedTool::boxed(&self.extra_tools));
+        if let Some(registry) = &self.tool_registry {
+            tools.extend(registry.tools());
+        }
+        tools
+    }
+
+    /// Fail if a registered tool has the name of a tool the agent declares, the model could
+    /// not tell them apart
+    pub fn check_tool_names(&self) -> Result<(), ToolRegistryError> {
+        let Some(registry) = &self.tool_registry else {
+            return Ok(());
+        };
+        let mut declared = self.inner.tools();
+        declared.extend(SharedTool::boxed(&self.extra_tools));
+        match declared.iter().find(|tool| registry.contains(tool.name())) {
+            Some(tool) => Err(ToolRegistryError::NameTaken(tool.name().to_string())),
+            None => Ok(()),
+        }
     }
 
     pub fn agent_config(&self) -> AgentConfig {
@@ -103,9 +174,19 @@ impl<T: AgentDeriveT> BaseAgent<T> {
             description: self.description().into(),
             id: self.id,
             output_schema: structured_schema,
+            checkpoint_store: self.checkpoint_store.clone(),
         }
     }
 
+    /// Config of the agent for a task, with the system prompt template rendered as description
+    pub fn task_config(&self, task: &Task) -> Result<AgentConfig, PromptError> {
+        let mut config = self.agent_config();
+        if let Some(template) = self.inner.system_prompt() {
+            config.description = render_system_prompt(template, &config, &self.tools(), task)?;
+        }
+        Ok(config)
+    }
+
     /// Get the LLM provider
     pub fn llm(&self) -> Arc<dyn LLMProvider> {
         self.llm.clone()
@@ -124,6 +205,17 @@ pub struct AgentBuilder<T: AgentDeriveT + AgentExecutor> {
     memory: Option<Box<dyn MemoryProvider>>,
     runtime: Option<Arc<dyn Runtime>>,
     subscribed_topics: Vec<String>,
+    tools: Vec<SharedTool>,
+    tool_registry: Option<ToolRegistry>,
+    handoffs: Vec<AgentID>,
+    checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    budget: Option<Budget>,
+    guardrails: Vec<Arc<dyn Guardrail>>,
+    tool_output_limits: Option<ToolOutputLimits>,
+    capabilities: Option<CapabilitySet>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    fallback_llms: Vec<NamedLLMProvider>,
+    fallback_policy: FallbackPolicy,
 }
 
 impl<T: AgentDeriveT + AgentExecutor> AgentBuilder<T> {
@@ -135,6 +227,17 @@ impl<T: AgentDeriveT + AgentExecutor> AgentBuilder<T> {
             memory: None,
             runtime: None,
             subscribed_topics: vec![],
+            tools: vec![],
+            tool_registry: None,
+            handoffs: vec![],
+            checkpoint_store: None,
+            budget: None,
+            guardrails: vec![],
+            tool_output_limits: None,
+            capabilities: None,
+            middleware: vec![],
+            fallback_llms: vec![],
+            fallback_policy: FallbackPolicy::default(),
         }
     }
 
@@ -144,12 +247,81 @@ impl<T: AgentDeriveT + AgentExecutor> AgentBuilder<T> {
         self
     }
 
+    /// Add a provider to fall back on when the providers before it fail, see [`crate::fallback`]
+    pub fn with_fallback_llm(mut self, name: impl Into<String>, llm: Arc<dyn LLMProvider>) -> Self {
+        self.fallback_llms.push(NamedLLMProvider::new(name, llm));
+        self
+    }
+
+    /// Set which failures move a call on to a fallback provider
+    pub fn with_fallback_policy(mut self, policy: FallbackPolicy) -> Self {
+        self.fallback_policy = policy;
+        self
+    }
+
     /// Set the memory provider
     pub fn with_memory(mut self, memory: Box<dyn MemoryProvider>) -> Self {
         self.memory = Some(memory);
         self
     }
 
+    /// Give the agent a tool in addition to the ones it declares
+    pub fn with_tool(mut self, tool: Box<dyn ToolT>) -> Self {
+        self.tools.push(tool.into());
+        self
+    }
+
+    /// Give the agent the tools of a registry, tools added to or removed from the registry
+    /// later apply from the agent's next task
+    pub fn with_tool_registry(mut self, registry: ToolRegistry) -> Self {
+        self.tool_registry = Some(registry);
+        self
+    }
+
+    /// Allow the agent to hand off conversations to an agent registered on the runtime
+    pub fn handoff_to(mut self, agent_id: AgentID) -> Self {
+        self.handoffs.push(agent_id);
+        self
+    }
+
+    /// Checkpoint the agent's tasks after every turn so they can be resumed
+    pub fn with_checkpoint_store(mut self, store: Arc<dyn CheckpointStore>) -> Self {
+        self.checkpoint_store = Some(store);
+        self
+    }
+
+    /// Limit the tokens and cost of each task the agent runs
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Add a guardrail, guardrails apply in the order they are added
+    pub fn with_guardrail(mut self, guardrail: Arc<dyn Guardrail>) -> Self {
+        self.guardrails.push(guardrail);
+        self
+    }
+
+    /// Limit the size of tool results before they are stored in memory, see
+    /// [`crate::tool::output`]
+    pub fn with_tool_output_limits(mut self, limits: ToolOutputLimits) -> Self {
+        self.tool_output_limits = Some(limits);
+        self
+    }
+
+    /// Only allow calls to tools needing no capabilities beyond `capabilities`, see
+    /// [`crate::tool::Capability`]
+    pub fn with_capabilities(mut self, capabilities: impl Into<CapabilitySet>) -> Self {
+        self.capabilities = Some(capabilities.into());
+        self
+    }
+
+    /// Add a middleware, middleware runs in the order it is added
+    pub fn with_middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
     pub fn subscribe_topic<S: Into<String>>(mut self, topic: S) -> Self {
         self.subscribed_topics.push(topic.into());
         self
@@ -160,14 +332,42 @@ impl<T: AgentDeriveT + AgentExecutor> AgentBuilder<T> {
         let llm = self.llm.ok_or(AgentBuildError::BuildFailure(
             "LLM provider is required".to_string(),
         ))?;
-        let runnable = BaseAgent::new(self.inner, llm, self.memory).into_runnable();
-        if let Some(runtime) = self.runtime {
-            runtime.register_agent(runnable.clone()).await?;
-            for topic in self.subscribed_topics {
-                runtime.subscribe(runnable.id(), topic).await?;
-            }
-        } else {
-            return Err(AgentBuildError::BuildFailure("Runtime should be defined".into()).into());
+        let runtime = self.runtime.ok_or(AgentBuildError::BuildFailure(
+            "Runtime should be defined".into(),
+        ))?;
+        if let Some(template) = self.inner.system_prompt() {
+            validate_system_prompt(template)
+                .map_err(|e| AgentBuildError::BuildFailure(e.to_string()))?;
+        }
+        let mut agent = BaseAgent::new(self.inner, llm, self.memory);
+        agent.extra_tools = self.tools;
+        agent.tool_registry = self.tool_registry;
+        agent.checkpoint_store = self.checkpoint_store;
+        agent.budget = self.budget;
+        agent.guardrails = self.guardrails;
+        agent.tool_output_limits = self.tool_output_limits;
+        agent.capabilities = self.capabilities;
+        agent.middleware = self.middleware;
+        agent.fallback_llms = self.fallback_llms;
+        agent.fallback_policy = self.fallback_policy;
+        for target in self.handoffs {
+            let target = runtime.get_agent(target).await.ok_or_else(|| {
+                AgentBuildError::BuildFailure(format!("Handoff target {target} is not registered"))
+            })?;
+            agent
+                .extra_tools
+                .push(SharedTool::new(Arc::new(HandoffTool::to(
+                    runtime.clone(),
+                    &target,
+                ))));
+        }
+        agent
+            .check_tool_names()
+            .map_err(|e| AgentBuildError::BuildFailure(e.to_string()))?;
+        let runnable = agent.into_runnable();
+        runtime.register_agent(runnable.clone()).await?;
+        for topic in self.subscribed_topics {
+            runtime.subscribe(runnable.id(), topic).await?;
         }
         Ok(runnable)
     }
@@ -185,6 +385,7 @@ mod tests {
     use crate::memory::MemoryProvider;
     use crate::protocol::Event;
     use crate::runtime::Task;
+    use crate::tool::{ToolCallError, ToolRuntime};
     use async_trait::async_trait;
     use autoagents_llm::{chat::StructuredOutputFormat, LLMProvider};
     use autoagents_test_utils::agent::{MockAgentImpl, TestAgentOutput, TestError};
@@ -265,6 +466,7 @@ mod tests {
             id: Uuid::new_v4(),
             description: "A test agent".to_string(),
             output_schema: None,
+            checkpoint_store: None,
         };
 
         assert_eq!(config.name, "test_agent");
@@ -286,6 +488,7 @@ mod tests {
             id: Uuid::new_v4(),
             description: "A test agent".to_string(),
             output_schema: Some(schema.clone()),
+            checkpoint_store: None,
         };
 
         assert_eq!(config.name, "test_agent");
@@ -338,6 +541,69 @@ mod tests {
         assert!(tools.is_empty());
     }
 
+    #[derive(Debug)]
+    struct ClockTool;
+
+    impl ToolRuntime for ClockTool {
+        fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            Ok(Value::from("12:00"))
+        }
+    }
+
+    impl ToolT for ClockTool {
+        fn name(&self) -> &'static str {
+            "clock"
+        }
+
+        fn description(&self) -> &'static str {
+            "Tell the time"
+        }
+
+        fn args_schema(&self) -> Value {
+            serde_json::json!({"type": "object"})
+        }
+    }
+
+    #[test]
+    fn test_base_agent_sees_registry_changes() {
+        let registry = ToolRegistry::new();
+        let mut base_agent = BaseAgent::new(
+            MockAgentImpl::new("test", "test description"),
+            Arc::new(MockLLMProvider),
+            None,
+        );
+        base_agent.tool_registry = Some(registry.clone());
+        assert!(base_agent.tools().is_empty());
+
+        registry.register(ClockTool).unwrap();
+        registry.alias("clock", "time").unwrap();
+        let names: Vec<&str> = base_agent.tools().iter().map(|t| t.name()).collect();
+        assert_eq!(names, vec!["clock", "time"]);
+
+        // Aliases go with the tool
+        registry.remove("clock");
+        assert!(base_agent.tools().is_empty());
+    }
+
+    #[test]
+    fn test_registry_tools_clashing_with_declared_tools() {
+        let registry = ToolRegistry::new();
+        let mut base_agent = BaseAgent::new(
+            MockAgentImpl::new("test", "test description"),
+            Arc::new(MockLLMProvider),
+            None,
+        );
+        base_agent.extra_tools = vec![SharedTool::new(Arc::new(ClockTool))];
+        base_agent.tool_registry = Some(registry.clone());
+        assert!(base_agent.check_tool_names().is_ok());
+
+        registry.register(ClockTool).unwrap();
+        assert_eq!(
+            base_agent.check_tool_names(),
+            Err(ToolRegistryError::NameTaken("clock".to_string()))
+        );
+    }
+
     #[test]
     fn test_base_agent_llm() {
         let mock_agent = MockAgentImpl::new("test", "test description");
@@ -1,4 +1,6 @@
-use crate::agent::runnable::AgentState;
+use crate::agent::loop_detection::LoopDetection;
+use crate::agent::state::AgentState;
+use crate::checkpoint::Checkpoint;
 use crate::memory::MemoryProvider;
 use crate::protocol::Event;
 use crate::runtime::Task;
@@ -26,11 +28,46 @@ pub enum TurnResult<T> {
 #[derive(Debug, Clone)]
 pub struct ExecutorConfig {
     pub max_turns: usize,
+    /// How many times the model is re-prompted when its final response does not
+    /// match the agent's output schema
+    pub output_repair_attempts: usize,
+    /// How tools are offered to the model
+    pub tool_protocol: ToolProtocol,
+    /// When repeated tool calls count as a loop and what happens then
+    pub loop_detection: LoopDetection,
 }
 
 impl Default for ExecutorConfig {
     fn default() -> Self {
-        Self { max_turns: 10 }
+        Self {
+            max_turns: 10,
+            output_repair_attempts: 2,
+            tool_protocol: ToolProtocol::default(),
+            loop_detection: LoopDetection::default(),
+        }
+    }
+}
+
+/// How an executor exposes tools to the model
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToolProtocol {
+    /// Native tool calling when the provider supports it, the text protocol otherwise
+    #[default]
+    Auto,
+    /// The provider's native tool calling API
+    Native,
+    /// Tools are described in the prompt and calls are parsed from plain text
+    Text,
+}
+
+impl ToolProtocol {
+    /// Whether tool calls go through the text protocol with this provider
+    pub fn uses_text(self, llm: &dyn LLMProvider) -> bool {
+        match self {
+            ToolProtocol::Auto => !llm.supports_tool_calls(),
+            ToolProtocol::Native => false,
+            ToolProtocol::Text => true,
+        }
     }
 }
 
@@ -58,13 +95,40 @@ pub trait AgentExecutor: Send + Sync + 'static {
         state: Arc<RwLock<AgentState>>,
         tx_event: mpsc::Sender<Event>,
     ) -> Result<Self::Output, Self::Error>;
+
+    /// Continue a task from its last checkpoint
+    ///
+    /// Executors that do not checkpoint their progress start the task over. Tool calls that
+    /// were running when the task stopped may run again.
+    #[allow(clippy::too_many_arguments)]
+    async fn resume(
+        &self,
+        llm: Arc<dyn LLMProvider>,
+        memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+        tools: Vec<Box<dyn ToolT>>,
+        agent_config: &AgentConfig,
+        checkpoint: Checkpoint,
+        state: Arc<RwLock<AgentState>>,
+        tx_event: mpsc::Sender<Event>,
+    ) -> Result<Self::Output, Self::Error> {
+        self.execute(
+            llm,
+            memory,
+            tools,
+            agent_config,
+            checkpoint.task,
+            state,
+            tx_event,
+        )
+        .await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::agent::base::AgentConfig;
-    use crate::agent::runnable::AgentState;
+    use crate::agent::state::AgentState;
     use crate::memory::MemoryProvider;
     use crate::protocol::Event;
     use crate::runtime::Task;
@@ -128,6 +192,7 @@ mod tests {
         fn config(&self) -> ExecutorConfig {
             ExecutorConfig {
                 max_turns: self.max_turns,
+                ..Default::default()
             }
         }
 
@@ -223,24 +288,35 @@ mod tests {
     fn test_executor_config_default() {
         let config = ExecutorConfig::default();
         assert_eq!(config.max_turns, 10);
+        assert_eq!(config.output_repair_attempts, 2);
+        assert_eq!(config.tool_protocol, ToolProtocol::Auto);
     }
 
     #[test]
     fn test_executor_config_custom() {
-        let config = ExecutorConfig { max_turns: 5 };
+        let config = ExecutorConfig {
+            max_turns: 5,
+            ..Default::default()
+        };
         assert_eq!(config.max_turns, 5);
     }
 
     #[test]
     fn test_executor_config_clone() {
-        let config = ExecutorConfig { max_turns: 15 };
+        let config = ExecutorConfig {
+            max_turns: 15,
+            ..Default::default()
+        };
         let cloned = config.clone();
         assert_eq!(config.max_turns, cloned.max_turns);
     }
 
     #[test]
     fn test_executor_config_debug() {
-        let config = ExecutorConfig { max_turns: 20 };
+        let config = ExecutorConfig {
+            max_turns: 20,
+            ..Default::default()
+        };
         let debug_str = format!("{config:?}");
         assert!(debug_str.contains("ExecutorConfig"));
         assert!(debug_str.contains("20"));
@@ -291,6 +367,7 @@ mod tests {
             id: Uuid::new_v4(),
             description: "test agent".to_string(),
             output_schema: None,
+            checkpoint_store: None,
         };
         let task = Task::new("test task", None);
         let state = Arc::new(RwLock::new(AgentState::new()));
@@ -315,6 +392,7 @@ mod tests {
             id: Uuid::new_v4(),
             description: "test agent".to_string(),
             output_schema: None,
+            checkpoint_store: None,
         };
         let task = Task::new("test task", None);
         let state = Arc::new(RwLock::new(AgentState::new()));
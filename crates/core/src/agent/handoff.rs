@@ -0,0 +1,512 @@
+use super::runnable::{run_for_result, RunnableAgent};
+use crate::protocol::{AgentID, Event, TaskResult};
+use crate::runtime::{Runtime, Task};
+use crate::tool::{
+    Capability, CapabilityError, CapabilitySet, ToolCallContext, ToolCallError, ToolRuntime, ToolT,
+};
+use async_trait::async_trait;
+use autoagents_llm::chat::MessageType;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Error type for handoffs
+#[derive(Debug, Error)]
+pub enum HandoffError {
+    #[error("Handoff to '{0}' can only be called by an async executor")]
+    SyncCall(&'static str),
+
+    #[error("Handoff target '{0}' is not registered on the runtime")]
+    AgentNotFound(&'static str),
+
+    #[error("Failed to transfer the transcript: {0}")]
+    TranscriptError(String),
+
+    #[error("Agent '{agent}' failed after the handoff: {reason}")]
+    TaskFailed { agent: &'static str, reason: String },
+}
+
+impl From<HandoffError> for ToolCallError {
+    fn from(error: HandoffError) -> Self {
+        ToolCallError::RuntimeError(Box::new(error))
+    }
+}
+
+/// Tool that hands the conversation off to another agent of the runtime.
+///
+/// When called, the text messages of the calling agent's memory are copied into the
+/// target's memory and the remaining task runs on the target under the caller's
+/// [`SubmissionId`](crate::protocol::SubmissionId). The target's result becomes the
+/// caller's final response, so the task completes once, against the original submission.
+///
+/// The target continues with its own tools, so a caller granted capabilities can only hand
+/// off to a target whose capabilities it was granted as well.
+#[derive(Clone)]
+pub struct HandoffTool {
+    runtime: Arc<dyn Runtime>,
+    target: AgentID,
+    target_name: &'static str,
+    /// Capabilities of the target when it is known up front
+    target_capabilities: Option<CapabilitySet>,
+    name: &'static str,
+    description: &'static str,
+}
+
+impl Debug for HandoffTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HandoffTool")
+            .field("target", &self.target)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl HandoffTool {
+    /// Hand off to the agent `target`, which must be registered on `runtime` by the time
+    /// the tool is called. Its capabilities are checked against the caller's when called
+    pub fn new(
+        runtime: Arc<dyn Runtime>,
+        target: AgentID,
+        target_name: &'static str,
+        target_description: &str,
+    ) -> Self {
+        // Tool names and descriptions are static, a handoff tool lives as long as its agent
+        let name: &'static str = Box::leak(format!("transfer_to_{target_name}").into_boxed_str());
+        let description: &'static str = Box::leak(
+            format!(
+                "Hand the conversation off to {target_name} when it is better suited to \
+                 continue. {target_description}"
+            )
+            .into_boxed_str(),
+        );
+        Self {
+            runtime,
+            target,
+            target_name,
+            target_capabilities: None,
+            name,
+            description,
+        }
+    }
+
+    /// Hand off to an agent already built, the tool needs the capabilities of the agent
+    pub fn to(runtime: Arc<dyn Runtime>, target: &Arc<dyn RunnableAgent>) -> Self {
+        let mut tool = Self::new(runtime, target.id(), target.name(), target.description());
+        tool.target_capabilities = Some(target.capabilities());
+        tool
+    }
+
+    pub fn target(&self) -> AgentID {
+        self.target
+    }
+}
+
+impl ToolRuntime for HandoffTool {
+    fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+        Err(HandoffError::SyncCall(self.target_name).into())
+    }
+}
+
+#[async_trait]
+impl ToolT for HandoffTool {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn args_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "task": {
+                    "type": "string",
+                    "description": "What remains to be done, for the agent taking over"
+                }
+            },
+            "required": ["task"]
+        })
+    }
+
+    fn returns_directly(&self) -> bool {
+        true
+    }
+
+    /// A handoff can do anything the target's tools can, so callers need all of their
+    /// capabilities
+    fn capabilities(&self) -> Vec<Capability> {
+        self.target_capabilities
+            .iter()
+            .flat_map(CapabilitySet::iter)
+            .collect()
+    }
+
+    async fn call(&self, args: Value, context: ToolCallContext) -> Result<Value, ToolCallError> {
+        let agent = self
+            .runtime
+            .get_agent(self.target)
+            .await
+            .ok_or(HandoffError::AgentNotFound(self.target_name))?;
+        // Targets given by id are only known now, and a registered agent can be replaced
+        if let Some(granted) = &context.capabilities {
+            let required: Vec<Capability> = agent.capabilities().iter().collect();
+            let missing = granted.missing(&required);
+            if !missing.is_empty() {
+                let _ = context
+                    .tx_event
+                    .send(Event::ToolCallDenied {
+                        sub_id: context.sub_id,
+                        tool_name: self.name.to_string(),
+                        missing: missing.clone(),
+                    })
+                    .await;
+                return Err(CapabilityError::NotGranted {
+                    tool: self.name.to_string(),
+                    missing,
+                }
+                .into());
+            }
+        }
+        let remaining = args["task"].as_str().unwrap_or_default().to_string();
+
+        // Tool use messages refer to tools the target may not have, only text carries over
+        if let (Some(source), Some(target)) = (&context.memory, agent.memory()) {
+            let messages = source
+                .read()
+                .await
+                .recall("", None)
+                .await
+                .map_err(|e| HandoffError::TranscriptError(e.to_string()))?;
+            let mut target = target.write().await;
+            for message in messages
+                .iter()
+                .filter(|m| matches!(m.message_type, MessageType::Text))
+            {
+                target
+                    .remember(message)
+                    .await
+                    .map_err(|e| HandoffError::TranscriptError(e.to_string()))?;
+            }
+        }
+
+        let _ = context
+            .tx_event
+            .send(Event::TaskHandedOff {
+                sub_id: context.sub_id,
+                agent_id: self.target,
+                agent_name: self.target_name.to_string(),
+                task: remaining.clone(),
+            })
+            .await;
+
+        // The caller completes the submission, so the target's completion is not forwarded
+        let sub_id = context.sub_id;
+        let mut task = Task::new(remaining, Some(self.target));
+        task.submission_id = sub_id;
+        let result = run_for_result(
+            agent,
+            task,
+            context.tx_event.clone(),
+            move |event| match &event {
+                Event::TaskComplete {
+                    sub_id: completed, ..
+                } if *completed == sub_id => None,
+                _ => Some(event),
+            },
+        )
+        .await
+        .map_err(|e| HandoffError::TaskFailed {
+            agent: self.target_name,
+            reason: e.to_string(),
+        })?;
+
+        match result {
+            TaskResult::Value(value) => Ok(value),
+            TaskResult::Failure(reason) => Err(HandoffError::TaskFailed {
+                agent: self.target_name,
+                reason,
+            }
+            .into()),
+            TaskResult::Aborted => Err(HandoffError::TaskFailed {
+                agent: self.target_name,
+                reason: "Task was aborted".to_string(),
+            }
+            .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::prebuilt::react::{ReActAgentOutput, ReActExecutor};
+    use crate::agent::{
+        AgentConfig, AgentDeriveT, AgentExecutor, AgentState, BaseAgent, IntoRunnable,
+    };
+    use crate::error::Error;
+    use crate::memory::{MemoryProvider, SlidingWindowMemory};
+    use crate::protocol::SubmissionId;
+    use crate::runtime::SingleThreadedRuntime;
+    use autoagents_llm::chat::{ChatMessage, ChatRole, StructuredOutputFormat};
+    use autoagents_test_utils::llm::{tool_call, ScriptedLLMProvider, ScriptedResponse};
+    use std::sync::Mutex;
+    use tokio::sync::{mpsc, RwLock};
+    use uuid::Uuid;
+
+    struct BillingAgent {
+        id: AgentID,
+        memory: Arc<RwLock<Box<dyn MemoryProvider>>>,
+        sub_ids: Mutex<Vec<SubmissionId>>,
+    }
+
+    impl std::fmt::Debug for BillingAgent {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("billing")
+        }
+    }
+
+    #[async_trait]
+    impl RunnableAgent for BillingAgent {
+        fn name(&self) -> &'static str {
+            "billing"
+        }
+
+        fn description(&self) -> &'static str {
+            "Handles invoices and refunds"
+        }
+
+        fn id(&self) -> AgentID {
+            self.id
+        }
+
+        fn memory(&self) -> Option<Arc<RwLock<Box<dyn MemoryProvider>>>> {
+            Some(self.memory.clone())
+        }
+
+        fn capabilities(&self) -> CapabilitySet {
+            CapabilitySet::from([Capability::Network])
+        }
+
+        async fn run(self: Arc<Self>, task: Task, tx: mpsc::Sender<Event>) -> Result<(), Error> {
+            self.sub_ids.lock().unwrap().push(task.submission_id);
+            let seen = self.memory.read().await.size();
+            let _ = tx
+                .send(Event::TaskComplete {
+                    sub_id: task.submission_id,
+                    result: TaskResult::Value(Value::String(format!(
+                        "refunded ({}) after {seen} messages",
+                        task.prompt
+                    ))),
+                })
+                .await;
+            Ok(())
+        }
+    }
+
+    struct TriageExecutor;
+
+    impl ReActExecutor for TriageExecutor {}
+
+    async fn setup() -> (Arc<SingleThreadedRuntime>, Arc<BillingAgent>) {
+        let runtime = SingleThreadedRuntime::new(None);
+        let billing = Arc::new(BillingAgent {
+            id: Uuid::new_v4(),
+            memory: Arc::new(RwLock::new(Box::new(SlidingWindowMemory::new(10)))),
+            sub_ids: Mutex::new(Vec::new()),
+        });
+        runtime.register_agent(billing.clone()).await.unwrap();
+        (runtime, billing)
+    }
+
+    #[tokio::test]
+    async fn test_handoff_tool_metadata() {
+        let (runtime, billing) = setup().await;
+        let agent: Arc<dyn RunnableAgent> = billing;
+        let tool = HandoffTool::to(runtime, &agent);
+        assert_eq!(tool.name(), "transfer_to_billing");
+        assert!(tool.description().contains("Handles invoices and refunds"));
+        assert!(tool.returns_directly());
+        assert!(tool.run(serde_json::json!({"task": "refund"})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_restricted_caller_cannot_hand_off_to_more_capable_target() {
+        let (runtime, billing) = setup().await;
+        let agent: Arc<dyn RunnableAgent> = billing.clone();
+        let known = HandoffTool::to(runtime.clone(), &agent);
+        assert_eq!(known.capabilities(), vec![Capability::Network]);
+        // A target given by id is checked when the tool is called
+        let by_id = HandoffTool::new(runtime.clone(), billing.id, "billing", "Handles refunds");
+        assert!(by_id.capabilities().is_empty());
+
+        let caller = CapabilitySet::from([Capability::FilesystemRead]);
+        for tool in caller.restrict_tools(vec![Box::new(known), Box::new(by_id.clone())]) {
+            let (context, mut rx) = context();
+            let error = tool
+                .call(serde_json::json!({"task": "refund"}), context)
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("not granted: network"));
+            assert!(matches!(
+                rx.try_recv(),
+                Ok(Event::ToolCallDenied { missing, .. }) if missing == vec![Capability::Network]
+            ));
+        }
+        assert!(billing.sub_ids.lock().unwrap().is_empty());
+
+        // Granted the capabilities of the target, the caller hands off
+        let tools =
+            CapabilitySet::from([Capability::Network]).restrict_tools(vec![Box::new(by_id)]);
+        let (context, _rx) = context();
+        let result = tools[0]
+            .call(serde_json::json!({"task": "refund"}), context)
+            .await
+            .unwrap();
+        assert_eq!(result, "refunded (refund) after 0 messages");
+    }
+
+    fn context() -> (ToolCallContext, mpsc::Receiver<Event>) {
+        let (tx, rx) = mpsc::channel(10);
+        let context = ToolCallContext {
+            sub_id: Uuid::new_v4(),
+            call_id: "call_1".to_string(),
+            tx_event: tx,
+            memory: None,
+            capabilities: None,
+        };
+        (context, rx)
+    }
+
+    /// Billing agent running the ReAct executor, as agents handed off to usually do
+    #[derive(Debug, Clone)]
+    struct RefundAgent;
+
+    impl ReActExecutor for RefundAgent {}
+
+    impl AgentDeriveT for RefundAgent {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "Handles invoices and refunds"
+        }
+
+        fn output_schema(&self) -> Option<Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "billing"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_react_hands_off_and_keeps_submission() {
+        let runtime = SingleThreadedRuntime::new(None);
+        let billing_llm = Arc::new(ScriptedLLMProvider::with_texts(vec![
+            "Order 42 was refunded",
+        ]));
+        let billing = BaseAgent::new(
+            RefundAgent,
+            billing_llm.clone(),
+            Some(Box::new(SlidingWindowMemory::new(10))),
+        )
+        .into_runnable();
+        runtime.register_agent(billing.clone()).await.unwrap();
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(HandoffTool::to(runtime, &billing))];
+        let llm = Arc::new(ScriptedLLMProvider::new(vec![ScriptedResponse::ToolCalls(
+            vec![tool_call(
+                "call_1",
+                "transfer_to_billing",
+                r#"{"task":"refund order 42"}"#,
+            )],
+        )]));
+        let memory: Arc<RwLock<Box<dyn MemoryProvider>>> =
+            Arc::new(RwLock::new(Box::new(SlidingWindowMemory::new(10))));
+        memory
+            .write()
+            .await
+            .remember(&ChatMessage {
+                role: ChatRole::Assistant,
+                message_type: MessageType::Text,
+                content: "Hi, how can I help?".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let task = Task::new("I want a refund for order 42", None);
+        let sub_id = task.submission_id;
+        let (tx, mut rx) = mpsc::channel(100);
+        let output: ReActAgentOutput = TriageExecutor
+            .execute(
+                llm.clone(),
+                Some(memory),
+                tools,
+                &AgentConfig {
+                    name: "triage".to_string(),
+                    description: "Routes customers".to_string(),
+                    id: Uuid::new_v4(),
+                    // The target's answer is not the caller's to repair
+                    output_schema: Some(StructuredOutputFormat {
+                        name: "Answer".to_string(),
+                        description: None,
+                        schema: Some(serde_json::json!({
+                            "type": "object",
+                            "properties": {"answer": {"type": "string"}},
+                            "required": ["answer"]
+                        })),
+                        strict: None,
+                    }),
+                    checkpoint_store: None,
+                },
+                task,
+                Arc::new(RwLock::new(AgentState::new())),
+                tx,
+            )
+            .await
+            .unwrap();
+
+        // The response of the target, not its serialized output
+        assert_eq!(output.response, "Order 42 was refunded");
+        assert_eq!(llm.requests().len(), 1);
+        // Greeting and customer request were transferred to the target
+        let prompt = serde_json::to_string(&billing_llm.requests()[0]).unwrap();
+        assert!(prompt.contains("Hi, how can I help?"));
+        assert!(prompt.contains("I want a refund for order 42"));
+        assert!(prompt.contains("refund order 42"));
+
+        let mut handed_off = false;
+        let mut target_started = false;
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                Event::TaskHandedOff {
+                    sub_id: id,
+                    agent_name,
+                    ..
+                } => {
+                    assert_eq!(id, sub_id);
+                    assert_eq!(agent_name, "billing");
+                    handed_off = true;
+                }
+                Event::TaskStarted {
+                    sub_id: id,
+                    agent_id,
+                    ..
+                } if agent_id == billing.id() => {
+                    assert_eq!(id, sub_id);
+                    target_started = true;
+                }
+                Event::TaskComplete { .. } => panic!("Target completion must not be forwarded"),
+                _ => {}
+            }
+        }
+        assert!(handed_off);
+        assert!(target_started);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::tool::ToolCallResult;
+
+/// Detection of an agent repeating the same tool calls without making progress
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopDetection {
+    /// Identical calls in a row that make a loop. A single call is no repeat, so `0` and `1`
+    /// disable the check
+    pub max_repeats: usize,
+    /// Longest sequence of calls that makes a loop when it occurs twice in a row, such as
+    /// `a, b, a, b`. Below `2` oscillations are not checked
+    pub max_cycle_len: usize,
+    /// Abort the task on a loop instead of telling the model to change course
+    pub abort: bool,
+}
+
+impl Default for LoopDetection {
+    fn default() -> Self {
+        Self {
+            max_repeats: 3,
+            max_cycle_len: 3,
+            abort: false,
+        }
+    }
+}
+
+/// Calls an agent keeps repeating
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolLoop {
+    /// Tools of the repeated sequence of calls, in call order
+    pub tools: Vec<String>,
+    /// How many times the sequence occurred in a row
+    pub repeats: usize,
+}
+
+impl ToolLoop {
+    /// Observation telling the model to stop repeating itself
+    pub fn correction(&self) -> String {
+        let calls = self
+            .tools
+            .iter()
+            .map(|tool| format!("`{tool}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let pattern = if self.tools.len() == 1 {
+            format!(
+                "You called {calls} with the same arguments {} times in a row",
+                self.repeats
+            )
+        } else {
+            format!("You keep alternating between the same calls to {calls}")
+        };
+        format!(
+            "{pattern} and it is not making progress. Do not repeat these calls, use the results \
+             you already have or try a different approach."
+        )
+    }
+}
+
+impl LoopDetection {
+    /// Loop at the end of `calls`, the tool calls of a task in order
+    pub fn detect(&self, calls: &[ToolCallResult]) -> Option<ToolLoop> {
+        let same = |a: &ToolCallResult, b: &ToolCallResult| {
+            a.tool_name == b.tool_name && a.arguments == b.arguments
+        };
+
+        if self.max_repeats > 1 && calls.len() >= self.max_repeats {
+            let tail = &calls[calls.len() - self.max_repeats..];
+            if tail.iter().all(|call| same(call, &tail[0])) {
+                return Some(ToolLoop {
+                    tools: vec![tail[0].tool_name.clone()],
+                    repeats: self.max_repeats,
+                });
+            }
+        }
+
+        for len in 2..=self.max_cycle_len {
+            if calls.len() < 2 * len {
+                break;
+            }
+            let tail = &calls[calls.len() - 2 * len..];
+            let (first, second) = tail.split_at(len);
+            // A cycle of one call repeated is caught by the repeat check
+            let single = first.iter().all(|call| same(call, &first[0]));
+            if !single && first.iter().zip(second).all(|(a, b)| same(a, b)) {
+                return Some(ToolLoop {
+                    tools: first.iter().map(|call| call.tool_name.clone()).collect(),
+                    repeats: 2,
+                });
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(tool: &str, query: &str) -> ToolCallResult {
+        ToolCallResult {
+            tool_name: tool.to_string(),
+            success: true,
+            arguments: json!({ "query": query }),
+            result: json!("no results"),
+        }
+    }
+
+    #[test]
+    fn test_detects_repeated_calls() {
+        let detection = LoopDetection::default();
+        let mut calls = vec![call("search", "rust"), call("search", "rust")];
+        assert_eq!(detection.detect(&calls), None);
+
+        calls.push(call("search", "rust"));
+        let detected = detection.detect(&calls).unwrap();
+        assert_eq!(detected.tools, vec!["search"]);
+        assert_eq!(detected.repeats, 3);
+        assert!(detected.correction().contains("same arguments 3 times"));
+
+        // Different arguments are progress
+        calls.push(call("search", "rust async"));
+        assert_eq!(detection.detect(&calls), None);
+    }
+
+    #[test]
+    fn test_detects_oscillation() {
+        let detection = LoopDetection::default();
+        let calls = vec![
+            call("open", "a.txt"),
+            call("search", "main"),
+            call("open", "a.txt"),
+            call("search", "main"),
+        ];
+        assert_eq!(
+            detection.detect(&calls).map(|detected| detected.tools),
+            Some(vec!["open".to_string(), "search".to_string()])
+        );
+
+        for max_repeats in [0, 1] {
+            let disabled = LoopDetection {
+                max_repeats,
+                max_cycle_len: 0,
+                abort: false,
+            };
+            assert_eq!(disabled.detect(&calls), None);
+            assert_eq!(
+                disabled.detect(&[call("a", "x"), call("a", "x"), call("a", "x")]),
+                None
+            );
+        }
+    }
+}
@@ -1,13 +1,24 @@
 mod base;
 mod error;
 mod executor;
+mod handoff;
+mod loop_detection;
 mod output;
 pub mod prebuilt;
+mod prompt;
 mod runnable;
+mod state;
+mod tool;
 
 pub use base::{AgentBuilder, AgentConfig, AgentDeriveT, BaseAgent};
 pub use error::AgentResultError;
 pub use error::{AgentBuildError, RunnableAgentError};
-pub use executor::{AgentExecutor, ExecutorConfig, TurnResult};
-pub use output::AgentOutputT;
-pub use runnable::{AgentState, IntoRunnable, RunnableAgent};
+pub use executor::{AgentExecutor, ExecutorConfig, ToolProtocol, TurnResult};
+pub use handoff::{HandoffError, HandoffTool};
+pub use loop_detection::{LoopDetection, ToolLoop};
+pub use output::{validate_output, AgentOutputT, OutputValidationError};
+pub use prompt::{render_system_prompt, validate_system_prompt, PromptError};
+pub(crate) use runnable::{resume_for_result, run_for_result};
+pub use runnable::{IntoRunnable, RunnableAgent};
+pub use state::{AgentState, ReflectionRound};
+pub use tool::{AgentTool, AgentToolError};
@@ -1,4 +1,8 @@
+use crate::tool::{validate_arguments, ArgumentsValidationError};
+use autoagents_llm::chat::StructuredOutputFormat;
+use log::warn;
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 
 /// Trait for agent output types that can generate structured output schemas
 pub trait AgentOutputT: Serialize + DeserializeOwned + Send + Sync {
@@ -20,6 +24,69 @@ impl AgentOutputT for String {
     }
 }
 
+/// Error returned when a structured response does not match the agent's output schema
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum OutputValidationError {
+    #[error("Response is not valid JSON: {0}")]
+    InvalidJson(String),
+
+    #[error("Response does not match the output schema: {}", .0.join("; "))]
+    SchemaMismatch(Vec<String>),
+}
+
+impl OutputValidationError {
+    /// Prompt asking the model to correct its previous response
+    pub fn repair_prompt(&self) -> String {
+        let problems = match self {
+            Self::InvalidJson(e) => format!("- the response is not valid JSON ({e})"),
+            Self::SchemaMismatch(errors) => errors
+                .iter()
+                .map(|e| format!("- {e}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        format!(
+            "Your previous response does not match the required output schema:\n{problems}\n\n\
+             Respond again with only a JSON object that satisfies the schema."
+        )
+    }
+}
+
+/// Validate a structured response against the output format of an agent.
+///
+/// Returns the parsed JSON value. Formats without a schema accept any JSON.
+pub fn validate_output(
+    format: &StructuredOutputFormat,
+    response: &str,
+) -> Result<Value, OutputValidationError> {
+    let value: Value = serde_json::from_str(response.trim())
+        .map_err(|e| OutputValidationError::InvalidJson(e.to_string()))?;
+    let Some(schema) = &format.schema else {
+        return Ok(value);
+    };
+    match validate_arguments(schema, &value) {
+        Ok(()) => Ok(value),
+        Err(ArgumentsValidationError::InvalidSchema(e)) => {
+            warn!("Skipping output validation for '{}': {e}", format.name);
+            Ok(value)
+        }
+        Err(ArgumentsValidationError::Invalid(violations)) => {
+            Err(OutputValidationError::SchemaMismatch(
+                violations
+                    .into_iter()
+                    .map(|v| {
+                        if v.path.is_empty() {
+                            v.message
+                        } else {
+                            format!("{}: {}", v.path, v.message)
+                        }
+                    })
+                    .collect(),
+            ))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -448,4 +515,52 @@ mod tests {
             assert_eq!(output, deserialized);
         }
     }
+
+    fn test_format() -> StructuredOutputFormat {
+        StructuredOutputFormat {
+            name: "TestOutput".to_string(),
+            description: None,
+            schema: Some(TestOutput::structured_output_format()),
+            strict: Some(true),
+        }
+    }
+
+    #[test]
+    fn test_validate_output_success() {
+        let value = validate_output(&test_format(), r#"{"message": "hi", "count": 1}"#).unwrap();
+        assert_eq!(value["count"], 1);
+    }
+
+    #[test]
+    fn test_validate_output_invalid_json() {
+        let err = validate_output(&test_format(), "The answer is 1").unwrap_err();
+        assert!(matches!(err, OutputValidationError::InvalidJson(_)));
+        assert!(err.repair_prompt().contains("not valid JSON"));
+    }
+
+    #[test]
+    fn test_validate_output_schema_mismatch() {
+        let err =
+            validate_output(&test_format(), r#"{"message": "hi", "count": "1"}"#).unwrap_err();
+        match &err {
+            OutputValidationError::SchemaMismatch(errors) => {
+                assert_eq!(errors.len(), 1);
+                assert!(errors[0].starts_with("/count"));
+            }
+            _ => panic!("Expected SchemaMismatch"),
+        }
+        assert!(err.repair_prompt().contains("/count"));
+    }
+
+    #[test]
+    fn test_validate_output_without_schema() {
+        let format = StructuredOutputFormat {
+            name: "Any".to_string(),
+            description: None,
+            schema: None,
+            strict: None,
+        };
+        assert!(validate_output(&format, r#"{"anything": true}"#).is_ok());
+        assert!(validate_output(&format, "plain").is_err());
+    }
 }
@@ -1 +1,5 @@
+pub mod plan_and_execute;
 pub mod react;
+pub mod reflection;
+pub mod router;
+pub mod text_protocol;
@@ -0,0 +1,896 @@
+//! Plan-and-execute executor.
+//!
+//! The executor first asks the LLM for an explicit step plan (via structured output),
+//! then runs every step with a ReAct sub-loop that has access to the agent's tools.
+//! When a step fails the remaining plan is revised, and once all steps are done the
+//! LLM synthesizes the final answer from the step results.
+//!
+//! Agents opt in by delegating their [`AgentExecutor`] implementation:
+//!
+//! ```ignore
+//! #[async_trait]
+//! impl AgentExecutor for ResearchAgent {
+//!     type Output = PlanAndExecuteOutput;
+//!     type Error = PlanAndExecuteError;
+//!
+//!     fn config(&self) -> ExecutorConfig {
+//!         self.executor.config()
+//!     }
+//!
+//!     async fn execute(&self, llm, memory, tools, agent_config, task, state, tx_event)
+//!         -> Result<Self::Output, Self::Error> {
+//!         self.executor
+//!             .execute(llm, memory, tools, agent_config, task, state, tx_event)
+//!             .await
+//!     }
+//! }
+//! ```
+use crate::agent::base::AgentConfig;
+use crate::agent::executor::{AgentExecutor, ExecutorConfig, TurnResult};
+use crate::agent::prebuilt::react::{ReActExecutor, ReActExecutorError};
+use crate::agent::state::AgentState;
+use crate::memory::{MemoryProvider, SlidingWindowMemory};
+use crate::protocol::Event;
+use crate::runtime::Task;
+use crate::tool::{ToolCallResult, ToolT};
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType, StructuredOutputFormat};
+use autoagents_llm::LLMProvider;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::mpsc::error::SendError;
+use tokio::sync::{mpsc, RwLock};
+
+/// A single step of a plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanStep {
+    /// Position of the step in the plan
+    pub index: usize,
+    /// What the step should accomplish
+    pub description: String,
+}
+
+/// Result of executing a single plan step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepResult {
+    pub step: PlanStep,
+    pub success: bool,
+    pub response: String,
+    pub tool_calls: Vec<ToolCallResult>,
+}
+
+/// Output of the plan-and-execute executor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanAndExecuteOutput {
+    /// The final plan, including revisions made after failed steps
+    pub plan: Vec<PlanStep>,
+    /// Results of every executed step, in execution order
+    pub step_results: Vec<StepResult>,
+    /// The final answer synthesized from the step results
+    pub response: String,
+}
+
+impl From<PlanAndExecuteOutput> for Value {
+    fn from(output: PlanAndExecuteOutput) -> Self {
+        serde_json::to_value(output).unwrap_or(Value::Null)
+    }
+}
+
+impl PlanAndExecuteOutput {
+    /// Extract the agent output from the final answer
+    /// This parses the response string as JSON and deserializes it to the target type
+    pub fn extract_agent_output<T>(val: Value) -> Result<T, PlanAndExecuteError>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        let output: Self = serde_json::from_value(val)
+            .map_err(|e| PlanAndExecuteError::AgentOutputError(e.to_string()))?;
+        serde_json::from_str(&output.response)
+            .map_err(|e| PlanAndExecuteError::AgentOutputError(e.to_string()))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum PlanAndExecuteError {
+    #[error("LLM error: {0}")]
+    LLMError(String),
+
+    #[error("Planning error: {0}")]
+    PlanningError(String),
+
+    #[error("Step {index} failed after {max_replans} re-plans: {reason}")]
+    StepFailed {
+        index: usize,
+        max_replans: usize,
+        reason: String,
+    },
+
+    #[error("Event error: {0}")]
+    EventError(#[from] SendError<Event>),
+
+    #[error("Extracting Agent Output Error: {0}")]
+    AgentOutputError(String),
+}
+
+/// Configuration for the plan-and-execute executor
+#[derive(Debug, Clone)]
+pub struct PlanAndExecuteConfig {
+    /// Maximum number of steps a plan may contain
+    pub max_steps: usize,
+    /// Maximum ReAct turns spent on a single step
+    pub max_step_turns: usize,
+    /// Maximum number of plan revisions after failed steps
+    pub max_replans: usize,
+    /// Number of messages kept in the per-step working memory
+    pub step_memory_window: usize,
+}
+
+impl Default for PlanAndExecuteConfig {
+    fn default() -> Self {
+        Self {
+            max_steps: 10,
+            max_step_turns: 5,
+            max_replans: 2,
+            step_memory_window: 40,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PlanResponse {
+    steps: Vec<String>,
+}
+
+/// ReAct loop used to execute individual steps
+struct StepExecutor;
+
+impl ReActExecutor for StepExecutor {}
+
+/// Executor that plans a task up front and then executes the plan step by step
+#[derive(Debug, Clone, Default)]
+pub struct PlanAndExecute {
+    config: PlanAndExecuteConfig,
+}
+
+impl PlanAndExecute {
+    pub fn new(config: PlanAndExecuteConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn plan_config(&self) -> &PlanAndExecuteConfig {
+        &self.config
+    }
+
+    fn plan_schema() -> StructuredOutputFormat {
+        StructuredOutputFormat {
+            name: "Plan".to_string(),
+            description: Some("An ordered list of steps".to_string()),
+            schema: Some(serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "steps": {
+                        "type": "array",
+                        "items": {"type": "string"}
+                    }
+                },
+                "required": ["steps"],
+                "additionalProperties": false
+            })),
+            strict: Some(true),
+        }
+    }
+
+    fn describe_tools(tools: &[Box<dyn ToolT>]) -> String {
+        if tools.is_empty() {
+            return "No tools are available.".to_string();
+        }
+        tools
+            .iter()
+            .map(|t| format!("- {}: {}", t.name(), t.description()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn describe_results(results: &[StepResult]) -> String {
+        if results.is_empty() {
+            return "None".to_string();
+        }
+        results
+            .iter()
+            .map(|r| {
+                format!(
+                    "{}. {} [{}]: {}",
+                    r.step.index + 1,
+                    r.step.description,
+                    if r.success { "done" } else { "failed" },
+                    r.response
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    async fn request_plan(
+        &self,
+        llm: &Arc<dyn LLMProvider>,
+        agent_config: &AgentConfig,
+        prompt: String,
+    ) -> Result<Vec<String>, PlanAndExecuteError> {
+        let messages = vec![
+            ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: agent_config.description.clone(),
+            },
+            ChatMessage {
+                role: ChatRole::User,
+                message_type: MessageType::Text,
+                content: prompt,
+            },
+        ];
+        let response = llm
+            .chat(&messages, Some(Self::plan_schema()))
+            .await
+            .map_err(|e| PlanAndExecuteError::LLMError(e.to_string()))?;
+        let text = response.text().unwrap_or_default();
+        let plan: PlanResponse = serde_json::from_str(text.trim())
+            .map_err(|e| PlanAndExecuteError::PlanningError(format!("Invalid plan: {e}")))?;
+        Ok(plan
+            .steps
+            .into_iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect())
+    }
+
+    async fn create_plan(
+        &self,
+        llm: &Arc<dyn LLMProvider>,
+        tools: &[Box<dyn ToolT>],
+        agent_config: &AgentConfig,
+        task: &Task,
+    ) -> Result<Vec<PlanStep>, PlanAndExecuteError> {
+        let prompt = format!(
+            "Create a step-by-step plan to accomplish the task below. \
+             Use at most {} short, concrete steps, each achievable with the available tools. \
+             Do not include a step for presenting the final answer.\n\n\
+             Available tools:\n{}\n\nTask: {}",
+            self.config.max_steps,
+            Self::describe_tools(tools),
+            task.prompt
+        );
+        let steps = self.request_plan(llm, agent_config, prompt).await?;
+        if steps.is_empty() {
+            return Err(PlanAndExecuteError::PlanningError(
+                "The plan contains no steps".to_string(),
+            ));
+        }
+        Ok(steps
+            .into_iter()
+            .take(self.config.max_steps)
+            .enumerate()
+            .map(|(index, description)| PlanStep { index, description })
+            .collect())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn replan(
+        &self,
+        llm: &Arc<dyn LLMProvider>,
+        tools: &[Box<dyn ToolT>],
+        agent_config: &AgentConfig,
+        task: &Task,
+        results: &[StepResult],
+        failed: &StepResult,
+        remaining: &[PlanStep],
+    ) -> Result<Vec<PlanStep>, PlanAndExecuteError> {
+        let prompt = format!(
+            "You are revising a plan after a step failed.\n\n\
+             Task: {}\n\nAvailable tools:\n{}\n\n\
+             Completed steps:\n{}\n\n\
+             Failed step: {}\nFailure: {}\n\n\
+             Remaining steps of the current plan:\n{}\n\n\
+             Return the new list of steps that still have to be executed to finish the task \
+             (at most {} steps).",
+            task.prompt,
+            Self::describe_tools(tools),
+            Self::describe_results(results),
+            failed.step.description,
+            failed.response,
+            if remaining.is_empty() {
+                "None".to_string()
+            } else {
+                remaining
+                    .iter()
+                    .map(|s| format!("- {}", s.description))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            },
+            self.config.max_steps.saturating_sub(results.len()).max(1),
+        );
+        let steps = self.request_plan(llm, agent_config, prompt).await?;
+        // Without new steps the failure would go unreported and synthesis would run as if
+        // the task succeeded
+        if steps.is_empty() {
+            return Err(PlanAndExecuteError::PlanningError(format!(
+                "The revised plan contains no steps after step {} failed: {}",
+                failed.step.index, failed.response
+            )));
+        }
+        let offset = results.len();
+        Ok(steps
+            .into_iter()
+            .take(self.config.max_steps.saturating_sub(offset).max(1))
+            .enumerate()
+            .map(|(i, description)| PlanStep {
+                index: offset + i,
+                description,
+            })
+            .collect())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_step(
+        &self,
+        llm: &Arc<dyn LLMProvider>,
+        tools: &[Box<dyn ToolT>],
+        agent_config: &AgentConfig,
+        task: &Task,
+        step: &PlanStep,
+        results: &[StepResult],
+        state: Arc<RwLock<AgentState>>,
+        tx_event: mpsc::Sender<Event>,
+    ) -> Result<StepResult, PlanAndExecuteError> {
+        let step_memory: Arc<RwLock<Box<dyn MemoryProvider>>> = Arc::new(RwLock::new(Box::new(
+            SlidingWindowMemory::new(self.config.step_memory_window.max(1)),
+        )));
+        let _ = step_memory
+            .write()
+            .await
+            .remember(&ChatMessage {
+                role: ChatRole::User,
+                message_type: MessageType::Text,
+                content: format!(
+                    "Overall task: {}\n\nCompleted steps:\n{}\n\nCurrent step: {}\n\n\
+                     Complete only the current step and report its result.",
+                    task.prompt,
+                    Self::describe_results(results),
+                    step.description
+                ),
+            })
+            .await;
+
+        // Steps produce free-form results, the output schema only applies to the final answer
+        let step_config = AgentConfig {
+            name: agent_config.name.clone(),
+            description: agent_config.description.clone(),
+            id: agent_config.id,
+            output_schema: None,
+            checkpoint_store: None,
+        };
+
+        let mut tool_calls = Vec::new();
+        for turn in 0..self.config.max_step_turns {
+            let mut messages = vec![ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: format!(
+                    "{}\n\nYou are executing one step of a larger plan.",
+                    agent_config.description
+                ),
+            }];
+            messages.extend(
+                step_memory
+                    .read()
+                    .await
+                    .recall("", None)
+                    .await
+                    .unwrap_or_default(),
+            );
+
+            tx_event
+                .send(Event::TurnStarted {
+                    sub_id: task.submission_id,
+                    turn_number: turn,
+                    max_turns: self.config.max_step_turns,
+                })
+                .await?;
+            let turn_result = StepExecutor
+                .process_turn(
+                    task.submission_id,
+                    llm.clone(),
+                    &messages,
+                    Some(step_memory.clone()),
+                    tools,
+                    &step_config,
+                    state.clone(),
+                    tx_event.clone(),
+                )
+                .await;
+
+            match turn_result {
+                Ok(TurnResult::Complete(output)) => {
+                    // Calls of the final turn, such as one returning directly
+                    tool_calls.extend(output.tool_calls);
+                    tx_event
+                        .send(Event::TurnCompleted {
+                            sub_id: task.submission_id,
+                            turn_number: turn,
+                            final_turn: true,
+                        })
+                        .await?;
+                    return Ok(StepResult {
+                        step: step.clone(),
+                        success: true,
+                        response: output.response,
+                        tool_calls,
+                    });
+                }
+                Ok(TurnResult::Continue(partial)) => {
+                    if let Some(partial) = partial {
+                        tool_calls.extend(partial.tool_calls);
+                    }
+                    tx_event
+                        .send(Event::TurnCompleted {
+                            sub_id: task.submission_id,
+                            turn_number: turn,
+                            final_turn: false,
+                        })
+                        .await?;
+                }
+                Err(ReActExecutorError::EventError(e)) => return Err(e.into()),
+                Err(e) => {
+                    warn!("Plan step {} failed: {e}", step.index);
+                    return Ok(StepResult {
+                        step: step.clone(),
+                        success: false,
+                        response: e.to_string(),
+                        tool_calls,
+                    });
+                }
+            }
+        }
+
+        Ok(StepResult {
+            step: step.clone(),
+            success: false,
+            response: format!(
+                "Step did not complete within {} turns",
+                self.config.max_step_turns
+            ),
+            tool_calls,
+        })
+    }
+
+    async fn synthesize(
+        &self,
+        llm: &Arc<dyn LLMProvider>,
+        agent_config: &AgentConfig,
+        task: &Task,
+        results: &[StepResult],
+    ) -> Result<String, PlanAndExecuteError> {
+        let messages = vec![
+            ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: agent_config.description.clone(),
+            },
+            ChatMessage {
+                role: ChatRole::User,
+                message_type: MessageType::Text,
+                content: format!(
+                    "Task: {}\n\nThe task was executed as a plan with these results:\n{}\n\n\
+                     Using these results, give the final answer to the task.",
+                    task.prompt,
+                    Self::describe_results(results)
+                ),
+            },
+        ];
+        let response = llm
+            .chat(&messages, agent_config.output_schema.clone())
+            .await
+            .map_err(|e| PlanAndExecuteError::LLMError(e.to_string()))?;
+        Ok(response.text().unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl AgentExecutor for PlanAndExecute {
+    type Output = PlanAndExecuteOutput;
+    type Error = PlanAndExecuteError;
+
+    fn config(&self) -> ExecutorConfig {
+        ExecutorConfig {
+            max_turns: self.config.max_step_turns,
+            ..Default::default()
+        }
+    }
+
+    async fn execute(
+        &self,
+        llm: Arc<dyn LLMProvider>,
+        memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+        tools: Vec<Box<dyn ToolT>>,
+        agent_config: &AgentConfig,
+        task: Task,
+        state: Arc<RwLock<AgentState>>,
+        tx_event: mpsc::Sender<Event>,
+    ) -> Result<Self::Output, Self::Error> {
+        debug!("Starting Plan-and-Execute Executor");
+
+        if let Some(memory) = &memory {
+            let _ = memory
+                .write()
+                .await
+                .remember(&ChatMessage {
+                    role: ChatRole::User,
+                    message_type: MessageType::Text,
+                    content: task.prompt.clone(),
+                })
+                .await;
+        }
+
+        {
+            let mut state = state.write().await;
+            state.record_task(task.clone());
+        }
+
+        tx_event
+            .send(Event::TaskStarted {
+                sub_id: task.submission_id,
+                agent_id: agent_config.id,
+                task_description: task.prompt.clone(),
+            })
+            .await?;
+
+        let mut plan = self.create_plan(&llm, &tools, agent_config, &task).await?;
+        tx_event
+            .send(Event::PlanCreated {
+                sub_id: task.submission_id,
+                revision: 0,
+                steps: plan.iter().map(|s| s.description.clone()).collect(),
+            })
+            .await?;
+
+        let mut results: Vec<StepResult> = Vec::new();
+        let mut replans = 0;
+        let mut next = 0;
+        while next < plan.len() {
+            let step = plan[next].clone();
+            tx_event
+                .send(Event::PlanStepStarted {
+                    sub_id: task.submission_id,
+                    step_index: step.index,
+                    description: step.description.clone(),
+                })
+                .await?;
+
+            let result = self
+                .execute_step(
+                    &llm,
+                    &tools,
+                    agent_config,
+                    &task,
+                    &step,
+                    &results,
+                    state.clone(),
+                    tx_event.clone(),
+                )
+                .await?;
+
+            tx_event
+                .send(Event::PlanStepCompleted {
+                    sub_id: task.submission_id,
+                    step_index: step.index,
+                    success: result.success,
+                    response: result.response.clone(),
+                })
+                .await?;
+
+            if result.success {
+                results.push(result);
+                next += 1;
+                continue;
+            }
+
+            if replans >= self.config.max_replans {
+                return Err(PlanAndExecuteError::StepFailed {
+                    index: step.index,
+                    max_replans: self.config.max_replans,
+                    reason: result.response,
+                });
+            }
+            replans += 1;
+
+            let revised = self
+                .replan(
+                    &llm,
+                    &tools,
+                    agent_config,
+                    &task,
+                    &results,
+                    &result,
+                    &plan[next + 1..],
+                )
+                .await?;
+            plan.truncate(next);
+            plan.extend(revised);
+            tx_event
+                .send(Event::PlanCreated {
+                    sub_id: task.submission_id,
+                    revision: replans,
+                    steps: plan.iter().map(|s| s.description.clone()).collect(),
+                })
+                .await?;
+        }
+
+        let response = self.synthesize(&llm, agent_config, &task, &results).await?;
+
+        if let Some(memory) = &memory {
+            if !response.is_empty() {
+                let _ = memory
+                    .write()
+                    .await
+                    .remember(&ChatMessage {
+                        role: ChatRole::Assistant,
+                        message_type: MessageType::Text,
+                        content: response.clone(),
+                    })
+                    .await;
+            }
+        }
+
+        Ok(PlanAndExecuteOutput {
+            plan,
+            step_results: results,
+            response,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::{ToolCallError, ToolRuntime};
+    use autoagents_test_utils::llm::{tool_call, ScriptedLLMProvider, ScriptedResponse};
+    use uuid::Uuid;
+
+    #[derive(Debug)]
+    struct WeatherTool;
+
+    impl ToolT for WeatherTool {
+        fn name(&self) -> &'static str {
+            "weather"
+        }
+
+        fn description(&self) -> &'static str {
+            "Get the weather for a city"
+        }
+
+        fn args_schema(&self) -> Value {
+            serde_json::json!({
+                "type": "object",
+                "properties": {"city": {"type": "string"}},
+                "required": ["city"]
+            })
+        }
+    }
+
+    impl ToolRuntime for WeatherTool {
+        fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            Ok(format!("Sunny in {}", args["city"].as_str().unwrap_or_default()).into())
+        }
+    }
+
+    /// The weather tool, handing its result straight back
+    #[derive(Debug)]
+    struct ForecastTool;
+
+    impl ToolT for ForecastTool {
+        fn name(&self) -> &'static str {
+            "forecast"
+        }
+
+        fn description(&self) -> &'static str {
+            "Get the forecast for a city"
+        }
+
+        fn args_schema(&self) -> Value {
+            WeatherTool.args_schema()
+        }
+
+        fn returns_directly(&self) -> bool {
+            true
+        }
+    }
+
+    impl ToolRuntime for ForecastTool {
+        fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            WeatherTool.execute(args)
+        }
+    }
+
+    fn agent_config() -> AgentConfig {
+        AgentConfig {
+            name: "planner".to_string(),
+            description: "You are a planner".to_string(),
+            id: Uuid::new_v4(),
+            output_schema: None,
+            checkpoint_store: None,
+        }
+    }
+
+    async fn run(
+        executor: PlanAndExecute,
+        llm: Arc<ScriptedLLMProvider>,
+    ) -> (
+        Result<PlanAndExecuteOutput, PlanAndExecuteError>,
+        Vec<Event>,
+    ) {
+        let (tx, mut rx) = mpsc::channel(100);
+        let result = executor
+            .execute(
+                llm,
+                None,
+                vec![Box::new(WeatherTool)],
+                &agent_config(),
+                Task::new("Weather in Paris?", None),
+                Arc::new(RwLock::new(AgentState::new())),
+                tx,
+            )
+            .await;
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        (result, events)
+    }
+
+    #[tokio::test]
+    async fn test_plan_and_execute_success() {
+        let llm = Arc::new(ScriptedLLMProvider::new(vec![
+            ScriptedResponse::Text(r#"{"steps":["Look up the weather","Summarize"]}"#.into()),
+            ScriptedResponse::ToolCalls(vec![tool_call("1", "weather", r#"{"city":"Paris"}"#)]),
+            ScriptedResponse::Text("Sunny in Paris".into()),
+            ScriptedResponse::Text("It is sunny".into()),
+            ScriptedResponse::Text("The weather in Paris is sunny".into()),
+        ]));
+
+        let (result, events) = run(PlanAndExecute::default(), llm.clone()).await;
+        let output = result.unwrap();
+
+        assert_eq!(output.plan.len(), 2);
+        assert_eq!(output.step_results.len(), 2);
+        assert_eq!(output.step_results[0].tool_calls.len(), 1);
+        assert_eq!(output.step_results[0].response, "Sunny in Paris");
+        assert_eq!(output.response, "The weather in Paris is sunny");
+        assert_eq!(llm.remaining(), 0);
+
+        let step_events = events
+            .iter()
+            .filter(|e| matches!(e, Event::PlanStepCompleted { success: true, .. }))
+            .count();
+        assert_eq!(step_events, 2);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, Event::PlanCreated { revision: 0, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_step_keeps_tool_calls_of_final_turn() {
+        let llm = Arc::new(ScriptedLLMProvider::new(vec![
+            ScriptedResponse::Text(r#"{"steps":["Look up the forecast"]}"#.into()),
+            ScriptedResponse::ToolCalls(vec![tool_call("1", "forecast", r#"{"city":"Paris"}"#)]),
+            ScriptedResponse::Text("The forecast for Paris is sunny".into()),
+        ]));
+        let (tx, _rx) = mpsc::channel(100);
+        let output = PlanAndExecute::default()
+            .execute(
+                llm,
+                None,
+                vec![Box::new(ForecastTool)],
+                &agent_config(),
+                Task::new("Forecast for Paris?", None),
+                Arc::new(RwLock::new(AgentState::new())),
+                tx,
+            )
+            .await
+            .unwrap();
+
+        let step = &output.step_results[0];
+        assert_eq!(step.response, "Sunny in Paris");
+        assert_eq!(step.tool_calls.len(), 1);
+        assert_eq!(step.tool_calls[0].tool_name, "forecast");
+    }
+
+    #[tokio::test]
+    async fn test_plan_and_execute_replans_failed_step() {
+        let executor = PlanAndExecute::new(PlanAndExecuteConfig {
+            max_step_turns: 1,
+            ..Default::default()
+        });
+        let llm = Arc::new(ScriptedLLMProvider::new(vec![
+            ScriptedResponse::Text(r#"{"steps":["Guess the weather"]}"#.into()),
+            ScriptedResponse::ToolCalls(vec![tool_call("1", "weather", r#"{"town":"Paris"}"#)]),
+            ScriptedResponse::Text(r#"{"steps":["Look up the weather for Paris"]}"#.into()),
+            ScriptedResponse::Text("Sunny".into()),
+            ScriptedResponse::Text("Sunny in Paris".into()),
+        ]));
+
+        let (result, events) = run(executor, llm).await;
+        let output = result.unwrap();
+
+        assert_eq!(output.plan.len(), 1);
+        assert_eq!(output.plan[0].description, "Look up the weather for Paris");
+        assert_eq!(output.step_results.len(), 1);
+        assert_eq!(output.response, "Sunny in Paris");
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, Event::PlanStepCompleted { success: false, .. })));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, Event::PlanCreated { revision: 1, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_plan_and_execute_gives_up_after_max_replans() {
+        let executor = PlanAndExecute::new(PlanAndExecuteConfig {
+            max_replans: 0,
+            ..Default::default()
+        });
+        let llm = Arc::new(ScriptedLLMProvider::new(vec![
+            ScriptedResponse::Text(r#"{"steps":["Look up the weather"]}"#.into()),
+            ScriptedResponse::Error("boom".into()),
+        ]));
+
+        let (result, _) = run(executor, llm).await;
+        assert!(matches!(
+            result,
+            Err(PlanAndExecuteError::StepFailed { index: 0, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_plan_and_execute_empty_revision_fails() {
+        let executor = PlanAndExecute::new(PlanAndExecuteConfig {
+            max_step_turns: 1,
+            ..Default::default()
+        });
+        let llm = Arc::new(ScriptedLLMProvider::new(vec![
+            ScriptedResponse::Text(r#"{"steps":["Guess the weather"]}"#.into()),
+            ScriptedResponse::ToolCalls(vec![tool_call("1", "weather", r#"{"town":"Paris"}"#)]),
+            ScriptedResponse::Text(r#"{"steps":[]}"#.into()),
+            ScriptedResponse::Text("Unused synthesis".into()),
+        ]));
+
+        let (result, _) = run(executor, llm.clone()).await;
+        assert!(matches!(
+            result,
+            Err(PlanAndExecuteError::PlanningError(reason)) if reason.contains("step 0 failed")
+        ));
+        assert_eq!(llm.remaining(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_plan_and_execute_invalid_plan() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec!["not a plan"]));
+        let (result, _) = run(PlanAndExecute::default(), llm).await;
+        assert!(matches!(result, Err(PlanAndExecuteError::PlanningError(_))));
+    }
+
+    #[test]
+    fn test_extract_agent_output() {
+        let output = PlanAndExecuteOutput {
+            plan: vec![],
+            step_results: vec![],
+            response: r#"{"value": 4}"#.to_string(),
+        };
+        let value: Value = output.into();
+        let extracted: serde_json::Map<String, Value> =
+            PlanAndExecuteOutput::extract_agent_output(value).unwrap();
+        assert_eq!(extracted["value"], 4);
+    }
+}
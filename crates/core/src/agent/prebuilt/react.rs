@@ -1,14 +1,17 @@
 use crate::agent::base::AgentConfig;
 use crate::agent::executor::{AgentExecutor, ExecutorConfig, TurnResult};
-use crate::agent::runnable::AgentState;
-use crate::memory::MemoryProvider;
-use crate::protocol::Event;
+use crate::agent::output::{validate_output, OutputValidationError};
+use crate::agent::prebuilt::text_protocol::{self, TextStep};
+use crate::agent::state::AgentState;
+use crate::checkpoint::{Checkpoint, CheckpointError};
+use crate::memory::{MemoryProvider, SlidingWindowMemory};
+use crate::protocol::{Event, SubmissionId};
 use crate::runtime::Task;
-use crate::tool::{ToolCallResult, ToolT};
+use crate::tool::{ArgumentsValidationError, ToolCallContext, ToolCallResult, ToolT};
 use async_trait::async_trait;
 use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType, Tool};
 use autoagents_llm::{LLMProvider, ToolCall};
-use log::{debug, error};
+use log::{debug, warn};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::sync::Arc;
@@ -16,6 +19,9 @@ use thiserror::Error;
 use tokio::sync::mpsc::error::SendError;
 use tokio::sync::{mpsc, RwLock};
 
+/// Window of the transcript kept for tasks executed without a memory provider
+const SCRATCH_MEMORY_WINDOW: usize = 100;
+
 /// Output of the ReAct-style agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReActAgentOutput {
@@ -41,6 +47,32 @@ impl ReActAgentOutput {
         serde_json::from_str(&react_output.response)
             .map_err(|e| ReActExecutorError::AgentOutputError(e.to_string()))
     }
+
+    /// Text of an agent result: the response of ReAct outputs, strings as they are and other
+    /// values as JSON
+    pub(crate) fn response_text(value: Value) -> String {
+        match value {
+            Value::String(text) => text,
+            Value::Object(map) => match map.get("response") {
+                Some(Value::String(response)) => response.clone(),
+                _ => Value::Object(map).to_string(),
+            },
+            value => value.to_string(),
+        }
+    }
+}
+
+/// The successful call of a tool returning directly, whose result is the final response
+fn direct_result<'a>(
+    tools: &[Box<dyn ToolT>],
+    results: &'a [ToolCallResult],
+) -> Option<&'a ToolCallResult> {
+    results.iter().find(|result| {
+        result.success
+            && tools
+                .iter()
+                .any(|t| t.name() == result.tool_name && t.returns_directly())
+    })
 }
 
 #[derive(Error, Debug)]
@@ -65,16 +97,34 @@ pub enum ReActExecutorError {
 
     #[error("Extracting Agent Output Error: {0}")]
     AgentOutputError(String),
+
+    #[error("Checkpoint error: {0}")]
+    CheckpointError(#[from] CheckpointError),
+
+    #[error("Agent is stuck repeating calls to {}", tools.join(", "))]
+    LoopDetected { tools: Vec<String> },
+
+    #[error("Output does not match the schema after {attempts} attempts: {source}")]
+    OutputValidationError {
+        attempts: usize,
+        source: OutputValidationError,
+    },
 }
 
 #[async_trait]
 pub trait ReActExecutor: Send + Sync + 'static {
+    /// Configuration of the ReAct loop, override to tune it per agent
+    fn executor_config(&self) -> ExecutorConfig {
+        ExecutorConfig::default()
+    }
+
     async fn process_tool_calls(
         &self,
+        sub_id: SubmissionId,
         tools: &[Box<dyn ToolT>],
         tool_calls: Vec<autoagents_llm::ToolCall>,
         tx_event: mpsc::Sender<Event>,
-        _memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+        memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
     ) -> Vec<ToolCallResult> {
         let mut results = Vec::new();
 
@@ -93,19 +143,51 @@ pub trait ReActExecutor: Send + Sync + 'static {
                         .await;
 
                     match serde_json::from_str::<Value>(&tool_args) {
-                        Ok(parsed_args) => match tool.run(parsed_args) {
-                            Ok(output) => ToolCallResult {
-                                tool_name: tool_name.clone(),
-                                success: true,
-                                arguments: serde_json::from_str(&tool_args).unwrap_or(Value::Null),
-                                result: output,
-                            },
-                            Err(e) => ToolCallResult {
-                                tool_name: tool_name.clone(),
-                                success: false,
-                                arguments: serde_json::from_str(&tool_args).unwrap_or(Value::Null),
-                                result: serde_json::json!({"error": e.to_string()}),
-                            },
+                        Ok(parsed_args) => match tool.validate_args(&parsed_args) {
+                            Err(ArgumentsValidationError::Invalid(violations)) => {
+                                let _ = tx_event
+                                    .send(Event::ToolCallValidationFailed {
+                                        id: call.id.clone(),
+                                        tool_name: tool_name.clone(),
+                                        arguments: tool_args.clone(),
+                                        violations: violations.clone(),
+                                    })
+                                    .await;
+                                results.push(ToolCallResult {
+                                    tool_name: tool_name.clone(),
+                                    success: false,
+                                    arguments: parsed_args,
+                                    result: ArgumentsValidationError::Invalid(violations)
+                                        .to_json(&tool_name),
+                                });
+                                continue;
+                            }
+                            validation => {
+                                if let Err(e) = validation {
+                                    warn!("Skipping argument validation for '{tool_name}': {e}");
+                                }
+                                let context = ToolCallContext {
+                                    sub_id,
+                                    call_id: call.id.clone(),
+                                    tx_event: tx_event.clone(),
+                                    memory: memory.clone(),
+                                    capabilities: None,
+                                };
+                                match tool.call(parsed_args.clone(), context).await {
+                                    Ok(output) => ToolCallResult {
+                                        tool_name: tool_name.clone(),
+                                        success: true,
+                                        arguments: parsed_args,
+                                        result: output,
+                                    },
+                                    Err(e) => ToolCallResult {
+                                        tool_name: tool_name.clone(),
+                                        success: false,
+                                        arguments: parsed_args,
+                                        result: serde_json::json!({"error": e.to_string()}),
+                                    },
+                                }
+                            }
                         },
                         Err(e) => ToolCallResult {
                             tool_name: tool_name.clone(),
@@ -150,6 +232,7 @@ pub trait ReActExecutor: Send + Sync + 'static {
     #[allow(clippy::too_many_arguments)]
     async fn process_turn(
         &self,
+        sub_id: SubmissionId,
         llm: Arc<dyn LLMProvider>,
         messages: &[ChatMessage],
         memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
@@ -158,7 +241,17 @@ pub trait ReActExecutor: Send + Sync + 'static {
         state: Arc<RwLock<AgentState>>,
         tx_event: mpsc::Sender<Event>,
     ) -> Result<TurnResult<ReActAgentOutput>, ReActExecutorError> {
-        let response = if !tools.is_empty() {
+        let text_protocol =
+            !tools.is_empty() && self.executor_config().tool_protocol.uses_text(llm.as_ref());
+        let response = if text_protocol {
+            // The output schema is left out, it would keep the model from writing actions
+            llm.chat(
+                &text_protocol::with_tool_instructions(messages, tools),
+                None,
+            )
+            .await
+            .map_err(|e| ReActExecutorError::LLMError(e.to_string()))?
+        } else if !tools.is_empty() {
             let tools_serialized: Vec<Tool> = tools.iter().map(Tool::from).collect();
             llm.chat_with_tools(
                 messages,
@@ -173,65 +266,59 @@ pub trait ReActExecutor: Send + Sync + 'static {
                 .map_err(|e| ReActExecutorError::LLMError(e.to_string()))?
         };
 
-        let response_text = response.text().unwrap_or_default();
-        if let Some(tool_calls) = response.tool_calls() {
-            let tool_results = self
-                .process_tool_calls(tools, tool_calls.clone(), tx_event.clone(), memory.clone())
-                .await;
-
-            // Store tool calls and results in memory
-            if let Some(mem) = &memory {
-                let mut mem = mem.write().await;
-
-                // Record that assistant is calling tools
-                let _ = mem
-                    .remember(&ChatMessage {
-                        role: ChatRole::Assistant,
-                        message_type: MessageType::ToolUse(tool_calls.clone()),
-                        content: response_text.clone(),
-                    })
-                    .await;
-
-                // Create ToolCall objects with the results for ToolResult message type
-                let mut result_tool_calls = Vec::new();
-                for (tool_call, result) in tool_calls.iter().zip(&tool_results) {
-                    let result_content = if result.success {
-                        match &result.result {
-                            serde_json::Value::String(s) => s.clone(),
-                            other => serde_json::to_string(other).unwrap_or_default(),
-                        }
-                    } else {
-                        serde_json::json!({"error": format!("{:?}", result.result)}).to_string()
-                    };
-
-                    // Create a new ToolCall with the result in the arguments field
-                    result_tool_calls.push(ToolCall {
-                        id: tool_call.id.clone(),
-                        call_type: tool_call.call_type.clone(),
-                        function: autoagents_llm::FunctionCall {
-                            name: tool_call.function.name.clone(),
-                            arguments: result_content,
-                        },
-                    });
+        let mut response_text = response.text().unwrap_or_default();
+        let mut tool_calls = response.tool_calls();
+        if text_protocol {
+            match text_protocol::parse_response(&response_text) {
+                TextStep::Action { name, input } => {
+                    tool_calls = Some(vec![text_protocol::to_tool_call(name, input)]);
+                    response_text = text_protocol::until_observation(&response_text).to_string();
                 }
-
-                // Store tool results using ToolResult message type with Tool role
-                let _ = mem
-                    .remember(&ChatMessage {
-                        role: ChatRole::Tool,
-                        message_type: MessageType::ToolResult(result_tool_calls),
-                        content: String::new(),
-                    })
-                    .await;
+                TextStep::FinalAnswer(answer) => response_text = answer,
             }
-
-            {
-                let mut guard = state.write().await;
-                for result in &tool_results {
-                    guard.record_tool_call(result.clone());
+        }
+        if let Some(tool_calls) = tool_calls {
+            // Save the request so a task interrupted while the tools run can replay them
+            if let Some(store) = &agent_config.checkpoint_store {
+                if let Some(mut checkpoint) = store.load(sub_id).await? {
+                    checkpoint.pending_tool_calls = tool_calls.clone();
+                    checkpoint.pending_response = response_text.clone();
+                    checkpoint.pending_results = Vec::new();
+                    store.save(&checkpoint).await?;
                 }
             }
 
+            let tool_results = run_pending_tool_calls(
+                self,
+                sub_id,
+                tools,
+                &tool_calls,
+                Vec::new(),
+                agent_config,
+                tx_event.clone(),
+                memory.clone(),
+            )
+            .await?;
+
+            record_tool_round(
+                &memory,
+                &state,
+                &response_text,
+                &tool_calls,
+                &tool_results,
+                text_protocol,
+            )
+            .await;
+
+            // A tool returning directly, such as a handoff, provides the final response
+            if let Some(result) = direct_result(tools, &tool_results) {
+                let response = ReActAgentOutput::response_text(result.result.clone());
+                return Ok(TurnResult::Complete(ReActAgentOutput {
+                    response,
+                    tool_calls: tool_results,
+                }));
+            }
+
             // Continue to let the LLM generate a response based on tool results
             Ok(TurnResult::Continue(Some(ReActAgentOutput {
                 response: response_text,
@@ -260,19 +347,399 @@ pub trait ReActExecutor: Send + Sync + 'static {
     }
 }
 
+/// Run the tool calls without a result in `results`, saving every result to the task's
+/// checkpoint once the call completes so a resumed task does not run the call again
+#[allow(clippy::too_many_arguments)]
+async fn run_pending_tool_calls<E: ReActExecutor + ?Sized>(
+    executor: &E,
+    sub_id: SubmissionId,
+    tools: &[Box<dyn ToolT>],
+    tool_calls: &[ToolCall],
+    mut results: Vec<ToolCallResult>,
+    agent_config: &AgentConfig,
+    tx_event: mpsc::Sender<Event>,
+    memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+) -> Result<Vec<ToolCallResult>, ReActExecutorError> {
+    for call in tool_calls.iter().skip(results.len()) {
+        let result = executor
+            .process_tool_calls(
+                sub_id,
+                tools,
+                vec![call.clone()],
+                tx_event.clone(),
+                memory.clone(),
+            )
+            .await;
+        results.extend(result);
+        if let Some(store) = &agent_config.checkpoint_store {
+            if let Some(mut checkpoint) = store.load(sub_id).await? {
+                checkpoint.pending_results = results.clone();
+                store.save(&checkpoint).await?;
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// Record a round of tool calls and their results in memory and state
+///
+/// Rounds of the text protocol are recorded as plain text, the provider does not understand
+/// tool messages.
+async fn record_tool_round(
+    memory: &Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+    state: &Arc<RwLock<AgentState>>,
+    response_text: &str,
+    tool_calls: &[ToolCall],
+    tool_results: &[ToolCallResult],
+    text_protocol: bool,
+) {
+    if let (Some(mem), true) = (memory, text_protocol) {
+        let mut mem = mem.write().await;
+        let _ = mem
+            .remember(&ChatMessage {
+                role: ChatRole::Assistant,
+                message_type: MessageType::Text,
+                content: response_text.to_string(),
+            })
+            .await;
+        let _ = mem
+            .remember(&text_protocol::observation(tool_results))
+            .await;
+    } else if let Some(mem) = memory {
+        // Store tool calls and results in memory
+        let mut mem = mem.write().await;
+
+        // Record that assistant is calling tools
+        let _ = mem
+            .remember(&ChatMessage {
+                role: ChatRole::Assistant,
+                message_type: MessageType::ToolUse(tool_calls.to_vec()),
+                content: response_text.to_string(),
+            })
+            .await;
+
+        // Create ToolCall objects with the results for ToolResult message type
+        let mut result_tool_calls = Vec::new();
+        for (tool_call, result) in tool_calls.iter().zip(tool_results) {
+            let result_content = match &result.result {
+                serde_json::Value::String(s) => s.clone(),
+                other => serde_json::to_string(other).unwrap_or_default(),
+            };
+
+            // Create a new ToolCall with the result in the arguments field
+            result_tool_calls.push(ToolCall {
+                id: tool_call.id.clone(),
+                call_type: tool_call.call_type.clone(),
+                function: autoagents_llm::FunctionCall {
+                    name: tool_call.function.name.clone(),
+                    arguments: result_content,
+                },
+            });
+        }
+
+        // Store tool results using ToolResult message type with Tool role
+        let _ = mem
+            .remember(&ChatMessage {
+                role: ChatRole::Tool,
+                message_type: MessageType::ToolResult(result_tool_calls),
+                content: String::new(),
+            })
+            .await;
+    }
+
+    let mut guard = state.write().await;
+    for result in tool_results {
+        guard.record_tool_call(result.clone());
+    }
+}
+
+/// Memory of a task, a scratch memory when the agent has no memory provider
+fn task_memory(
+    memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+) -> Option<Arc<RwLock<Box<dyn MemoryProvider>>>> {
+    // Without a memory provider the loop still needs a transcript of the task,
+    // tool results and repair prompts, so keep one for the duration of the task
+    memory.or_else(|| {
+        let scratch: Box<dyn MemoryProvider> =
+            Box::new(SlidingWindowMemory::new(SCRATCH_MEMORY_WINDOW));
+        Some(Arc::new(RwLock::new(scratch)))
+    })
+}
+
+/// Progress of the ReAct loop, restored from a checkpoint when a task is resumed
+#[derive(Default)]
+struct LoopProgress {
+    turn: usize,
+    tool_calls: Vec<ToolCallResult>,
+    response: String,
+}
+
+/// Save the progress of a task, if the agent checkpoints its tasks
+async fn save_checkpoint(
+    agent_config: &AgentConfig,
+    task: &Task,
+    memory: &Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+    progress: &LoopProgress,
+    tx_event: &mpsc::Sender<Event>,
+) -> Result<(), ReActExecutorError> {
+    let Some(store) = &agent_config.checkpoint_store else {
+        return Ok(());
+    };
+    let mut checkpoint = Checkpoint::new(task.clone(), agent_config.name.clone());
+    checkpoint.turn = progress.turn;
+    checkpoint.tool_calls = progress.tool_calls.clone();
+    checkpoint.response = progress.response.clone();
+    if let Some(memory) = memory {
+        checkpoint.messages = memory
+            .read()
+            .await
+            .recall("", None)
+            .await
+            .unwrap_or_default();
+    }
+    store.save(&checkpoint).await?;
+    tx_event
+        .send(Event::CheckpointSaved {
+            sub_id: task.submission_id,
+            turn: progress.turn,
+        })
+        .await?;
+    Ok(())
+}
+
+/// Run the ReAct loop from `progress` until the task completes or turns run out
+#[allow(clippy::too_many_arguments)]
+async fn react_loop<E: ReActExecutor + ?Sized>(
+    executor: &E,
+    llm: Arc<dyn LLMProvider>,
+    memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+    tools: &[Box<dyn ToolT>],
+    agent_config: &AgentConfig,
+    task: &Task,
+    state: Arc<RwLock<AgentState>>,
+    tx_event: mpsc::Sender<Event>,
+    mut progress: LoopProgress,
+) -> Result<ReActAgentOutput, ReActExecutorError> {
+    let config = executor.executor_config();
+    let max_turns = config.max_turns;
+    let mut repair_attempts = 0;
+    let mut output_error: Option<OutputValidationError> = None;
+    // Calls before the last correction don't count towards the next loop
+    let mut loop_start = progress.tool_calls.len();
+
+    for turn in progress.turn..max_turns {
+        //Prepare messages with memory
+        let mut messages = vec![ChatMessage {
+            role: ChatRole::System,
+            message_type: MessageType::Text,
+            content: agent_config.description.clone(),
+        }];
+        if let Some(memory) = &memory {
+            // Fetch All previous messsages and extend
+            messages.extend(
+                memory
+                    .read()
+                    .await
+                    .recall("", None)
+                    .await
+                    .unwrap_or_default(),
+            );
+        }
+
+        tx_event
+            .send(Event::TurnStarted {
+                sub_id: task.submission_id,
+                turn_number: turn,
+                max_turns,
+            })
+            .await?;
+        match executor
+            .process_turn(
+                task.submission_id,
+                llm.clone(),
+                &messages,
+                memory.clone(),
+                tools,
+                agent_config,
+                state.clone(),
+                tx_event.clone(),
+            )
+            .await?
+        {
+            TurnResult::Complete(result) => {
+                // A result returned directly is not the model's to repair
+                let direct = direct_result(tools, &result.tool_calls).is_some();
+                if let Some(format) = agent_config.output_schema.as_ref().filter(|_| !direct) {
+                    if let Err(e) = validate_output(format, &result.response) {
+                        tx_event
+                            .send(Event::OutputValidationFailed {
+                                sub_id: task.submission_id,
+                                attempt: repair_attempts + 1,
+                                error: e.to_string(),
+                            })
+                            .await?;
+                        if repair_attempts >= config.output_repair_attempts {
+                            return Err(ReActExecutorError::OutputValidationError {
+                                attempts: repair_attempts + 1,
+                                source: e,
+                            });
+                        }
+                        repair_attempts += 1;
+                        if let Some(memory) = &memory {
+                            let _ = memory
+                                .write()
+                                .await
+                                .remember(&ChatMessage {
+                                    role: ChatRole::User,
+                                    message_type: MessageType::Text,
+                                    content: e.repair_prompt(),
+                                })
+                                .await;
+                        }
+                        output_error = Some(e);
+                        progress.turn = turn + 1;
+                        save_checkpoint(agent_config, task, &memory, &progress, &tx_event).await?;
+                        tx_event
+                            .send(Event::TurnCompleted {
+                                sub_id: task.submission_id,
+                                turn_number: turn,
+                                final_turn: false,
+                            })
+                            .await?;
+                        continue;
+                    }
+                }
+                tx_event
+                    .send(Event::TurnCompleted {
+                        sub_id: task.submission_id,
+                        turn_number: turn,
+                        final_turn: true,
+                    })
+                    .await?;
+                // If we have accumulated tool calls, merge them with the final result
+                if !progress.tool_calls.is_empty() {
+                    progress.tool_calls.extend(result.tool_calls);
+                    return Ok(ReActAgentOutput {
+                        response: result.response,
+                        tool_calls: progress.tool_calls,
+                    });
+                }
+                return Ok(result);
+            }
+            TurnResult::Continue(partial_result) => {
+                // Accumulate tool calls and continue for final response
+                if let Some(partial_result) = partial_result {
+                    progress.tool_calls.extend(partial_result.tool_calls);
+                    if !partial_result.response.is_empty() {
+                        progress.response = partial_result.response;
+                    }
+                }
+                if let Some(detected) = config
+                    .loop_detection
+                    .detect(&progress.tool_calls[loop_start..])
+                {
+                    tx_event
+                        .send(Event::ToolLoopDetected {
+                            sub_id: task.submission_id,
+                            tools: detected.tools.clone(),
+                            repeats: detected.repeats,
+                        })
+                        .await?;
+                    if config.loop_detection.abort {
+                        return Err(ReActExecutorError::LoopDetected {
+                            tools: detected.tools,
+                        });
+                    }
+                    if let Some(memory) = &memory {
+                        let _ = memory
+                            .write()
+                            .await
+                            .remember(&ChatMessage {
+                                role: ChatRole::User,
+                                message_type: MessageType::Text,
+                                content: detected.correction(),
+                            })
+                            .await;
+                    }
+                    loop_start = progress.tool_calls.len();
+                }
+                progress.turn = turn + 1;
+                save_checkpoint(agent_config, task, &memory, &progress, &tx_event).await?;
+                tx_event
+                    .send(Event::TurnCompleted {
+                        sub_id: task.submission_id,
+                        turn_number: turn,
+                        final_turn: false,
+                    })
+                    .await?;
+                continue;
+            }
+        }
+    }
+
+    // Turns ran out while the model was still repairing its output
+    if let Some(e) = output_error {
+        return Err(ReActExecutorError::OutputValidationError {
+            attempts: repair_attempts,
+            source: e,
+        });
+    }
+
+    // If we've exhausted turns but have results, return what we have
+    if !progress.response.is_empty() || !progress.tool_calls.is_empty() {
+        Ok(ReActAgentOutput {
+            response: progress.response,
+            tool_calls: progress.tool_calls,
+        })
+    } else {
+        Err(ReActExecutorError::MaxTurnsExceeded { max_turns })
+    }
+}
+
+/// Run the ReAct loop and drop the task's checkpoint once it completes
+#[allow(clippy::too_many_arguments)]
+async fn run_to_completion<E: ReActExecutor + ?Sized>(
+    executor: &E,
+    llm: Arc<dyn LLMProvider>,
+    memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+    tools: &[Box<dyn ToolT>],
+    agent_config: &AgentConfig,
+    task: &Task,
+    state: Arc<RwLock<AgentState>>,
+    tx_event: mpsc::Sender<Event>,
+    progress: LoopProgress,
+) -> Result<ReActAgentOutput, ReActExecutorError> {
+    let output = react_loop(
+        executor,
+        llm,
+        memory,
+        tools,
+        agent_config,
+        task,
+        state,
+        tx_event,
+        progress,
+    )
+    .await?;
+    if let Some(store) = &agent_config.checkpoint_store {
+        store.delete(task.submission_id).await?;
+    }
+    Ok(output)
+}
+
 #[async_trait]
 impl<T: ReActExecutor> AgentExecutor for T {
     type Output = ReActAgentOutput;
     type Error = ReActExecutorError;
 
     fn config(&self) -> ExecutorConfig {
-        ExecutorConfig { max_turns: 10 }
+        self.executor_config()
     }
 
     async fn execute(
         &self,
         llm: Arc<dyn LLMProvider>,
-        mut memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+        memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
         tools: Vec<Box<dyn ToolT>>,
         agent_config: &AgentConfig,
         task: Task,
@@ -280,18 +747,13 @@ impl<T: ReActExecutor> AgentExecutor for T {
         tx_event: mpsc::Sender<Event>,
     ) -> Result<Self::Output, Self::Error> {
         debug!("Starting ReAct Executor");
-        let max_turns = self.config().max_turns;
-        let mut accumulated_tool_calls = Vec::new();
-        let mut final_response = String::new();
+        let memory = task_memory(memory);
 
-        if let Some(memory) = &mut memory {
+        if let Some(memory) = &memory {
             let mut mem = memory.write().await;
-            let chat_msg = ChatMessage {
-                role: ChatRole::User,
-                message_type: MessageType::Text,
-                content: task.prompt.clone(),
-            };
-            let _ = mem.remember(&chat_msg).await;
+            for chat_msg in task.messages() {
+                let _ = mem.remember(&chat_msg).await;
+            }
         }
 
         // Record the task in state
@@ -304,111 +766,134 @@ impl<T: ReActExecutor> AgentExecutor for T {
             .send(Event::TaskStarted {
                 sub_id: task.submission_id,
                 agent_id: agent_config.id,
-                task_description: task.prompt,
+                task_description: task.prompt.clone(),
             })
             .await?;
 
-        for turn in 0..max_turns {
-            //Prepare messages with memory
-            let mut messages = vec![ChatMessage {
-                role: ChatRole::System,
-                message_type: MessageType::Text,
-                content: agent_config.description.clone(),
-            }];
-            if let Some(memory) = &memory {
-                // Fetch All previous messsages and extend
-                messages.extend(
-                    memory
-                        .read()
-                        .await
-                        .recall("", None)
-                        .await
-                        .unwrap_or_default(),
-                );
+        // A task that dies during its first turn is resumable as well
+        let progress = LoopProgress::default();
+        save_checkpoint(agent_config, &task, &memory, &progress, &tx_event).await?;
+
+        run_to_completion(
+            self,
+            llm,
+            memory,
+            &tools,
+            agent_config,
+            &task,
+            state,
+            tx_event,
+            progress,
+        )
+        .await
+    }
+
+    async fn resume(
+        &self,
+        llm: Arc<dyn LLMProvider>,
+        memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+        tools: Vec<Box<dyn ToolT>>,
+        agent_config: &AgentConfig,
+        checkpoint: Checkpoint,
+        state: Arc<RwLock<AgentState>>,
+        tx_event: mpsc::Sender<Event>,
+    ) -> Result<Self::Output, Self::Error> {
+        debug!("Resuming ReAct Executor at turn {}", checkpoint.turn);
+        let memory = task_memory(memory);
+        let task = checkpoint.task;
+
+        // The checkpoint holds the whole transcript, replace whatever the memory has now
+        if let Some(memory) = &memory {
+            let mut mem = memory.write().await;
+            let _ = mem.clear().await;
+            for message in &checkpoint.messages {
+                let _ = mem.remember(message).await;
             }
+        }
 
-            tx_event
-                .send(Event::TurnStarted {
-                    turn_number: turn,
-                    max_turns,
-                })
-                .await?;
-            match self
-                .process_turn(
-                    llm.clone(),
-                    &messages,
-                    memory.clone(),
-                    &tools,
-                    agent_config,
-                    state.clone(),
-                    tx_event.clone(),
-                )
-                .await?
-            {
-                TurnResult::Complete(result) => {
-                    // If we have accumulated tool calls, merge them with the final result
-                    if !accumulated_tool_calls.is_empty() {
-                        tx_event
-                            .send(Event::TurnCompleted {
-                                turn_number: turn,
-                                final_turn: true,
-                            })
-                            .await?;
-                        return Ok(ReActAgentOutput {
-                            response: result.response,
-                            tool_calls: accumulated_tool_calls,
-                        });
-                    }
-                    tx_event
-                        .send(Event::TurnCompleted {
-                            turn_number: turn,
-                            final_turn: true,
-                        })
-                        .await?;
-                    return Ok(result);
-                }
-                TurnResult::Continue(Some(partial_result)) => {
-                    // Accumulate tool calls and continue for final response
-                    accumulated_tool_calls.extend(partial_result.tool_calls);
-                    if !partial_result.response.is_empty() {
-                        final_response = partial_result.response;
-                    }
-                    tx_event
-                        .send(Event::TurnCompleted {
-                            turn_number: turn,
-                            final_turn: false,
-                        })
-                        .await?;
-                    continue;
-                }
-                TurnResult::Continue(None) => {
-                    tx_event
-                        .send(Event::TurnCompleted {
-                            turn_number: turn,
-                            final_turn: false,
-                        })
-                        .await?;
-                    continue;
-                }
+        {
+            let mut state = state.write().await;
+            state.record_task(task.clone());
+            for result in &checkpoint.tool_calls {
+                state.record_tool_call(result.clone());
             }
         }
 
-        // If we've exhausted turns but have results, return what we have
-        if !final_response.is_empty() || !accumulated_tool_calls.is_empty() {
-            Ok(ReActAgentOutput {
-                response: final_response,
-                tool_calls: accumulated_tool_calls,
+        tx_event
+            .send(Event::TaskResumed {
+                sub_id: task.submission_id,
+                agent_id: agent_config.id,
+                turn: checkpoint.turn,
             })
-        } else {
-            Err(ReActExecutorError::MaxTurnsExceeded { max_turns })
+            .await?;
+
+        let mut progress = LoopProgress {
+            turn: checkpoint.turn,
+            tool_calls: checkpoint.tool_calls,
+            response: checkpoint.response,
+        };
+
+        // The task stopped while tools were running, run the calls that did not complete.
+        // A call interrupted while running is run again.
+        if !checkpoint.pending_tool_calls.is_empty() {
+            let results = run_pending_tool_calls(
+                self,
+                task.submission_id,
+                &tools,
+                &checkpoint.pending_tool_calls,
+                checkpoint.pending_results,
+                agent_config,
+                tx_event.clone(),
+                memory.clone(),
+            )
+            .await?;
+            record_tool_round(
+                &memory,
+                &state,
+                &checkpoint.pending_response,
+                &checkpoint.pending_tool_calls,
+                &results,
+                self.executor_config().tool_protocol.uses_text(llm.as_ref()),
+            )
+            .await;
+            progress.tool_calls.extend(results);
+            if !checkpoint.pending_response.is_empty() {
+                progress.response = checkpoint.pending_response;
+            }
+            progress.turn += 1;
+            save_checkpoint(agent_config, &task, &memory, &progress, &tx_event).await?;
         }
+
+        run_to_completion(
+            self,
+            llm,
+            memory,
+            &tools,
+            agent_config,
+            &task,
+            state,
+            tx_event,
+            progress,
+        )
+        .await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::LoopDetection;
+    use crate::checkpoint::{CheckpointStore, InMemoryCheckpointStore};
+    use crate::memory::SlidingWindowMemory;
+    use crate::runtime::Attachment;
+    use crate::tool::{ToolCallError, ToolRuntime};
+    use autoagents_llm::{
+        chat::{ImageMime, StructuredOutputFormat},
+        FunctionCall,
+    };
+    use autoagents_test_utils::llm::{ScriptedLLMProvider, ScriptedResponse};
     use serde::{Deserialize, Serialize};
+    use uuid::Uuid;
 
     #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
     struct TestAgentOutput {
@@ -433,4 +918,577 @@ mod tests {
             ReActAgentOutput::extract_agent_output(react_value).unwrap();
         assert_eq!(extracted, agent_output);
     }
+
+    #[derive(Debug)]
+    struct EchoTool;
+
+    impl ToolT for EchoTool {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        fn description(&self) -> &'static str {
+            "Echo the given text"
+        }
+
+        fn args_schema(&self) -> Value {
+            serde_json::json!({
+                "type": "object",
+                "properties": {"text": {"type": "string"}},
+                "required": ["text"]
+            })
+        }
+    }
+
+    impl ToolRuntime for EchoTool {
+        fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            Ok(args["text"].clone())
+        }
+    }
+
+    struct TestExecutor;
+
+    impl ReActExecutor for TestExecutor {}
+
+    fn tool_call(arguments: &str) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "echo".to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_tool_calls_valid_arguments() {
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(EchoTool)];
+        let (tx, mut rx) = mpsc::channel(10);
+
+        let results = TestExecutor
+            .process_tool_calls(
+                Uuid::new_v4(),
+                &tools,
+                vec![tool_call(r#"{"text":"hi"}"#)],
+                tx,
+                None,
+            )
+            .await;
+
+        assert!(results[0].success);
+        assert_eq!(results[0].result, "hi");
+        assert!(matches!(
+            rx.recv().await,
+            Some(Event::ToolCallRequested { .. })
+        ));
+        assert!(matches!(
+            rx.recv().await,
+            Some(Event::ToolCallCompleted { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_process_tool_calls_invalid_arguments() {
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(EchoTool)];
+        let (tx, mut rx) = mpsc::channel(10);
+
+        let results = TestExecutor
+            .process_tool_calls(
+                Uuid::new_v4(),
+                &tools,
+                vec![tool_call(r#"{"text":1}"#)],
+                tx,
+                None,
+            )
+            .await;
+
+        assert!(!results[0].success);
+        assert_eq!(results[0].result["violations"][0]["path"], "/text");
+        assert!(matches!(
+            rx.recv().await,
+            Some(Event::ToolCallRequested { .. })
+        ));
+        match rx.recv().await {
+            Some(Event::ToolCallValidationFailed {
+                tool_name,
+                violations,
+                ..
+            }) => {
+                assert_eq!(tool_name, "echo");
+                assert_eq!(violations.len(), 1);
+            }
+            other => panic!("Expected ToolCallValidationFailed, got {other:?}"),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_text_protocol_without_native_tool_calls() {
+        let llm = Arc::new(
+            ScriptedLLMProvider::with_texts(vec![
+                "Thought: I should echo\nAction: echo\nAction Input: {\"text\": \"hi\"}\n\
+                 Observation: made up",
+                "Thought: I know the answer\nFinal Answer: It said hi",
+            ])
+            .without_tool_calls(),
+        );
+        let (tx, mut rx) = mpsc::channel(100);
+        let output = TestExecutor
+            .execute(
+                llm.clone(),
+                None,
+                vec![Box::new(EchoTool)],
+                &checkpoint_config(Arc::new(InMemoryCheckpointStore::new())),
+                Task::new("Echo hi", None),
+                Arc::new(RwLock::new(AgentState::new())),
+                tx,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(output.response, "It said hi");
+        assert_eq!(output.tool_calls.len(), 1);
+        assert!(output.tool_calls[0].success);
+        assert_eq!(output.tool_calls[0].result, "hi");
+
+        let requests = llm.requests();
+        assert!(requests[0][0].content.starts_with("Test agent\n\n"));
+        assert!(requests[0][0]
+            .content
+            .contains("- echo: Echo the given text"));
+        let round = &requests[1][2..];
+        assert_eq!(round[0].role, ChatRole::Assistant);
+        assert!(round[0]
+            .content
+            .ends_with(r#"Action Input: {"text": "hi"}"#));
+        assert_eq!(round[1].role, ChatRole::User);
+        assert_eq!(round[1].content, "Observation: hi");
+        assert!(round.iter().all(|m| m.message_type == MessageType::Text));
+
+        let mut completed = false;
+        while let Ok(event) = rx.try_recv() {
+            if let Event::ToolCallCompleted { tool_name, .. } = event {
+                completed = tool_name == "echo";
+            }
+        }
+        assert!(completed);
+    }
+
+    fn echo_loop_llm(repeats: usize) -> Arc<ScriptedLLMProvider> {
+        let mut responses: Vec<ScriptedResponse> = (0..repeats)
+            .map(|_| ScriptedResponse::ToolCalls(vec![tool_call(r#"{"text":"again"}"#)]))
+            .collect();
+        responses.push(ScriptedResponse::Text("Stopped".to_string()));
+        Arc::new(ScriptedLLMProvider::new(responses))
+    }
+
+    #[tokio::test]
+    async fn test_repeated_tool_calls_get_a_correction() {
+        let llm = echo_loop_llm(3);
+        let (tx, mut rx) = mpsc::channel(100);
+        let output = TestExecutor
+            .execute(
+                llm.clone(),
+                None,
+                vec![Box::new(EchoTool)],
+                &checkpoint_config(Arc::new(InMemoryCheckpointStore::new())),
+                Task::new("Echo", None),
+                Arc::new(RwLock::new(AgentState::new())),
+                tx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.response, "Stopped");
+        assert_eq!(output.tool_calls.len(), 3);
+
+        let last_request = llm.requests().pop().unwrap();
+        let correction = last_request.last().unwrap();
+        assert_eq!(correction.role, ChatRole::User);
+        assert!(correction
+            .content
+            .starts_with("You called `echo` with the same arguments 3 times in a row"));
+        let mut detected = vec![];
+        while let Ok(event) = rx.try_recv() {
+            if let Event::ToolLoopDetected { tools, repeats, .. } = event {
+                detected.push((tools, repeats));
+            }
+        }
+        assert_eq!(detected, vec![(vec!["echo".to_string()], 3)]);
+    }
+
+    struct AbortOnLoopExecutor;
+
+    impl ReActExecutor for AbortOnLoopExecutor {
+        fn executor_config(&self) -> ExecutorConfig {
+            ExecutorConfig {
+                loop_detection: LoopDetection {
+                    max_repeats: 2,
+                    abort: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_tool_calls_abort() {
+        let llm = echo_loop_llm(3);
+        let (tx, _rx) = mpsc::channel(100);
+        let result = AbortOnLoopExecutor
+            .execute(
+                llm.clone(),
+                None,
+                vec![Box::new(EchoTool)],
+                &checkpoint_config(Arc::new(InMemoryCheckpointStore::new())),
+                Task::new("Echo", None),
+                Arc::new(RwLock::new(AgentState::new())),
+                tx,
+            )
+            .await;
+        match result {
+            Err(ReActExecutorError::LoopDetected { tools }) => assert_eq!(tools, vec!["echo"]),
+            other => panic!("Expected LoopDetected, got {other:?}"),
+        }
+        assert_eq!(llm.remaining(), 2);
+    }
+
+    fn schema_config() -> AgentConfig {
+        AgentConfig {
+            name: "test_agent".to_string(),
+            description: "Test agent".to_string(),
+            id: Uuid::new_v4(),
+            output_schema: Some(StructuredOutputFormat {
+                name: "TestAgentOutput".to_string(),
+                description: None,
+                schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "value": {"type": "integer"},
+                        "message": {"type": "string"}
+                    },
+                    "required": ["value", "message"]
+                })),
+                strict: Some(true),
+            }),
+            checkpoint_store: None,
+        }
+    }
+
+    async fn run_with_responses(
+        responses: Vec<&str>,
+    ) -> (
+        Result<ReActAgentOutput, ReActExecutorError>,
+        Arc<ScriptedLLMProvider>,
+        Vec<Event>,
+    ) {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(
+            responses.into_iter().map(String::from).collect(),
+        ));
+        let (tx, mut rx) = mpsc::channel(100);
+        let result = TestExecutor
+            .execute(
+                llm.clone(),
+                None,
+                vec![],
+                &schema_config(),
+                Task::new("Give me a value", None),
+                Arc::new(RwLock::new(AgentState::new())),
+                tx,
+            )
+            .await;
+        let mut events = vec![];
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        (result, llm, events)
+    }
+
+    #[tokio::test]
+    async fn test_execute_repairs_invalid_output() {
+        let (result, llm, events) =
+            run_with_responses(vec!["not json", r#"{"value": 1, "message": "ok"}"#]).await;
+
+        let output: TestAgentOutput =
+            ReActAgentOutput::extract_agent_output(result.unwrap().into()).unwrap();
+        assert_eq!(output.value, 1);
+
+        let requests = llm.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0]
+            .iter()
+            .any(|m| m.content.contains("Give me a value")));
+        assert!(requests[1]
+            .iter()
+            .any(|m| m.role == ChatRole::User && m.content.contains("not valid JSON")));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, Event::OutputValidationFailed { attempt: 1, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_execute_gives_up_after_repair_attempts() {
+        let (result, llm, events) = run_with_responses(vec![
+            r#"{"value": "one"}"#,
+            r#"{"value": "one"}"#,
+            r#"{"value": "one"}"#,
+        ])
+        .await;
+
+        match result {
+            Err(ReActExecutorError::OutputValidationError { attempts, source }) => {
+                assert_eq!(attempts, 3);
+                assert!(matches!(source, OutputValidationError::SchemaMismatch(_)));
+            }
+            other => panic!("Expected OutputValidationError, got {other:?}"),
+        }
+        assert_eq!(llm.remaining(), 0);
+        let failures = events
+            .iter()
+            .filter(|e| matches!(e, Event::OutputValidationFailed { .. }))
+            .count();
+        assert_eq!(failures, 3);
+    }
+
+    #[tokio::test]
+    async fn test_execute_sends_attachments_and_remembers_them() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec!["A red car"]));
+        let memory: Arc<RwLock<Box<dyn MemoryProvider>>> =
+            Arc::new(RwLock::new(Box::new(SlidingWindowMemory::new(10))));
+        let task = Task::new("What is in the picture?", None)
+            .with_attachment(Attachment::image(ImageMime::PNG, vec![0x89, 0x50]))
+            .with_attachment(Attachment::file("notes.txt", "text/plain", "Taken at noon"));
+        let (tx, _rx) = mpsc::channel(100);
+        TestExecutor
+            .execute(
+                llm.clone(),
+                Some(memory.clone()),
+                vec![],
+                &checkpoint_config(Arc::new(InMemoryCheckpointStore::new())),
+                task,
+                Arc::new(RwLock::new(AgentState::new())),
+                tx,
+            )
+            .await
+            .unwrap();
+
+        let request = &llm.requests()[0];
+        assert_eq!(request[1].content, "What is in the picture?");
+        assert_eq!(
+            request[2].message_type,
+            MessageType::Image((ImageMime::PNG, vec![0x89, 0x50]))
+        );
+        assert_eq!(
+            request[3].content,
+            "File notes.txt (text/plain):\nTaken at noon"
+        );
+        assert!(request[1..].iter().all(|m| m.role == ChatRole::User));
+
+        let remembered = memory.read().await.recall("", None).await.unwrap();
+        assert_eq!(remembered[1].message_type, request[2].message_type);
+        assert_eq!(remembered[2].content, request[3].content);
+    }
+
+    fn checkpoint_config(store: Arc<InMemoryCheckpointStore>) -> AgentConfig {
+        AgentConfig {
+            name: "test_agent".to_string(),
+            description: "Test agent".to_string(),
+            id: Uuid::new_v4(),
+            output_schema: None,
+            checkpoint_store: Some(store),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_checkpoints_and_resumes_after_failure() {
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let config = checkpoint_config(store.clone());
+        let tools: Vec<Box<dyn ToolT>> = vec![Box::new(EchoTool)];
+        let llm = Arc::new(ScriptedLLMProvider::new(vec![
+            ScriptedResponse::ToolCalls(vec![tool_call(r#"{"text":"first"}"#)]),
+            ScriptedResponse::Error("connection reset".to_string()),
+        ]));
+        let task = Task::new("Echo first", None);
+        let sub_id = task.submission_id;
+        let (tx, mut rx) = mpsc::channel(100);
+        let result = TestExecutor
+            .execute(
+                llm,
+                None,
+                tools,
+                &config,
+                task,
+                Arc::new(RwLock::new(AgentState::new())),
+                tx,
+            )
+            .await;
+        assert!(matches!(result, Err(ReActExecutorError::LLMError(_))));
+        let mut saved = vec![];
+        while let Ok(event) = rx.try_recv() {
+            if let Event::CheckpointSaved { turn, .. } = event {
+                saved.push(turn);
+            }
+        }
+        assert_eq!(saved, vec![0, 1]);
+
+        let checkpoint = store.load(sub_id).await.unwrap().unwrap();
+        assert_eq!(checkpoint.turn, 1);
+        assert_eq!(checkpoint.tool_calls.len(), 1);
+        assert_eq!(checkpoint.messages.len(), 3);
+        assert!(checkpoint.pending_tool_calls.is_empty());
+
+        // A restarted process continues with fresh memory and state
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec!["Echoed".to_string()]));
+        let (tx, mut rx) = mpsc::channel(100);
+        let output = TestExecutor
+            .resume(
+                llm.clone(),
+                Some(Arc::new(RwLock::new(Box::new(SlidingWindowMemory::new(
+                    10,
+                ))))),
+                vec![Box::new(EchoTool)],
+                &config,
+                checkpoint,
+                Arc::new(RwLock::new(AgentState::new())),
+                tx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.response, "Echoed");
+        assert_eq!(output.tool_calls.len(), 1);
+        // System prompt, task, tool use and tool result
+        assert_eq!(llm.requests()[0].len(), 4);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(Event::TaskResumed {
+                sub_id: id,
+                turn: 1,
+                ..
+            }) if id == sub_id
+        ));
+        while let Ok(event) = rx.try_recv() {
+            if let Event::TurnStarted { turn_number, .. } = event {
+                assert_eq!(turn_number, 1);
+            }
+        }
+        assert!(store.load(sub_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resume_replays_pending_tool_calls() {
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let mut checkpoint = Checkpoint::new(Task::new("Echo pending", None), "test_agent");
+        checkpoint.messages.push(ChatMessage {
+            role: ChatRole::User,
+            message_type: MessageType::Text,
+            content: "Echo pending".to_string(),
+        });
+        checkpoint.pending_tool_calls = vec![tool_call(r#"{"text":"pending"}"#)];
+        checkpoint.pending_response = "Calling echo".to_string();
+        let sub_id = checkpoint.sub_id();
+        store.save(&checkpoint).await.unwrap();
+
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec!["Done".to_string()]));
+        let state = Arc::new(RwLock::new(AgentState::new()));
+        let (tx, _rx) = mpsc::channel(100);
+        let output = TestExecutor
+            .resume(
+                llm.clone(),
+                None,
+                vec![Box::new(EchoTool)],
+                &checkpoint_config(store.clone()),
+                checkpoint,
+                state.clone(),
+                tx,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(output.response, "Done");
+        assert_eq!(output.tool_calls[0].result, "pending");
+        assert_eq!(state.read().await.tool_calls.len(), 1);
+        let request = &llm.requests()[0];
+        assert!(matches!(request[2].message_type, MessageType::ToolUse(_)));
+        assert!(matches!(
+            request[3].message_type,
+            MessageType::ToolResult(_)
+        ));
+        assert!(store.load(sub_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resume_skips_completed_tool_calls() {
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let mut checkpoint = Checkpoint::new(Task::new("Echo twice", None), "test_agent");
+        checkpoint.pending_tool_calls = vec![
+            tool_call(r#"{"text":"first"}"#),
+            tool_call(r#"{"text":"second"}"#),
+        ];
+        checkpoint.pending_results = vec![ToolCallResult {
+            tool_name: "echo".to_string(),
+            success: true,
+            arguments: serde_json::json!({"text": "first"}),
+            result: Value::String("first".to_string()),
+        }];
+        store.save(&checkpoint).await.unwrap();
+
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec!["Done".to_string()]));
+        let (tx, mut rx) = mpsc::channel(100);
+        let output = TestExecutor
+            .resume(
+                llm,
+                None,
+                vec![Box::new(EchoTool)],
+                &checkpoint_config(store),
+                checkpoint,
+                Arc::new(RwLock::new(AgentState::new())),
+                tx,
+            )
+            .await
+            .unwrap();
+
+        let results: Vec<_> = output.tool_calls.iter().map(|r| &r.result).collect();
+        assert_eq!(results, vec!["first", "second"]);
+        let requested: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok())
+            .filter_map(|event| match event {
+                Event::ToolCallRequested { arguments, .. } => Some(arguments),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(requested, vec![r#"{"text":"second"}"#]);
+    }
+
+    #[tokio::test]
+    async fn test_completed_tool_calls_are_checkpointed() {
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let config = checkpoint_config(store.clone());
+        let task = Task::new("Echo", None);
+        let sub_id = task.submission_id;
+        store
+            .save(&Checkpoint::new(task, "test_agent"))
+            .await
+            .unwrap();
+
+        let calls = vec![
+            tool_call(r#"{"text":"first"}"#),
+            tool_call(r#"{"text":"second"}"#),
+        ];
+        let (tx, _rx) = mpsc::channel(100);
+        run_pending_tool_calls(
+            &TestExecutor,
+            sub_id,
+            &[Box::new(EchoTool) as Box<dyn ToolT>],
+            &calls,
+            Vec::new(),
+            &config,
+            tx,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let checkpoint = store.load(sub_id).await.unwrap().unwrap();
+        assert_eq!(checkpoint.pending_results.len(), 2);
+    }
 }
@@ -0,0 +1,710 @@
+//! Reflection (self-critique) executor wrapper.
+//!
+//! [`Reflection`] wraps any [`AgentExecutor`], runs a critic prompt on the draft it
+//! produces and asks the wrapped executor to revise the draft until the critic approves
+//! or the configured number of rounds is reached. Revisions run within the task: they are not
+//! reported or recorded as new tasks. A critic reply that is not a valid verdict fails the
+//! task with [`ReflectionError::CriticError`]. The critic can use a different
+//! [`LLMProvider`] than the agent itself.
+//!
+//! When the wrapped type is an agent, the wrapper is an agent too:
+//!
+//! ```ignore
+//! let agent = Reflection::new(MathAgent {}).with_config(ReflectionConfig {
+//!     max_rounds: 2,
+//!     ..Default::default()
+//! });
+//! AgentBuilder::new(agent).with_llm(llm).runtime(runtime).build().await?;
+//! ```
+use crate::agent::base::{AgentConfig, AgentDeriveT};
+use crate::agent::executor::{AgentExecutor, ExecutorConfig};
+use crate::agent::state::AgentState;
+use crate::memory::{MemoryProvider, MemoryType};
+use crate::protocol::Event;
+use crate::runtime::Task;
+use crate::tool::{SharedTool, ToolT};
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType, StructuredOutputFormat};
+use autoagents_llm::error::LLMError;
+use autoagents_llm::LLMProvider;
+use log::debug;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::sync::mpsc::error::SendError;
+use tokio::sync::{mpsc, RwLock};
+
+pub use crate::agent::state::ReflectionRound;
+
+const DEFAULT_CRITIC_PROMPT: &str = "You are a meticulous reviewer. Check the response for \
+correctness, completeness and clarity with respect to the task. Approve it only if it needs \
+no changes, otherwise give concrete, actionable feedback.";
+
+#[derive(Debug, Deserialize)]
+struct Critique {
+    approved: bool,
+    #[serde(default)]
+    feedback: String,
+}
+
+#[derive(Error, Debug)]
+pub enum ReflectionError<E: std::error::Error + 'static> {
+    #[error(transparent)]
+    Executor(E),
+
+    #[error("Critic error: {0}")]
+    CriticError(String),
+
+    #[error("Event error: {0}")]
+    EventError(#[from] SendError<Event>),
+}
+
+/// Configuration for the reflection loop
+#[derive(Debug, Clone)]
+pub struct ReflectionConfig {
+    /// Maximum number of critiques; the draft is revised between critiques
+    pub max_rounds: usize,
+    /// System prompt of the critic
+    pub critic_prompt: String,
+}
+
+impl Default for ReflectionConfig {
+    fn default() -> Self {
+        Self {
+            max_rounds: 3,
+            critic_prompt: DEFAULT_CRITIC_PROMPT.to_string(),
+        }
+    }
+}
+
+/// Executor wrapper that iterates critique and revision over the wrapped executor's output
+pub struct Reflection<E> {
+    inner: E,
+    critic_llm: Option<Arc<dyn LLMProvider>>,
+    config: ReflectionConfig,
+}
+
+impl<E> Reflection<E> {
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            critic_llm: None,
+            config: ReflectionConfig::default(),
+        }
+    }
+
+    /// Use a different LLM provider for the critic
+    pub fn with_critic_llm(mut self, llm: Arc<dyn LLMProvider>) -> Self {
+        self.critic_llm = Some(llm);
+        self
+    }
+
+    pub fn with_config(mut self, config: ReflectionConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    fn critique_schema() -> StructuredOutputFormat {
+        StructuredOutputFormat {
+            name: "Critique".to_string(),
+            description: Some("Review verdict".to_string()),
+            schema: Some(serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "approved": {"type": "boolean"},
+                    "feedback": {"type": "string"}
+                },
+                "required": ["approved", "feedback"],
+                "additionalProperties": false
+            })),
+            strict: Some(true),
+        }
+    }
+
+    fn draft_text(draft: &Value) -> String {
+        match draft {
+            Value::String(s) => s.clone(),
+            other => serde_json::to_string_pretty(other).unwrap_or_default(),
+        }
+    }
+
+    async fn critique(
+        &self,
+        llm: &Arc<dyn LLMProvider>,
+        task: &Task,
+        draft: &Value,
+    ) -> Result<Critique, String> {
+        let messages = vec![
+            ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: self.config.critic_prompt.clone(),
+            },
+            ChatMessage {
+                role: ChatRole::User,
+                message_type: MessageType::Text,
+                content: format!(
+                    "Task:\n{}\n\nResponse to review:\n{}",
+                    task.prompt,
+                    Self::draft_text(draft)
+                ),
+            },
+        ];
+        let llm = self.critic_llm.as_ref().unwrap_or(llm);
+        let response = llm
+            .chat(&messages, Some(Self::critique_schema()))
+            .await
+            .map_err(|e| e.to_string())?;
+        let text = response.text().unwrap_or_default();
+        serde_json::from_str(text.trim())
+            .map_err(|e| format!("Critique is not a valid verdict ({e}): {text}"))
+    }
+
+    /// Run the wrapped executor on a revision of the task.
+    ///
+    /// The revision is not a new task: it does not emit `TaskStarted`, is not added to the task
+    /// history and its prompt is not stored in memory as a message of the user. The tool calls
+    /// and the answer of the revision are stored in memory.
+    #[allow(clippy::too_many_arguments)]
+    async fn revise(
+        &self,
+        llm: Arc<dyn LLMProvider>,
+        memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+        tools: Vec<Box<dyn ToolT>>,
+        agent_config: &AgentConfig,
+        mut revision: Task,
+        state: Arc<RwLock<AgentState>>,
+        tx_event: mpsc::Sender<Event>,
+    ) -> Result<E::Output, E::Error>
+    where
+        E: AgentExecutor,
+    {
+        let revised = Arc::new(Mutex::new(Vec::new()));
+        let overlay = memory.as_ref().map(|memory| {
+            // The attachments of the task are in memory since the first draft
+            revision.attachments.clear();
+            Arc::new(RwLock::new(Box::new(RevisionMemory {
+                memory: memory.clone(),
+                messages: revised.clone(),
+            }) as Box<dyn MemoryProvider>))
+        });
+        let (tx, mut rx) = mpsc::channel(REVISION_EVENT_BUFFER);
+        let forwarder = tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                if matches!(event, Event::TaskStarted { .. }) {
+                    continue;
+                }
+                if tx_event.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let prompt = revision.prompt.clone();
+        let sub_id = revision.submission_id;
+        let output = self
+            .inner
+            .execute(
+                llm,
+                overlay,
+                tools,
+                agent_config,
+                revision,
+                state.clone(),
+                tx,
+            )
+            .await;
+        let _ = forwarder.await;
+
+        // Other submissions may have recorded tasks meanwhile, only the revision is removed
+        state
+            .write()
+            .await
+            .task_history
+            .retain(|task| task.submission_id != sub_id || task.prompt != prompt);
+        if let Some(memory) = memory {
+            let revised = std::mem::take(&mut *revised.lock().unwrap());
+            let mut memory = memory.write().await;
+            for message in revised {
+                if message.role == ChatRole::User && message.content == prompt {
+                    continue;
+                }
+                let _ = memory.remember(&message).await;
+            }
+        }
+        output
+    }
+}
+
+/// Events of a revision buffered before they are forwarded to the agent's channel
+const REVISION_EVENT_BUFFER: usize = 100;
+
+/// Memory seen by a revision: the agent's memory with the messages of the revision on top
+struct RevisionMemory {
+    memory: Arc<RwLock<Box<dyn MemoryProvider>>>,
+    messages: Arc<Mutex<Vec<ChatMessage>>>,
+}
+
+#[async_trait]
+impl MemoryProvider for RevisionMemory {
+    async fn remember(&mut self, message: &ChatMessage) -> Result<(), LLMError> {
+        self.messages.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let mut messages = self.memory.read().await.recall(query, None).await?;
+        messages.extend(self.messages.lock().unwrap().iter().cloned());
+        if let Some(limit) = limit {
+            let skip = messages.len().saturating_sub(limit);
+            messages.drain(..skip);
+        }
+        Ok(messages)
+    }
+
+    async fn clear(&mut self) -> Result<(), LLMError> {
+        self.messages.lock().unwrap().clear();
+        Ok(())
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::SlidingWindow
+    }
+
+    fn size(&self) -> usize {
+        let stored = self.memory.try_read().map(|m| m.size()).unwrap_or(0);
+        stored + self.messages.lock().unwrap().len()
+    }
+}
+
+impl<E: Debug> Debug for Reflection<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+#[async_trait]
+impl<E: AgentExecutor> AgentExecutor for Reflection<E> {
+    type Output = E::Output;
+    type Error = ReflectionError<E::Error>;
+
+    fn config(&self) -> ExecutorConfig {
+        self.inner.config()
+    }
+
+    async fn execute(
+        &self,
+        llm: Arc<dyn LLMProvider>,
+        memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+        tools: Vec<Box<dyn ToolT>>,
+        agent_config: &AgentConfig,
+        task: Task,
+        state: Arc<RwLock<AgentState>>,
+        tx_event: mpsc::Sender<Event>,
+    ) -> Result<Self::Output, Self::Error> {
+        debug!("Starting Reflection Executor");
+        // Every revision runs the wrapped executor again with the same tools
+        let tools: Vec<SharedTool> = tools.into_iter().map(SharedTool::from).collect();
+        let mut output = self
+            .inner
+            .execute(
+                llm.clone(),
+                memory.clone(),
+                SharedTool::boxed(&tools),
+                agent_config,
+                task.clone(),
+                state.clone(),
+                tx_event.clone(),
+            )
+            .await
+            .map_err(ReflectionError::Executor)?;
+
+        for round in 1..=self.config.max_rounds {
+            let draft: Value = output.clone().into();
+            let critique = self
+                .critique(&llm, &task, &draft)
+                .await
+                .map_err(ReflectionError::CriticError)?;
+
+            state
+                .write()
+                .await
+                .record_reflection_round(ReflectionRound {
+                    round,
+                    draft: draft.clone(),
+                    approved: critique.approved,
+                    feedback: critique.feedback.clone(),
+                });
+            tx_event
+                .send(Event::ReflectionRoundCompleted {
+                    sub_id: task.submission_id,
+                    round,
+                    approved: critique.approved,
+                    feedback: critique.feedback.clone(),
+                })
+                .await?;
+
+            if critique.approved || round == self.config.max_rounds {
+                break;
+            }
+
+            let mut revision = task.clone();
+            revision.prompt = format!(
+                "{}\n\nYour previous answer:\n{}\n\nReviewer feedback:\n{}\n\n\
+                 Revise your answer to address the feedback.",
+                task.prompt,
+                Self::draft_text(&draft),
+                critique.feedback
+            );
+            output = self
+                .revise(
+                    llm.clone(),
+                    memory.clone(),
+                    SharedTool::boxed(&tools),
+                    agent_config,
+                    revision,
+                    state.clone(),
+                    tx_event.clone(),
+                )
+                .await
+                .map_err(ReflectionError::Executor)?;
+        }
+
+        Ok(output)
+    }
+}
+
+#[async_trait]
+impl<E: AgentDeriveT> AgentDeriveT for Reflection<E> {
+    type Output = <E as AgentDeriveT>::Output;
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        self.inner.output_schema()
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn tools(&self) -> Vec<Box<dyn ToolT>> {
+        self.inner.tools()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SlidingWindowMemory;
+    use autoagents_test_utils::agent::MockAgentImpl;
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+    use uuid::Uuid;
+
+    /// Answers the task prompt with a single LLM call
+    struct SingleShot;
+
+    #[async_trait]
+    impl AgentExecutor for SingleShot {
+        type Output = String;
+        type Error = SingleShotError;
+
+        fn config(&self) -> ExecutorConfig {
+            ExecutorConfig::default()
+        }
+
+        async fn execute(
+            &self,
+            llm: Arc<dyn LLMProvider>,
+            memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+            _tools: Vec<Box<dyn ToolT>>,
+            agent_config: &AgentConfig,
+            task: Task,
+            state: Arc<RwLock<AgentState>>,
+            tx_event: mpsc::Sender<Event>,
+        ) -> Result<Self::Output, Self::Error> {
+            // Report and record the task the way the prebuilt executors do
+            state.write().await.record_task(task.clone());
+            let _ = tx_event
+                .send(Event::TaskStarted {
+                    sub_id: task.submission_id,
+                    agent_id: agent_config.id,
+                    task_description: task.prompt.clone(),
+                })
+                .await;
+            let prompt = ChatMessage::user().content(task.prompt).build();
+            let messages = [prompt.clone()];
+            let response = llm
+                .chat(&messages, None)
+                .await
+                .map_err(|e| SingleShotError(e.to_string()))?;
+            let answer = response.text().unwrap_or_default();
+            if let Some(memory) = memory {
+                let mut memory = memory.write().await;
+                let _ = memory.remember(&prompt).await;
+                let _ = memory
+                    .remember(&ChatMessage::assistant().content(answer.clone()).build())
+                    .await;
+            }
+            Ok(answer)
+        }
+    }
+
+    #[derive(Debug, Error)]
+    #[error("{0}")]
+    struct SingleShotError(String);
+
+    /// Single shot sharing its state with another submission recording a task meanwhile
+    struct Busy;
+
+    #[async_trait]
+    impl AgentExecutor for Busy {
+        type Output = String;
+        type Error = SingleShotError;
+
+        fn config(&self) -> ExecutorConfig {
+            ExecutorConfig::default()
+        }
+
+        async fn execute(
+            &self,
+            llm: Arc<dyn LLMProvider>,
+            memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+            tools: Vec<Box<dyn ToolT>>,
+            agent_config: &AgentConfig,
+            task: Task,
+            state: Arc<RwLock<AgentState>>,
+            tx_event: mpsc::Sender<Event>,
+        ) -> Result<Self::Output, Self::Error> {
+            state
+                .write()
+                .await
+                .record_task(Task::new("Other task", None));
+            SingleShot
+                .execute(llm, memory, tools, agent_config, task, state, tx_event)
+                .await
+        }
+    }
+
+    fn agent_config() -> AgentConfig {
+        AgentConfig {
+            name: "writer".to_string(),
+            description: "You are a writer".to_string(),
+            id: Uuid::new_v4(),
+            output_schema: None,
+            checkpoint_store: None,
+        }
+    }
+
+    async fn run(
+        executor: &Reflection<SingleShot>,
+        llm: Arc<ScriptedLLMProvider>,
+    ) -> (
+        Result<String, ReflectionError<SingleShotError>>,
+        AgentState,
+        Vec<Event>,
+    ) {
+        run_with_memory(executor, llm, None).await
+    }
+
+    async fn run_with_memory(
+        executor: &Reflection<SingleShot>,
+        llm: Arc<ScriptedLLMProvider>,
+        memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+    ) -> (
+        Result<String, ReflectionError<SingleShotError>>,
+        AgentState,
+        Vec<Event>,
+    ) {
+        let state = Arc::new(RwLock::new(AgentState::new()));
+        let (tx, mut rx) = mpsc::channel(100);
+        let result = executor
+            .execute(
+                llm,
+                memory,
+                vec![],
+                &agent_config(),
+                Task::new("Write a haiku", None),
+                state.clone(),
+                tx,
+            )
+            .await;
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        let state = state.read().await.clone();
+        (result, state, events)
+    }
+
+    #[tokio::test]
+    async fn test_reflection_revises_until_approved() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec![
+            "draft 1",
+            r#"{"approved": false, "feedback": "Too short"}"#,
+            "draft 2",
+            r#"{"approved": true, "feedback": "Good"}"#,
+        ]));
+
+        let (result, state, events) = run(&Reflection::new(SingleShot), llm.clone()).await;
+
+        assert_eq!(result.unwrap(), "draft 2");
+        assert_eq!(state.reflection_rounds.len(), 2);
+        assert!(!state.reflection_rounds[0].approved);
+        assert_eq!(state.reflection_rounds[0].draft, "draft 1");
+        assert!(state.reflection_rounds[1].approved);
+        // One task started, two critiques
+        assert_eq!(events.len(), 3);
+
+        // The revision prompt carries the previous draft and the feedback
+        let revision_prompt = &llm.requests()[2][0].content;
+        assert!(revision_prompt.contains("draft 1"));
+        assert!(revision_prompt.contains("Too short"));
+    }
+
+    #[tokio::test]
+    async fn test_reflection_stops_at_max_rounds() {
+        let executor = Reflection::new(SingleShot).with_config(ReflectionConfig {
+            max_rounds: 1,
+            ..Default::default()
+        });
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec![
+            "draft 1",
+            r#"{"approved": false, "feedback": "Needs more detail"}"#,
+        ]));
+
+        let (result, state, _) = run(&executor, llm.clone()).await;
+
+        assert_eq!(result.unwrap(), "draft 1");
+        assert_eq!(state.reflection_rounds.len(), 1);
+        assert!(!state.reflection_rounds[0].approved);
+        assert_eq!(state.reflection_rounds[0].feedback, "Needs more detail");
+        assert_eq!(llm.remaining(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reflection_invalid_critique_is_an_error() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec![
+            "draft 1",
+            "Needs more detail",
+        ]));
+
+        let (result, state, _) = run(&Reflection::new(SingleShot), llm).await;
+
+        assert!(matches!(
+            result,
+            Err(ReflectionError::CriticError(reason)) if reason.contains("Needs more detail")
+        ));
+        assert!(state.reflection_rounds.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_revisions_are_not_recorded_as_tasks() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec![
+            "draft 1",
+            r#"{"approved": false, "feedback": "Too short"}"#,
+            "draft 2",
+            r#"{"approved": true, "feedback": "Good"}"#,
+        ]));
+        let memory: Arc<RwLock<Box<dyn MemoryProvider>>> =
+            Arc::new(RwLock::new(Box::new(SlidingWindowMemory::new(10))));
+
+        let (result, state, events) =
+            run_with_memory(&Reflection::new(SingleShot), llm, Some(memory.clone())).await;
+
+        assert_eq!(result.unwrap(), "draft 2");
+        assert_eq!(state.task_history.len(), 1);
+        let started = events
+            .iter()
+            .filter(|event| matches!(event, Event::TaskStarted { .. }))
+            .count();
+        assert_eq!(started, 1);
+        // The revision prompt is not stored as a message of the user
+        let stored: Vec<String> = memory
+            .read()
+            .await
+            .recall("", None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|message| message.content)
+            .collect();
+        assert_eq!(stored, vec!["Write a haiku", "draft 1", "draft 2"]);
+    }
+
+    #[tokio::test]
+    async fn test_revisions_keep_tasks_of_other_submissions() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec![
+            "draft 1",
+            r#"{"approved": false, "feedback": "Too short"}"#,
+            "draft 2",
+            r#"{"approved": true, "feedback": "Good"}"#,
+        ]));
+        let state = Arc::new(RwLock::new(AgentState::new()));
+        let (tx, _rx) = mpsc::channel(100);
+        let result = Reflection::new(Busy)
+            .execute(
+                llm,
+                None,
+                vec![],
+                &agent_config(),
+                Task::new("Write a haiku", None),
+                state.clone(),
+                tx,
+            )
+            .await;
+
+        assert_eq!(result.unwrap(), "draft 2");
+        let prompts: Vec<String> = state
+            .read()
+            .await
+            .task_history
+            .iter()
+            .map(|task| task.prompt.clone())
+            .collect();
+        assert_eq!(prompts, vec!["Other task", "Write a haiku", "Other task"]);
+    }
+
+    #[tokio::test]
+    async fn test_reflection_uses_critic_llm() {
+        let critic = Arc::new(ScriptedLLMProvider::with_texts(vec![
+            r#"{"approved": true, "feedback": ""}"#,
+        ]));
+        let executor = Reflection::new(SingleShot).with_critic_llm(critic.clone());
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec!["draft 1"]));
+
+        let (result, state, _) = run(&executor, llm.clone()).await;
+
+        assert_eq!(result.unwrap(), "draft 1");
+        assert!(state.reflection_rounds[0].approved);
+        assert_eq!(llm.requests().len(), 1);
+        assert_eq!(critic.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reflection_critic_error() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec!["draft 1"]));
+        let (result, _, _) = run(&Reflection::new(SingleShot), llm).await;
+        assert!(matches!(result, Err(ReflectionError::CriticError(_))));
+    }
+
+    #[test]
+    fn test_reflection_delegates_agent_metadata() {
+        let agent = Reflection::new(MockAgentImpl::new("solver", "Solves problems"));
+        assert_eq!(agent.name(), "solver");
+        assert_eq!(agent.description(), "Solves problems");
+        assert!(agent.output_schema().is_some());
+    }
+}
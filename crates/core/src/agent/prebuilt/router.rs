@@ -0,0 +1,685 @@
+//! Router (supervisor) agent.
+//!
+//! [`RouterAgent`] is registered on a runtime like any other agent. For every task it
+//! chooses one of the other agents registered on the same runtime, forwards the task to it
+//! and completes with that agent's result. The choice is made by the LLM from the agents'
+//! `name()` and `description()`, unless a keyword rule or, when enabled, embedding
+//! similarity picks an agent first.
+//!
+//! Other routers on the runtime are only candidates when listed with
+//! [`RouterAgent::with_candidates`], and a task forwarded through more than
+//! [`RouterAgent::with_max_depth`] routers fails instead of being routed again.
+//!
+//! ```ignore
+//! let router = RouterAgent::new("router", "Dispatches tasks", llm, runtime.clone())
+//!     .with_keyword("weather", "weather_agent")
+//!     .with_embedding_routing(0.8);
+//! runtime.register_agent(Arc::new(router)).await?;
+//! ```
+use crate::agent::error::RunnableAgentError;
+use crate::agent::output::validate_output;
+use crate::agent::runnable::RunnableAgent;
+use crate::error::Error;
+use crate::memory::MemoryProvider;
+use crate::protocol::{AgentID, Event, TaskResult};
+use crate::runtime::{Runtime, Task};
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType, StructuredOutputFormat};
+use autoagents_llm::LLMProvider;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::{mpsc, RwLock};
+use uuid::Uuid;
+
+/// Number of routers a task can be forwarded through by default
+pub const DEFAULT_MAX_ROUTE_DEPTH: usize = 3;
+
+/// How the router chose an agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RouteMethod {
+    Keyword,
+    Embedding,
+    Llm,
+}
+
+/// The agent chosen for a task
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteDecision {
+    pub agent_id: AgentID,
+    pub agent_name: String,
+    pub method: RouteMethod,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RouteChoice {
+    agent: String,
+    #[serde(default)]
+    reason: String,
+}
+
+#[derive(Error, Debug)]
+pub enum RouterError {
+    #[error("No agents to route to")]
+    NoAgents,
+
+    #[error("LLM error: {0}")]
+    LLMError(String),
+
+    #[error("Invalid routing choice: {0}")]
+    InvalidChoice(String),
+
+    #[error("Task was already forwarded through {0} routers")]
+    MaxDepth(usize),
+}
+
+/// Agent that dispatches each task to the best suited agent on its runtime
+pub struct RouterAgent {
+    id: AgentID,
+    name: &'static str,
+    description: &'static str,
+    llm: Arc<dyn LLMProvider>,
+    runtime: Arc<dyn Runtime>,
+    candidates: Option<Vec<AgentID>>,
+    keywords: Vec<(String, String)>,
+    embedding_threshold: Option<f32>,
+    max_depth: usize,
+    /// Embeddings of the candidates' descriptions by agent id
+    description_embeddings: RwLock<HashMap<AgentID, Vec<f32>>>,
+}
+
+impl Debug for RouterAgent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RouterAgent")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("candidates", &self.candidates)
+            .field("keywords", &self.keywords)
+            .field("embedding_threshold", &self.embedding_threshold)
+            .field("max_depth", &self.max_depth)
+            .finish()
+    }
+}
+
+impl RouterAgent {
+    pub fn new(
+        name: &'static str,
+        description: &'static str,
+        llm: Arc<dyn LLMProvider>,
+        runtime: Arc<dyn Runtime>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            description,
+            llm,
+            runtime,
+            candidates: None,
+            keywords: Vec::new(),
+            embedding_threshold: None,
+            max_depth: DEFAULT_MAX_ROUTE_DEPTH,
+            description_embeddings: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Only route to these agents instead of every agent on the runtime but routers
+    pub fn with_candidates(mut self, agent_ids: Vec<AgentID>) -> Self {
+        self.candidates = Some(agent_ids);
+        self
+    }
+
+    /// Route tasks mentioning `keyword` (case-insensitive) to the agent named `agent_name`
+    pub fn with_keyword(
+        mut self,
+        keyword: impl Into<String>,
+        agent_name: impl Into<String>,
+    ) -> Self {
+        self.keywords
+            .push((keyword.into().to_lowercase(), agent_name.into()));
+        self
+    }
+
+    /// Route by cosine similarity between the task and the agent descriptions when the
+    /// best match reaches `threshold`
+    pub fn with_embedding_routing(mut self, threshold: f32) -> Self {
+        self.embedding_threshold = Some(threshold);
+        self
+    }
+
+    /// Fail tasks already forwarded through `max_depth` routers
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    async fn candidate_agents(&self) -> Vec<Arc<dyn RunnableAgent>> {
+        let mut agents: Vec<Arc<dyn RunnableAgent>> = self
+            .runtime
+            .agents()
+            .await
+            .into_iter()
+            .filter(|a| a.id() != self.id)
+            .filter(|a| match &self.candidates {
+                Some(ids) => ids.contains(&a.id()),
+                None => !a.dispatches_tasks(),
+            })
+            .collect();
+        agents.sort_by_key(|a| a.name());
+        agents
+    }
+
+    /// Choose the agent for a task without running it
+    pub async fn route(&self, prompt: &str) -> Result<RouteDecision, RouterError> {
+        let agents = self.candidate_agents().await;
+        if agents.is_empty() {
+            return Err(RouterError::NoAgents);
+        }
+
+        let lowercase = prompt.to_lowercase();
+        for (keyword, agent_name) in &self.keywords {
+            if !lowercase.contains(keyword.as_str()) {
+                continue;
+            }
+            if let Some(agent) = agents.iter().find(|a| a.name() == agent_name) {
+                return Ok(decision(
+                    agent,
+                    RouteMethod::Keyword,
+                    format!("Task mentions '{keyword}'"),
+                ));
+            }
+        }
+
+        if let Some(threshold) = self.embedding_threshold {
+            match self.route_by_embedding(prompt, &agents).await {
+                Ok(Some((agent, score))) if score >= threshold => {
+                    return Ok(decision(
+                        agent,
+                        RouteMethod::Embedding,
+                        format!("Similarity {score:.3}"),
+                    ));
+                }
+                Ok(_) => debug!("No agent similar enough, routing with the LLM"),
+                Err(e) => warn!("Embedding routing failed, routing with the LLM: {e}"),
+            }
+        }
+
+        self.route_with_llm(prompt, &agents).await
+    }
+
+    async fn route_by_embedding<'a>(
+        &self,
+        prompt: &str,
+        agents: &'a [Arc<dyn RunnableAgent>],
+    ) -> Result<Option<(&'a Arc<dyn RunnableAgent>, f32)>, RouterError> {
+        let uncached: Vec<&Arc<dyn RunnableAgent>> = {
+            let cache = self.description_embeddings.read().await;
+            agents
+                .iter()
+                .filter(|a| !cache.contains_key(&a.id()))
+                .collect()
+        };
+        let mut input = vec![prompt.to_string()];
+        input.extend(
+            uncached
+                .iter()
+                .map(|a| format!("{}: {}", a.name(), a.description())),
+        );
+        let embeddings = self
+            .llm
+            .embed(input)
+            .await
+            .map_err(|e| RouterError::LLMError(e.to_string()))?;
+        let mut embeddings = embeddings.into_iter();
+        let Some(task) = embeddings.next() else {
+            return Ok(None);
+        };
+        let mut cache = self.description_embeddings.write().await;
+        for (agent, embedding) in uncached.iter().zip(embeddings) {
+            cache.insert(agent.id(), embedding);
+        }
+
+        Ok(agents
+            .iter()
+            .filter_map(|agent| {
+                let embedding = cache.get(&agent.id())?;
+                Some((agent, cosine_similarity(&task, embedding)))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1)))
+    }
+
+    async fn route_with_llm(
+        &self,
+        prompt: &str,
+        agents: &[Arc<dyn RunnableAgent>],
+    ) -> Result<RouteDecision, RouterError> {
+        let names: Vec<&str> = agents.iter().map(|a| a.name()).collect();
+        let catalog = agents
+            .iter()
+            .map(|a| format!("- {}: {}", a.name(), a.description()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let format = StructuredOutputFormat {
+            name: "Route".to_string(),
+            description: Some("The agent best suited for the task".to_string()),
+            schema: Some(serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "agent": {"type": "string", "enum": names},
+                    "reason": {"type": "string"}
+                },
+                "required": ["agent", "reason"],
+                "additionalProperties": false
+            })),
+            strict: Some(true),
+        };
+        let messages = vec![
+            ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: format!(
+                    "{}\n\nChoose the agent best suited to handle the user's task. \
+                     Available agents:\n{catalog}",
+                    self.description
+                ),
+            },
+            ChatMessage {
+                role: ChatRole::User,
+                message_type: MessageType::Text,
+                content: prompt.to_string(),
+            },
+        ];
+
+        let response = self
+            .llm
+            .chat(&messages, Some(format.clone()))
+            .await
+            .map_err(|e| RouterError::LLMError(e.to_string()))?;
+        let text = response.text().unwrap_or_default();
+        let value = validate_output(&format, &text)
+            .map_err(|e| RouterError::InvalidChoice(e.to_string()))?;
+        let choice: RouteChoice =
+            serde_json::from_value(value).map_err(|e| RouterError::InvalidChoice(e.to_string()))?;
+
+        agents
+            .iter()
+            .find(|a| a.name() == choice.agent)
+            .map(|agent| decision(agent, RouteMethod::Llm, choice.reason))
+            .ok_or(RouterError::InvalidChoice(choice.agent))
+    }
+}
+
+fn decision(agent: &Arc<dyn RunnableAgent>, method: RouteMethod, reason: String) -> RouteDecision {
+    RouteDecision {
+        agent_id: agent.id(),
+        agent_name: agent.name().to_string(),
+        method,
+        reason,
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[async_trait]
+impl RunnableAgent for RouterAgent {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn id(&self) -> AgentID {
+        self.id
+    }
+
+    fn memory(&self) -> Option<Arc<RwLock<Box<dyn MemoryProvider>>>> {
+        None
+    }
+
+    fn dispatches_tasks(&self) -> bool {
+        true
+    }
+
+    async fn run(self: Arc<Self>, task: Task, tx_event: mpsc::Sender<Event>) -> Result<(), Error> {
+        tx_event
+            .send(Event::TaskStarted {
+                sub_id: task.submission_id,
+                agent_id: self.id,
+                task_description: task.prompt.clone(),
+            })
+            .await
+            .map_err(RunnableAgentError::event_send_error)?;
+
+        let route = if task.route_depth >= self.max_depth {
+            Err(RouterError::MaxDepth(task.route_depth))
+        } else {
+            self.route(&task.prompt).await
+        };
+        let routed = match route {
+            Ok(decision) => {
+                tx_event
+                    .send(Event::TaskRouted {
+                        sub_id: task.submission_id,
+                        agent_id: decision.agent_id,
+                        agent_name: decision.agent_name.clone(),
+                        method: decision.method,
+                        reason: decision.reason.clone(),
+                    })
+                    .await
+                    .map_err(RunnableAgentError::event_send_error)?;
+                // Context, attachments and budget go along with the prompt
+                self.runtime
+                    .execute_task(decision.agent_id, task.forwarded_to(decision.agent_id))
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            Err(e) => Err(e.to_string()),
+        };
+
+        let result = match &routed {
+            Ok(result) => result.clone(),
+            Err(e) => TaskResult::Failure(e.clone()),
+        };
+        let _ = tx_event
+            .send(Event::TaskComplete {
+                sub_id: task.submission_id,
+                result,
+            })
+            .await;
+
+        match routed {
+            Ok(_) => Ok(()),
+            Err(e) => Err(RunnableAgentError::ExecutorError(e).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::SingleThreadedRuntime;
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+    use serde_json::Value;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio_stream::StreamExt;
+
+    #[derive(Debug)]
+    struct NamedAgent {
+        id: AgentID,
+        name: &'static str,
+        description: &'static str,
+        received: std::sync::Mutex<Vec<Task>>,
+    }
+
+    #[async_trait]
+    impl RunnableAgent for NamedAgent {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn description(&self) -> &'static str {
+            self.description
+        }
+
+        fn id(&self) -> AgentID {
+            self.id
+        }
+
+        fn memory(&self) -> Option<Arc<RwLock<Box<dyn MemoryProvider>>>> {
+            None
+        }
+
+        async fn run(self: Arc<Self>, task: Task, tx: mpsc::Sender<Event>) -> Result<(), Error> {
+            self.received.lock().unwrap().push(task.clone());
+            let _ = tx
+                .send(Event::TaskComplete {
+                    sub_id: task.submission_id,
+                    result: TaskResult::Value(Value::String(format!(
+                        "{} handled {}",
+                        self.name, task.prompt
+                    ))),
+                })
+                .await;
+            Ok(())
+        }
+    }
+
+    async fn runtime() -> Arc<SingleThreadedRuntime> {
+        let runtime = SingleThreadedRuntime::new(None);
+        for (name, description) in [
+            ("weather_agent", "Answers questions about the weather"),
+            ("math_agent", "Solves arithmetic problems"),
+        ] {
+            runtime
+                .register_agent(Arc::new(NamedAgent {
+                    id: Uuid::new_v4(),
+                    name,
+                    description,
+                    received: Default::default(),
+                }))
+                .await
+                .unwrap();
+        }
+        runtime
+    }
+
+    fn router(runtime: Arc<SingleThreadedRuntime>, llm: Arc<ScriptedLLMProvider>) -> RouterAgent {
+        RouterAgent::new("router", "Dispatches tasks", llm, runtime)
+    }
+
+    #[tokio::test]
+    async fn test_route_by_keyword_skips_llm() {
+        let llm = Arc::new(ScriptedLLMProvider::default());
+        let router = router(runtime().await, llm.clone()).with_keyword("Rain", "weather_agent");
+
+        let decision = router.route("Will it rain tomorrow?").await.unwrap();
+        assert_eq!(decision.agent_name, "weather_agent");
+        assert_eq!(decision.method, RouteMethod::Keyword);
+        assert!(llm.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_route_with_llm() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec![
+            r#"{"agent": "math_agent", "reason": "It is arithmetic"}"#,
+        ]));
+        let router = router(runtime().await, llm.clone());
+
+        let decision = router.route("What is 2 + 2?").await.unwrap();
+        assert_eq!(decision.agent_name, "math_agent");
+        assert_eq!(decision.method, RouteMethod::Llm);
+        assert_eq!(decision.reason, "It is arithmetic");
+
+        let system = &llm.requests()[0][0].content;
+        assert!(system.contains("- math_agent: Solves arithmetic problems"));
+        assert!(system.contains("- weather_agent: Answers questions about the weather"));
+    }
+
+    #[tokio::test]
+    async fn test_route_rejects_unknown_agent() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec![
+            r#"{"agent": "poet_agent", "reason": "?"}"#,
+        ]));
+        let router = router(runtime().await, llm);
+        assert!(matches!(
+            router.route("Write a poem").await,
+            Err(RouterError::InvalidChoice(_))
+        ));
+    }
+
+    fn weather_embedding(text: &str) -> Vec<f32> {
+        let text = text.to_lowercase();
+        let weather = text.contains("weather") || text.contains("sunny");
+        if weather {
+            vec![1.0, 0.0]
+        } else {
+            vec![0.0, 1.0]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_route_by_embedding() {
+        let llm = Arc::new(ScriptedLLMProvider::default().with_embedder(weather_embedding));
+        let router = router(runtime().await, llm.clone()).with_embedding_routing(0.9);
+
+        let decision = router.route("Is it sunny?").await.unwrap();
+        assert_eq!(decision.agent_name, "weather_agent");
+        assert_eq!(decision.method, RouteMethod::Embedding);
+        assert!(llm.requests().is_empty());
+    }
+
+    static DESCRIPTION_EMBEDDINGS: AtomicUsize = AtomicUsize::new(0);
+
+    fn counting_embedding(text: &str) -> Vec<f32> {
+        // Descriptions are embedded as "name: description"
+        if text.contains(": ") {
+            DESCRIPTION_EMBEDDINGS.fetch_add(1, Ordering::SeqCst);
+        }
+        weather_embedding(text)
+    }
+
+    #[tokio::test]
+    async fn test_description_embeddings_are_cached() {
+        let llm = Arc::new(ScriptedLLMProvider::default().with_embedder(counting_embedding));
+        let router = router(runtime().await, llm).with_embedding_routing(0.9);
+
+        router.route("Is it sunny?").await.unwrap();
+        assert_eq!(DESCRIPTION_EMBEDDINGS.load(Ordering::SeqCst), 2);
+        let decision = router.route("Is the weather nice?").await.unwrap();
+        assert_eq!(decision.agent_name, "weather_agent");
+        assert_eq!(DESCRIPTION_EMBEDDINGS.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_routers_are_not_default_candidates() {
+        let runtime = runtime().await;
+        let llm = Arc::new(ScriptedLLMProvider::default());
+        let other = Arc::new(router(runtime.clone(), llm.clone()));
+        runtime.register_agent(other.clone()).await.unwrap();
+        let router = router(runtime.clone(), llm.clone());
+
+        let names: Vec<_> = router
+            .candidate_agents()
+            .await
+            .iter()
+            .map(|a| a.name())
+            .collect();
+        assert_eq!(names, vec!["math_agent", "weather_agent"]);
+
+        let router = router.with_candidates(vec![other.id()]);
+        assert_eq!(router.candidate_agents().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_routing_depth_is_capped() {
+        let runtime = SingleThreadedRuntime::new(None);
+        let llm = Arc::new(ScriptedLLMProvider::default());
+        // Two routers forwarding every task to each other
+        let ping = RouterAgent::new("ping", "Forwards to pong", llm.clone(), runtime.clone())
+            .with_keyword("ball", "pong");
+        let pong = RouterAgent::new("pong", "Forwards to ping", llm.clone(), runtime.clone())
+            .with_keyword("ball", "ping")
+            .with_candidates(vec![ping.id()]);
+        let ping = Arc::new(ping.with_candidates(vec![pong.id()]));
+        runtime.register_agent(ping.clone()).await.unwrap();
+        runtime.register_agent(Arc::new(pong)).await.unwrap();
+        let background = runtime.clone();
+        tokio::spawn(async move { background.run().await });
+
+        let result = runtime
+            .execute_task(ping.id(), Task::new("Catch the ball", Some(ping.id())))
+            .await;
+        let failure = match result {
+            Ok(TaskResult::Failure(failure)) => failure,
+            Err(e) => e.to_string(),
+            other => panic!("Expected a failure, got {other:?}"),
+        };
+        assert!(failure.contains("already forwarded through 3 routers"));
+        assert!(llm.requests().is_empty());
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_forwards_to_chosen_agent() {
+        let runtime = runtime().await;
+        let llm = Arc::new(ScriptedLLMProvider::default());
+        let router = Arc::new(router(runtime.clone(), llm).with_keyword("sum", "math_agent"));
+        runtime.register_agent(router.clone()).await.unwrap();
+        let mut events = runtime.take_event_receiver().await.unwrap();
+        let background = runtime.clone();
+        tokio::spawn(async move { background.run().await });
+
+        let task = Task::new("sum 1 and 2", Some(router.id()));
+        let sub_id = task.submission_id;
+        let result = runtime.execute_task(router.id(), task).await.unwrap();
+        match result {
+            TaskResult::Value(value) => assert_eq!(value, "math_agent handled sum 1 and 2"),
+            other => panic!("Expected value, got {other:?}"),
+        }
+
+        let mut routed = None;
+        while let Some(event) = events.next().await {
+            match event {
+                Event::TaskRouted {
+                    sub_id: id,
+                    agent_name,
+                    ..
+                } if id == sub_id => {
+                    routed = Some(agent_name);
+                }
+                Event::TaskComplete { sub_id: id, .. } if id == sub_id => break,
+                _ => {}
+            }
+        }
+        assert_eq!(routed.as_deref(), Some("math_agent"));
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_task_keeps_context_and_attachments() {
+        let runtime = SingleThreadedRuntime::new(None);
+        let vision = Arc::new(NamedAgent {
+            id: Uuid::new_v4(),
+            name: "vision_agent",
+            description: "Describes images",
+            received: Default::default(),
+        });
+        runtime.register_agent(vision.clone()).await.unwrap();
+        let llm = Arc::new(ScriptedLLMProvider::default());
+        let router = Arc::new(router(runtime.clone(), llm).with_keyword("photo", "vision_agent"));
+        runtime.register_agent(router.clone()).await.unwrap();
+        let background = runtime.clone();
+        tokio::spawn(async move { background.run().await });
+
+        let photo = crate::runtime::Attachment::image_url("https://example.com/cat.png");
+        let task = Task::new("Describe the photo", Some(router.id()))
+            .with_context("user", "Ada")
+            .with_attachment(photo.clone());
+        let sub_id = task.submission_id;
+        runtime.execute_task(router.id(), task).await.unwrap();
+
+        let received = vision.received.lock().unwrap().clone();
+        assert_eq!(received.len(), 1);
+        let forwarded = &received[0];
+        assert_eq!(forwarded.prompt, "Describe the photo");
+        assert_eq!(forwarded.attachments, vec![photo]);
+        assert_eq!(forwarded.context["user"], "Ada");
+        assert_eq!(forwarded.route_depth, 1);
+        assert_ne!(forwarded.submission_id, sub_id);
+        runtime.stop().await.unwrap();
+    }
+}
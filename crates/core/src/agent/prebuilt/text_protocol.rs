@@ -0,0 +1,263 @@
+//! Text protocol for tool calling with models that have no native tool calling.
+//!
+//! The tools are described in the system prompt and the model answers in the classic ReAct
+//! format:
+//!
+//! ```text
+//! Thought: I need the weather first
+//! Action: get_weather
+//! Action Input: {"city": "Paris"}
+//! ```
+//!
+//! or with a JSON action block:
+//!
+//! ```text
+//! {"action": "get_weather", "action_input": {"city": "Paris"}}
+//! ```
+//!
+//! Each action becomes a regular [`ToolCall`], so tools run and report the same
+//! [`ToolCallResult`]s as with native tool calling. Results are sent back to the model as an
+//! `Observation:` message. Select the protocol with
+//! [`ExecutorConfig::tool_protocol`](crate::agent::ExecutorConfig::tool_protocol).
+
+use crate::tool::{ToolCallResult, ToolT};
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
+use autoagents_llm::{FunctionCall, ToolCall};
+use serde_json::Value;
+use uuid::Uuid;
+
+const ACTION: &str = "Action:";
+const ACTION_INPUT: &str = "Action Input:";
+const OBSERVATION: &str = "Observation:";
+const FINAL_ANSWER: &str = "Final Answer:";
+
+/// What the model decided to do in a text protocol response
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextStep {
+    /// Call a tool, `input` holds the arguments as the model wrote them
+    Action { name: String, input: String },
+    /// The answer to the task
+    FinalAnswer(String),
+}
+
+/// Instructions describing the tools and the response format
+pub fn tool_instructions(tools: &[Box<dyn ToolT>]) -> String {
+    let mut instructions = String::from("You can use the following tools:\n\n");
+    for tool in tools {
+        instructions.push_str(&format!(
+            "- {}: {}\n  Arguments schema: {}\n",
+            tool.name(),
+            tool.description(),
+            tool.args_schema()
+        ));
+    }
+    let names: Vec<&str> = tools.iter().map(|tool| tool.name()).collect();
+    instructions.push_str(&format!(
+        "\nTo use a tool, respond in exactly this format and stop:\n\n\
+         Thought: what you need to do next\n\
+         {ACTION} the tool to use, one of [{}]\n\
+         {ACTION_INPUT} the arguments as a JSON object\n\n\
+         The result of the tool is sent back as:\n\n\
+         {OBSERVATION} the result\n\n\
+         Use as many tools as you need, one at a time. Once you know the answer, respond with:\n\n\
+         Thought: I know the answer\n\
+         {FINAL_ANSWER} the answer to the task",
+        names.join(", ")
+    ));
+    instructions
+}
+
+/// Messages with the tool instructions appended to the system prompt
+pub fn with_tool_instructions(
+    messages: &[ChatMessage],
+    tools: &[Box<dyn ToolT>],
+) -> Vec<ChatMessage> {
+    let instructions = tool_instructions(tools);
+    let mut messages = messages.to_vec();
+    match messages.first_mut() {
+        Some(system) if system.role == ChatRole::System => {
+            system.content = format!("{}\n\n{instructions}", system.content);
+        }
+        _ => messages.insert(
+            0,
+            ChatMessage {
+                role: ChatRole::System,
+                message_type: MessageType::Text,
+                content: instructions,
+            },
+        ),
+    }
+    messages
+}
+
+/// Parse the step a model took from its response.
+///
+/// `Action:`/`Action Input:` lines take precedence over a JSON action block. A response with
+/// neither is a final answer, the text after `Final Answer:` when present.
+pub fn parse_response(text: &str) -> TextStep {
+    let text = until_observation(text);
+    let action = find_marker(text, ACTION);
+    let final_answer = find_marker(text, FINAL_ANSWER);
+    match (action, final_answer) {
+        (Some(action), None) => parse_action_lines(&text[action..]),
+        (Some(action), Some(answer)) if action < answer => parse_action_lines(&text[action..]),
+        (_, Some(answer)) => {
+            TextStep::FinalAnswer(text[answer + FINAL_ANSWER.len()..].trim().to_string())
+        }
+        (None, None) => parse_action_block(text)
+            .unwrap_or_else(|| TextStep::FinalAnswer(text.trim().to_string())),
+    }
+}
+
+/// The response up to the first observation, models sometimes go on and make one up
+pub(crate) fn until_observation(text: &str) -> &str {
+    match find_marker(text, OBSERVATION) {
+        Some(index) => text[..index].trim_end(),
+        None => text,
+    }
+}
+
+/// Tool call for an action taken through the text protocol
+pub(crate) fn to_tool_call(name: String, input: String) -> ToolCall {
+    ToolCall {
+        id: format!("text_{}", Uuid::new_v4().simple()),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name,
+            arguments: input,
+        },
+    }
+}
+
+/// Message reporting the results of the tools to the model
+pub(crate) fn observation(results: &[ToolCallResult]) -> ChatMessage {
+    let content = results
+        .iter()
+        .map(|result| match &result.result {
+            Value::String(text) => format!("{OBSERVATION} {text}"),
+            other => format!("{OBSERVATION} {other}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    ChatMessage {
+        role: ChatRole::User,
+        message_type: MessageType::Text,
+        content,
+    }
+}
+
+/// Byte offset of `marker` at the start of a line
+fn find_marker(text: &str, marker: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with(marker) {
+            return Some(offset + line.len() - trimmed.len());
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn parse_action_lines(text: &str) -> TextStep {
+    let rest = &text[ACTION.len()..];
+    let (name, rest) = rest.split_once('\n').unwrap_or((rest, ""));
+    let name = name.trim().trim_matches('`').trim().to_string();
+    let input = match find_marker(rest, ACTION_INPUT) {
+        Some(index) => strip_fence(&rest[index + ACTION_INPUT.len()..]),
+        None => "{}".to_string(),
+    };
+    TextStep::Action { name, input }
+}
+
+fn parse_action_block(text: &str) -> Option<TextStep> {
+    let block = strip_fence(text);
+    let start = block.find('{')?;
+    let end = block.rfind('}')?;
+    let value: Value = serde_json::from_str(block.get(start..=end)?).ok()?;
+    let name = value.get("action")?.as_str()?.trim().to_string();
+    let input = match value.get("action_input") {
+        Some(Value::String(input)) => input.clone(),
+        Some(input) => input.to_string(),
+        None => "{}".to_string(),
+    };
+    if name.eq_ignore_ascii_case("final answer") {
+        return Some(TextStep::FinalAnswer(input));
+    }
+    Some(TextStep::Action { name, input })
+}
+
+/// Content of a fenced code block, or the trimmed text when there is none
+fn strip_fence(text: &str) -> String {
+    let text = text.trim();
+    let Some(start) = text.find("```") else {
+        return text.to_string();
+    };
+    let body = &text[start + 3..];
+    // Skip the language of the block, such as `json`
+    let body = body.split_once('\n').map_or(body, |(_, body)| body);
+    match body.find("```") {
+        Some(end) => body[..end].trim().to_string(),
+        None => body.trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(name: &str, input: &str) -> TextStep {
+        TextStep::Action {
+            name: name.to_string(),
+            input: input.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_action_lines() {
+        let response = "Thought: I need the weather\n\
+                        Action: get_weather\n\
+                        Action Input: {\"city\": \"Paris\"}\n\
+                        Observation: Sunny\n\
+                        Final Answer: It is sunny";
+        assert_eq!(
+            parse_response(response),
+            action("get_weather", r#"{"city": "Paris"}"#)
+        );
+
+        let fenced = "Action: `search`\nAction Input:\n```json\n{\"query\": \"rust\"}\n```";
+        assert_eq!(
+            parse_response(fenced),
+            action("search", r#"{"query": "rust"}"#)
+        );
+    }
+
+    #[test]
+    fn test_parse_action_block() {
+        let response = "I will look it up.\n```json\n\
+                        {\"action\": \"search\", \"action_input\": {\"query\": \"rust\"}}\n```";
+        assert_eq!(
+            parse_response(response),
+            action("search", r#"{"query":"rust"}"#)
+        );
+
+        let answer = r#"{"action": "Final Answer", "action_input": "42"}"#;
+        assert_eq!(
+            parse_response(answer),
+            TextStep::FinalAnswer("42".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_final_answer() {
+        assert_eq!(
+            parse_response("Thought: I know it\nFinal Answer: Paris"),
+            TextStep::FinalAnswer("Paris".to_string())
+        );
+        // Structured answers are not mistaken for action blocks
+        assert_eq!(
+            parse_response(r#"{"value": 1}"#),
+            TextStep::FinalAnswer(r#"{"value": 1}"#.to_string())
+        );
+    }
+}
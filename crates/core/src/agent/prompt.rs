@@ -0,0 +1,173 @@
+use crate::runtime::Task;
+use crate::tool::ToolT;
+use minijinja::{context, Environment};
+use serde::Serialize;
+use thiserror::Error;
+
+use super::AgentConfig;
+
+/// Error type for system prompt templates
+#[derive(Debug, Error)]
+pub enum PromptError {
+    #[error("System prompt template error: {0}")]
+    Template(#[from] minijinja::Error),
+}
+
+#[derive(Serialize)]
+struct ToolInfo {
+    name: &'static str,
+    description: &'static str,
+}
+
+/// Check that a system prompt template parses
+pub fn validate_system_prompt(template: &str) -> Result<(), PromptError> {
+    Environment::new().template_from_str(template)?;
+    Ok(())
+}
+
+/// Render a system prompt template for a task.
+///
+/// Templates use Jinja syntax and see the variables:
+/// - `agent`: `name`, `description` and `id` of the agent
+/// - `tools`: list of the agent's tools, each with a `name` and `description`
+/// - `task`: the context map of the task, see [`Task::with_context`]
+/// - `prompt`: the prompt of the task
+/// - `date`: the current date as `YYYY-MM-DD`
+///
+/// Undefined variables render as empty strings.
+pub fn render_system_prompt(
+    template: &str,
+    agent_config: &AgentConfig,
+    tools: &[Box<dyn ToolT>],
+    task: &Task,
+) -> Result<String, PromptError> {
+    let tools: Vec<ToolInfo> = tools
+        .iter()
+        .map(|tool| ToolInfo {
+            name: tool.name(),
+            description: tool.description(),
+        })
+        .collect();
+    let env = Environment::new();
+    let rendered = env.render_str(
+        template,
+        context! {
+            agent => context! {
+                name => agent_config.name,
+                description => agent_config.description,
+                id => agent_config.id.to_string(),
+            },
+            tools => tools,
+            task => task.context,
+            prompt => task.prompt,
+            date => chrono::Local::now().format("%Y-%m-%d").to_string(),
+        },
+    )?;
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::prebuilt::react::ReActExecutor;
+    use crate::agent::{AgentDeriveT, BaseAgent, IntoRunnable};
+    use autoagents_llm::chat::ChatRole;
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+    use serde_json::Value;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_render_system_prompt() {
+        let config = AgentConfig {
+            name: "support".to_string(),
+            description: "Answers support requests".to_string(),
+            id: Uuid::new_v4(),
+            output_schema: None,
+            checkpoint_store: None,
+        };
+        let task = Task::new("Where is my order?", None)
+            .with_context("customer", "Ada")
+            .with_context("orders", serde_json::json!([17, 42]));
+        let rendered = render_system_prompt(
+            "You are {{ agent.name }}. {{ agent.description }}.\n\
+             Customer: {{ task.customer }}{% for o in task.orders %} #{{ o }}{% endfor %}\n\
+             Tools: {{ tools | length }}{{ task.missing }}\n\
+             Today is {{ date }}.",
+            &config,
+            &[],
+            &task,
+        )
+        .unwrap();
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        assert_eq!(
+            rendered,
+            format!(
+                "You are support. Answers support requests.\n\
+                 Customer: Ada #17 #42\n\
+                 Tools: 0\n\
+                 Today is {today}."
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        assert!(validate_system_prompt("Hello {{ agent.name }}").is_ok());
+        assert!(matches!(
+            validate_system_prompt("Hello {% if %}"),
+            Err(PromptError::Template(_))
+        ));
+    }
+
+    #[derive(Debug)]
+    struct GreeterAgent;
+
+    impl ReActExecutor for GreeterAgent {}
+
+    impl AgentDeriveT for GreeterAgent {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "Greets customers"
+        }
+
+        fn output_schema(&self) -> Option<Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "greeter"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![]
+        }
+
+        fn system_prompt(&self) -> Option<&'static str> {
+            Some("{{ agent.description }} in {{ task.language | default('English') }}.")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_renders_system_prompt_per_task() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec!["Bonjour", "Hello"]));
+        let agent = BaseAgent::new(GreeterAgent, llm.clone(), None).into_runnable();
+        let (tx, _rx) = mpsc::channel(100);
+        agent
+            .clone()
+            .run(
+                Task::new("Greet Ada", None).with_context("language", "French"),
+                tx.clone(),
+            )
+            .await
+            .unwrap();
+        agent.run(Task::new("Greet Bob", None), tx).await.unwrap();
+
+        let requests = llm.requests();
+        assert_eq!(requests[0][0].role, ChatRole::System);
+        assert_eq!(requests[0][0].content, "Greets customers in French.");
+        assert_eq!(requests[1][0].content, "Greets customers in English.");
+    }
+}
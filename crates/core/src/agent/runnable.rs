@@ -1,41 +1,27 @@
-use super::base::{AgentDeriveT, BaseAgent};
+use super::base::{AgentConfig, AgentDeriveT, BaseAgent};
 use super::error::RunnableAgentError;
+use super::executor::AgentExecutor;
+use super::state::AgentState;
+use crate::budget::{Budget, BudgetUsage, BudgetedLLMProvider};
+use crate::checkpoint::{BudgetedCheckpointStore, CheckpointError};
 use crate::error::Error;
+use crate::fallback::{FallbackLLMProvider, NamedLLMProvider, PRIMARY_PROVIDER};
+use crate::guardrail::{GuardrailChain, GuardrailStage};
 use crate::memory::MemoryProvider;
-use crate::protocol::{Event, TaskResult};
-use crate::runtime::Task;
-use crate::tool::ToolCallResult;
+use crate::middleware::{MiddlewareChain, MiddlewareContext};
+use crate::protocol::{Event, SubmissionId, TaskResult};
+use crate::runtime::{AgentInfo, RuntimeError, Task};
+use crate::tool::{CapabilitySet, ToolT};
 use async_trait::async_trait;
+use autoagents_llm::LLMProvider;
 use serde_json::Value;
 use std::fmt::Debug;
+use std::future::Future;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
 use tokio::task::JoinHandle;
 use uuid::Uuid;
 
-/// State tracking for agent execution
-#[derive(Debug, Default, Clone)]
-pub struct AgentState {
-    /// Tool calls made during execution
-    pub tool_calls: Vec<ToolCallResult>,
-    /// Tasks that have been executed
-    pub task_history: Vec<Task>,
-}
-
-impl AgentState {
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    pub fn record_tool_call(&mut self, tool_call: ToolCallResult) {
-        self.tool_calls.push(tool_call);
-    }
-
-    pub fn record_task(&mut self, task: Task) {
-        self.task_history.push(task);
-    }
-}
-
 /// Trait for agents that can be executed within the system
 #[async_trait]
 pub trait RunnableAgent: Send + Sync + 'static + Debug {
@@ -47,6 +33,46 @@ pub trait RunnableAgent: Send + Sync + 'static + Debug {
 
     fn memory(&self) -> Option<Arc<RwLock<Box<dyn MemoryProvider>>>>;
 
+    /// Names of the tools the agent can call
+    fn tool_names(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Capabilities the agent's tool calls can use, every capability unless the agent says
+    /// otherwise
+    fn capabilities(&self) -> CapabilitySet {
+        CapabilitySet::all()
+    }
+
+    /// Whether the agent forwards its tasks to other agents, like a
+    /// [`RouterAgent`](crate::agent::prebuilt::router::RouterAgent)
+    fn dispatches_tasks(&self) -> bool {
+        false
+    }
+
+    fn info(&self) -> AgentInfo {
+        AgentInfo {
+            id: self.id(),
+            name: self.name().to_string(),
+            description: self.description().to_string(),
+            tools: self.tool_names().into_iter().map(String::from).collect(),
+        }
+    }
+
+    /// Copy of the agent's state, `None` for agents that keep no state
+    async fn state_snapshot(&self) -> Option<AgentState> {
+        None
+    }
+
+    /// Continue a submission from its last checkpoint, see [`crate::checkpoint`]
+    async fn resume(
+        self: Arc<Self>,
+        _sub_id: SubmissionId,
+        _tx_event: mpsc::Sender<Event>,
+    ) -> Result<(), Error> {
+        Err(CheckpointError::NoStore(self.name().to_string()).into())
+    }
+
     fn spawn_task(
         self: Arc<Self>,
         task: Task,
@@ -71,7 +97,6 @@ impl<T: AgentDeriveT> RunnableAgentImpl<T> {
         }
     }
 
-    #[allow(dead_code)]
     pub fn state(&self) -> Arc<RwLock<AgentState>> {
         self.state.clone()
     }
@@ -98,50 +123,375 @@ where
         self.agent.memory()
     }
 
-    async fn run(self: Arc<Self>, task: Task, tx_event: mpsc::Sender<Event>) -> Result<(), Error> {
+    fn tool_names(&self) -> Vec<&'static str> {
+        self.agent.tools().iter().map(|tool| tool.name()).collect()
+    }
+
+    /// Capabilities of the agent's tools, within the capabilities the agent was granted
+    fn capabilities(&self) -> CapabilitySet {
+        let used: CapabilitySet = self
+            .agent
+            .tools()
+            .iter()
+            .flat_map(|tool| tool.capabilities())
+            .collect();
+        match &self.agent.capabilities {
+            Some(granted) => used.intersection(granted),
+            None => used,
+        }
+    }
+
+    async fn state_snapshot(&self) -> Option<AgentState> {
+        Some(self.state().read().await.clone())
+    }
+
+    async fn run(
+        self: Arc<Self>,
+        mut task: Task,
+        tx_event: mpsc::Sender<Event>,
+    ) -> Result<(), Error> {
+        let mut submission =
+            self.submission(task.submission_id, task.budget.as_deref(), None, tx_event);
+        if let Some(chain) = &submission.guardrails {
+            // A blocked task never starts, the middleware does not see it
+            if let Err(e) = chain.check_task(&mut task).await {
+                return submission.fail(e.into()).await;
+            }
+        }
+        if let Err(e) = self.agent.check_tool_names() {
+            return submission.fail(e.into()).await;
+        }
+        let config = match self.agent.task_config(&task) {
+            Ok(config) => submission.checkpointing(config),
+            Err(e) => return submission.fail(e.into()).await,
+        };
+        submission.middleware = self.middleware(task.submission_id);
+        if let Some(chain) = &submission.middleware {
+            chain.on_task_start(&task).await;
+        }
         // Execute the agent's logic using the executor
-        match self
+        let result = self
             .agent
             .inner()
             .execute(
-                self.agent.llm(),
+                self.llm(&submission),
+                self.agent.memory(),
+                self.tools(&submission),
+                &config,
+                task,
+                self.state.clone(),
+                submission.tx_event.clone(),
+            )
+            .await;
+        Self::complete(submission, result).await
+    }
+
+    async fn resume(
+        self: Arc<Self>,
+        sub_id: SubmissionId,
+        tx_event: mpsc::Sender<Event>,
+    ) -> Result<(), Error> {
+        let store = self
+            .agent
+            .checkpoint_store
+            .clone()
+            .ok_or_else(|| CheckpointError::NoStore(self.name().to_string()))?;
+        let checkpoint = store
+            .load(sub_id)
+            .await?
+            .ok_or(CheckpointError::NotFound(sub_id))?;
+        // The prompt of the checkpointed task already went through the input guardrails
+        // The budget goes on from the usage of the interrupted run
+        let mut submission = self.submission(
+            sub_id,
+            checkpoint.task.budget.as_deref(),
+            checkpoint.budget_usage.clone(),
+            tx_event,
+        );
+        if let Err(e) = self.agent.check_tool_names() {
+            return submission.fail(e.into()).await;
+        }
+        let config = match self.agent.task_config(&checkpoint.task) {
+            Ok(config) => submission.checkpointing(config),
+            Err(e) => return submission.fail(e.into()).await,
+        };
+        submission.middleware = self.middleware(sub_id);
+        if let Some(chain) = &submission.middleware {
+            chain.on_task_start(&checkpoint.task).await;
+        }
+        let result = self
+            .agent
+            .inner()
+            .resume(
+                self.llm(&submission),
                 self.agent.memory(),
-                self.agent.tools(),
-                &self.agent.agent_config(),
-                task.clone(),
+                self.tools(&submission),
+                &config,
+                checkpoint,
                 self.state.clone(),
-                tx_event.clone(),
+                submission.tx_event.clone(),
             )
-            .await
-        {
+            .await;
+        Self::complete(submission, result).await
+    }
+}
+
+/// Everything wrapped around the executor while it works on one submission
+struct Submission {
+    sub_id: SubmissionId,
+    tx_event: mpsc::Sender<Event>,
+    budgeted: Option<Arc<BudgetedLLMProvider>>,
+    guardrails: Option<GuardrailChain>,
+    middleware: Option<MiddlewareChain>,
+}
+
+impl Submission {
+    /// Fail the submission with `error`
+    async fn fail(&self, error: Error) -> Result<(), Error> {
+        let _ = self
+            .tx_event
+            .send(Event::TaskComplete {
+                sub_id: self.sub_id,
+                result: TaskResult::Failure(error.to_string()),
+            })
+            .await;
+        Err(error)
+    }
+
+    /// Config saving the budget usage of the submission along with its checkpoints
+    fn checkpointing(&self, mut config: AgentConfig) -> AgentConfig {
+        if let (Some(store), Some(budgeted)) = (config.checkpoint_store.take(), &self.budgeted) {
+            config.checkpoint_store = Some(Arc::new(BudgetedCheckpointStore::new(
+                store,
+                budgeted.clone(),
+            )));
+        }
+        config
+    }
+}
+
+impl<T: AgentDeriveT> RunnableAgentImpl<T> {
+    /// Wrappers for a submission, without middleware until the task starts
+    fn submission(
+        &self,
+        sub_id: SubmissionId,
+        task_budget: Option<&Budget>,
+        usage: Option<BudgetUsage>,
+        tx_event: mpsc::Sender<Event>,
+    ) -> Submission {
+        Submission {
+            sub_id,
+            budgeted: self.budgeted_llm(task_budget, usage, sub_id, &tx_event),
+            guardrails: (!self.agent.guardrails.is_empty())
+                .then(|| GuardrailChain::new(&self.agent.guardrails, sub_id, tx_event.clone())),
+            middleware: None,
+            tx_event,
+        }
+    }
+
+    /// Provider counting the usage of a task against its budget, or the agent's budget,
+    /// from the `usage` of a previous run of the task
+    fn budgeted_llm(
+        &self,
+        task_budget: Option<&Budget>,
+        usage: Option<BudgetUsage>,
+        sub_id: SubmissionId,
+        tx_event: &mpsc::Sender<Event>,
+    ) -> Option<Arc<BudgetedLLMProvider>> {
+        let budget = task_budget.or(self.agent.budget.as_ref())?;
+        Some(Arc::new(
+            BudgetedLLMProvider::new(self.provider(sub_id, tx_event), budget.clone())
+                .with_usage(usage.unwrap_or_default())
+                .with_events(sub_id, tx_event.clone()),
+        ))
+    }
+
+    /// The agent's LLM, behind its fallback chain if it has one
+    fn provider(
+        &self,
+        sub_id: SubmissionId,
+        tx_event: &mpsc::Sender<Event>,
+    ) -> Arc<dyn LLMProvider> {
+        if self.agent.fallback_llms.is_empty() {
+            return self.agent.llm();
+        }
+        let mut providers = vec![NamedLLMProvider::new(PRIMARY_PROVIDER, self.agent.llm())];
+        providers.extend(self.agent.fallback_llms.iter().cloned());
+        Arc::new(
+            FallbackLLMProvider::new(providers, self.agent.fallback_policy)
+                .with_events(sub_id, tx_event.clone()),
+        )
+    }
+
+    fn middleware(&self, sub_id: SubmissionId) -> Option<MiddlewareChain> {
+        (!self.agent.middleware.is_empty()).then(|| {
+            MiddlewareChain::new(
+                &self.agent.middleware,
+                MiddlewareContext {
+                    sub_id,
+                    agent_id: self.agent.id,
+                    agent_name: self.agent.name(),
+                },
+            )
+        })
+    }
+
+    /// The LLM of a submission, counting against its budget if it has one
+    fn budgeted_or_provider(&self, submission: &Submission) -> Arc<dyn LLMProvider> {
+        match &submission.budgeted {
+            Some(llm) => llm.clone(),
+            None => self.provider(submission.sub_id, &submission.tx_event),
+        }
+    }
+
+    /// The LLM handed to the executor, calls skipped by middleware do not count against the budget
+    fn llm(&self, submission: &Submission) -> Arc<dyn LLMProvider> {
+        let llm = self.budgeted_or_provider(submission);
+        match &submission.middleware {
+            Some(chain) => chain.wrap_llm(llm),
+            None => llm,
+        }
+    }
+
+    /// The tools handed to the executor. Calls beyond the agent's capabilities are refused,
+    /// results are limited in size, guardrails check the limited results and middleware sees
+    /// them after the guardrails
+    fn tools(&self, submission: &Submission) -> Vec<Box<dyn ToolT>> {
+        let mut tools = self.agent.tools();
+        if let Some(capabilities) = &self.agent.capabilities {
+            tools = capabilities.restrict_tools(tools);
+        }
+        if let Some(limits) = &self.agent.tool_output_limits {
+            // Summaries count against the budget of the task
+            tools = limits.limit_tools(tools, self.budgeted_or_provider(submission));
+        }
+        if let Some(chain) = &submission.guardrails {
+            tools = chain.guard_tools(tools);
+        }
+        match &submission.middleware {
+            Some(chain) => chain.wrap_tools(tools),
+            None => tools,
+        }
+    }
+
+    /// Report the outcome of the executor as the completion of the submission
+    async fn complete(
+        submission: Submission,
+        result: Result<<T as AgentExecutor>::Output, <T as AgentExecutor>::Error>,
+    ) -> Result<(), Error> {
+        let outcome: Result<Value, Error> = match result {
             Ok(output) => {
                 // Convert output to Value
                 let value: Value = output.into();
+                match &submission.guardrails {
+                    Some(chain) => chain
+                        .check_value(GuardrailStage::Output, "", value)
+                        .await
+                        .map_err(Error::from),
+                    None => Ok(value),
+                }
+            }
+            // A spent budget is why the executor failed, report it as such
+            Err(e) => Err(
+                match submission.budgeted.as_ref().and_then(|llm| llm.exceeded()) {
+                    Some(budget_error) => budget_error.into(),
+                    None => RunnableAgentError::ExecutorError(e.to_string()).into(),
+                },
+            ),
+        };
+        if let Some(chain) = &submission.middleware {
+            let result = match &outcome {
+                Ok(value) => TaskResult::Value(value.clone()),
+                Err(e) => TaskResult::Failure(e.to_string()),
+            };
+            chain.on_task_end(&result).await;
+        }
 
+        match outcome {
+            Ok(value) => {
                 // Send completion event
-                let _ = tx_event
+                submission
+                    .tx_event
                     .send(Event::TaskComplete {
-                        sub_id: task.submission_id,
+                        sub_id: submission.sub_id,
                         result: TaskResult::Value(value),
                     })
                     .await
                     .map_err(RunnableAgentError::event_send_error)?;
-
                 Ok(())
             }
-            Err(e) => {
-                // Send error event
-                let error_msg = e.to_string();
-                let _ = tx_event
-                    .send(Event::TaskComplete {
-                        sub_id: task.submission_id,
-                        result: TaskResult::Failure(error_msg.clone()),
-                    })
-                    .await;
+            Err(error) => submission.fail(error).await,
+        }
+    }
+}
 
-                Err(RunnableAgentError::ExecutorError(error_msg).into())
+/// Run `agent` on `task` and wait for the task's result.
+///
+/// Every event of the agent is passed through `forward` and, unless it returns `None`,
+/// sent on to `tx_event`.
+pub(crate) async fn run_for_result<F>(
+    agent: Arc<dyn RunnableAgent>,
+    task: Task,
+    tx_event: mpsc::Sender<Event>,
+    forward: F,
+) -> Result<TaskResult, Error>
+where
+    F: Fn(Event) -> Option<Event> + Send + 'static,
+{
+    let sub_id = task.submission_id;
+    wait_for_result(sub_id, tx_event, forward, |tx| agent.run(task, tx)).await
+}
+
+/// Resume `sub_id` on `agent` and wait for the task's result, see [`run_for_result`]
+pub(crate) async fn resume_for_result<F>(
+    agent: Arc<dyn RunnableAgent>,
+    sub_id: SubmissionId,
+    tx_event: mpsc::Sender<Event>,
+    forward: F,
+) -> Result<TaskResult, Error>
+where
+    F: Fn(Event) -> Option<Event> + Send + 'static,
+{
+    wait_for_result(sub_id, tx_event, forward, |tx| agent.resume(sub_id, tx)).await
+}
+
+async fn wait_for_result<F, R, Fut>(
+    sub_id: SubmissionId,
+    tx_event: mpsc::Sender<Event>,
+    forward: F,
+    run: R,
+) -> Result<TaskResult, Error>
+where
+    F: Fn(Event) -> Option<Event> + Send + 'static,
+    R: FnOnce(mpsc::Sender<Event>) -> Fut,
+    Fut: Future<Output = Result<(), Error>>,
+{
+    let (tx, mut rx) = mpsc::channel(100);
+    let forwarder = tokio::spawn(async move {
+        let mut result = None;
+        while let Some(event) = rx.recv().await {
+            if let Event::TaskComplete {
+                sub_id: completed,
+                result: task_result,
+            } = &event
+            {
+                if *completed == sub_id {
+                    result = Some(task_result.clone());
+                }
+            }
+            if let Some(event) = forward(event) {
+                let _ = tx_event.send(event).await;
             }
         }
+        result
+    });
+
+    let run_result = run(tx).await;
+    let result = forwarder.await.map_err(RuntimeError::TaskJoinError)?;
+    match (result, run_result) {
+        (Some(result), _) => Ok(result),
+        (None, Err(e)) => Err(e),
+        (None, Ok(())) => Ok(TaskResult::Aborted),
     }
 }
 
@@ -0,0 +1,47 @@
+use crate::runtime::Task;
+use crate::tool::ToolCallResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// State tracking for agent execution
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AgentState {
+    /// Tool calls made during execution
+    pub tool_calls: Vec<ToolCallResult>,
+    /// Tasks that have been executed
+    pub task_history: Vec<Task>,
+    /// Critique rounds made by the reflection executor
+    pub reflection_rounds: Vec<ReflectionRound>,
+}
+
+impl AgentState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_tool_call(&mut self, tool_call: ToolCallResult) {
+        self.tool_calls.push(tool_call);
+    }
+
+    pub fn record_task(&mut self, task: Task) {
+        self.task_history.push(task);
+    }
+
+    pub fn record_reflection_round(&mut self, round: ReflectionRound) {
+        self.reflection_rounds.push(round);
+    }
+}
+
+/// A single critique of a draft, recorded by the
+/// [`Reflection`](super::prebuilt::reflection::Reflection) executor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReflectionRound {
+    /// Round number, starting at 1
+    pub round: usize,
+    /// The draft that was reviewed
+    pub draft: Value,
+    /// Whether the critic approved the draft
+    pub approved: bool,
+    /// The critic's feedback
+    pub feedback: String,
+}
@@ -0,0 +1,349 @@
+use super::runnable::RunnableAgent;
+use crate::protocol::{Event, TaskResult};
+use crate::runtime::Task;
+use crate::tool::{Capability, ToolCallContext, ToolCallError, ToolInputT, ToolRuntime, ToolT};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+/// Error type for agents called as tools
+#[derive(Debug, Error)]
+pub enum AgentToolError {
+    #[error("Agent tool '{0}' can only be called by an async executor")]
+    SyncCall(&'static str),
+
+    #[error("Agent '{agent}' failed: {reason}")]
+    TaskFailed { agent: &'static str, reason: String },
+
+    #[error("Agent '{0}' aborted the task")]
+    Aborted(&'static str),
+
+    #[error("Agent '{0}' finished without a result")]
+    NoResult(&'static str),
+
+    #[error("Input schema of agent tool '{agent}' is not valid JSON: {reason}")]
+    InvalidInputSchema { agent: &'static str, reason: String },
+}
+
+impl From<AgentToolError> for ToolCallError {
+    fn from(error: AgentToolError) -> Self {
+        ToolCallError::RuntimeError(Box::new(error))
+    }
+}
+
+/// Exposes an agent as a tool, so a manager agent can delegate work to it.
+///
+/// By default the tool takes a single `task` string which becomes the prompt of the
+/// sub-agent's task. With [`AgentTool::with_input`] the tool takes a typed input instead,
+/// which is handed to the sub-agent as JSON. Events of the sub-agent are forwarded to the
+/// caller wrapped in [`Event::SubAgentEvent`], under the submission of the calling task.
+#[derive(Debug, Clone)]
+pub struct AgentTool {
+    agent: Arc<dyn RunnableAgent>,
+    input_schema: Option<Value>,
+}
+
+impl AgentTool {
+    pub fn new(agent: Arc<dyn RunnableAgent>) -> Self {
+        Self {
+            agent,
+            input_schema: None,
+        }
+    }
+
+    /// Take a typed input instead of a task string, fails if the schema of the input is not
+    /// valid JSON
+    pub fn with_input<I: ToolInputT>(mut self) -> Result<Self, AgentToolError> {
+        let schema = serde_json::from_str(I::io_schema()).map_err(|e| {
+            AgentToolError::InvalidInputSchema {
+                agent: self.agent.name(),
+                reason: e.to_string(),
+            }
+        })?;
+        self.input_schema = Some(schema);
+        Ok(self)
+    }
+
+    pub fn agent(&self) -> Arc<dyn RunnableAgent> {
+        self.agent.clone()
+    }
+
+    fn prompt(&self, args: Value) -> String {
+        match (&self.input_schema, args) {
+            (None, Value::Object(mut map)) => match map.remove("task") {
+                Some(Value::String(task)) => task,
+                Some(other) => other.to_string(),
+                None => Value::Object(map).to_string(),
+            },
+            (_, args) => args.to_string(),
+        }
+    }
+}
+
+impl ToolRuntime for AgentTool {
+    fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+        Err(AgentToolError::SyncCall(self.agent.name()).into())
+    }
+}
+
+#[async_trait]
+impl ToolT for AgentTool {
+    fn name(&self) -> &'static str {
+        self.agent.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.agent.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.input_schema.clone().unwrap_or_else(|| {
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "task": {
+                        "type": "string",
+                        "description": "The task to delegate to the agent"
+                    }
+                },
+                "required": ["task"]
+            })
+        })
+    }
+
+    /// A call can do anything the tools of the agent can, so callers need all of their
+    /// capabilities
+    fn capabilities(&self) -> Vec<Capability> {
+        self.agent.capabilities().iter().collect()
+    }
+
+    async fn call(&self, args: Value, context: ToolCallContext) -> Result<Value, ToolCallError> {
+        let name = self.agent.name();
+        let agent_id = self.agent.id();
+        let task = Task::new(self.prompt(args), Some(agent_id));
+        let sub_id = task.submission_id;
+
+        let (tx, mut rx) = mpsc::channel(100);
+        let parent_tx = context.tx_event.clone();
+        let forwarder = tokio::spawn(async move {
+            let mut result = None;
+            while let Some(event) = rx.recv().await {
+                if let Event::TaskComplete {
+                    sub_id: completed,
+                    result: task_result,
+                } = &event
+                {
+                    if *completed == sub_id {
+                        result = Some(task_result.clone());
+                    }
+                }
+                let _ = parent_tx
+                    .send(Event::SubAgentEvent {
+                        parent_sub_id: context.sub_id,
+                        agent_id,
+                        event: Box::new(event),
+                    })
+                    .await;
+            }
+            result
+        });
+
+        let run_result = self.agent.clone().run(task, tx).await;
+        let result = forwarder.await.ok().flatten();
+
+        match (result, run_result) {
+            (Some(TaskResult::Value(value)), _) => Ok(value),
+            (Some(TaskResult::Failure(reason)), _) => Err(AgentToolError::TaskFailed {
+                agent: name,
+                reason,
+            }
+            .into()),
+            (Some(TaskResult::Aborted), _) => Err(AgentToolError::Aborted(name).into()),
+            (None, Err(e)) => Err(AgentToolError::TaskFailed {
+                agent: name,
+                reason: e.to_string(),
+            }
+            .into()),
+            (None, Ok(())) => Err(AgentToolError::NoResult(name).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::memory::MemoryProvider;
+    use crate::protocol::SubmissionId;
+    use tokio::sync::RwLock;
+    use uuid::Uuid;
+
+    #[derive(Debug)]
+    struct UppercaseAgent {
+        id: Uuid,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl RunnableAgent for UppercaseAgent {
+        fn name(&self) -> &'static str {
+            "uppercase"
+        }
+
+        fn description(&self) -> &'static str {
+            "Uppercases the task"
+        }
+
+        fn id(&self) -> Uuid {
+            self.id
+        }
+
+        fn memory(&self) -> Option<Arc<RwLock<Box<dyn MemoryProvider>>>> {
+            None
+        }
+
+        async fn run(
+            self: Arc<Self>,
+            task: Task,
+            tx_event: mpsc::Sender<Event>,
+        ) -> Result<(), Error> {
+            let _ = tx_event
+                .send(Event::TaskStarted {
+                    sub_id: task.submission_id,
+                    agent_id: self.id,
+                    task_description: task.prompt.clone(),
+                })
+                .await;
+            let result = if self.fail {
+                TaskResult::Failure("cannot uppercase".to_string())
+            } else {
+                TaskResult::Value(Value::String(task.prompt.to_uppercase()))
+            };
+            let _ = tx_event
+                .send(Event::TaskComplete {
+                    sub_id: task.submission_id,
+                    result,
+                })
+                .await;
+            Ok(())
+        }
+    }
+
+    fn agent_tool(fail: bool) -> AgentTool {
+        AgentTool::new(Arc::new(UppercaseAgent {
+            id: Uuid::new_v4(),
+            fail,
+        }))
+    }
+
+    fn context(sub_id: SubmissionId) -> (ToolCallContext, mpsc::Receiver<Event>) {
+        let (tx, rx) = mpsc::channel(10);
+        (
+            ToolCallContext {
+                sub_id,
+                call_id: "call_1".to_string(),
+                tx_event: tx,
+                memory: None,
+                capabilities: None,
+            },
+            rx,
+        )
+    }
+
+    #[test]
+    fn test_agent_tool_metadata() {
+        let tool = agent_tool(false);
+        assert_eq!(tool.name(), "uppercase");
+        assert_eq!(tool.description(), "Uppercases the task");
+        assert!(tool
+            .validate_args(&serde_json::json!({"task": "hello"}))
+            .is_ok());
+        assert!(tool.validate_args(&serde_json::json!({})).is_err());
+        assert!(tool.run(serde_json::json!({"task": "hello"})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_agent_tool_call_nests_events() {
+        let parent = Uuid::new_v4();
+        let (ctx, mut rx) = context(parent);
+
+        let result = agent_tool(false)
+            .call(serde_json::json!({"task": "hello"}), ctx)
+            .await
+            .unwrap();
+        assert_eq!(result, "HELLO");
+
+        let mut nested = vec![];
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                Event::SubAgentEvent {
+                    parent_sub_id,
+                    event,
+                    ..
+                } => {
+                    assert_eq!(parent_sub_id, parent);
+                    nested.push(*event);
+                }
+                other => panic!("Expected SubAgentEvent, got {other:?}"),
+            }
+        }
+        assert!(matches!(nested[0], Event::TaskStarted { .. }));
+        assert!(matches!(nested[1], Event::TaskComplete { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_agent_tool_call_failure() {
+        let (ctx, _rx) = context(Uuid::new_v4());
+        let err = agent_tool(true)
+            .call(serde_json::json!({"task": "hello"}), ctx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("cannot uppercase"));
+    }
+
+    #[derive(Debug)]
+    struct Query;
+
+    impl ToolInputT for Query {
+        fn io_schema() -> &'static str {
+            r#"{"type":"object","properties":{"city":{"type":"string"}},"required":["city"]}"#
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_tool_typed_input() {
+        let tool = agent_tool(false).with_input::<Query>().unwrap();
+        assert_eq!(tool.args_schema()["required"][0], "city");
+
+        let (ctx, _rx) = context(Uuid::new_v4());
+        let result = tool
+            .call(serde_json::json!({"city": "paris"}), ctx)
+            .await
+            .unwrap();
+        assert_eq!(result, r#"{"CITY":"PARIS"}"#);
+    }
+
+    #[derive(Debug)]
+    struct BrokenQuery;
+
+    impl ToolInputT for BrokenQuery {
+        fn io_schema() -> &'static str {
+            r#"{"type":"object","properties":"#
+        }
+    }
+
+    #[test]
+    fn test_agent_tool_rejects_invalid_input_schema() {
+        let error = agent_tool(false).with_input::<BrokenQuery>().unwrap_err();
+        assert!(matches!(
+            error,
+            AgentToolError::InvalidInputSchema {
+                agent: "uppercase",
+                ..
+            }
+        ));
+    }
+}
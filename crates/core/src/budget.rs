@@ -0,0 +1,631 @@
+//! Token and cost budgets for tasks.
+//!
+//! A [`Budget`] set on a [`Task`](crate::runtime::Task) or on an agent through
+//! [`AgentBuilder::with_budget`](crate::agent::AgentBuilder::with_budget) is enforced by
+//! wrapping the agent's LLM in a [`BudgetedLLMProvider`]. Every call the executor makes is
+//! counted, and once a limit is reached further calls are refused, which ends the task
+//! with a [`BudgetError`] and an [`Event::BudgetExceeded`].
+use crate::protocol::{Event, SubmissionId};
+use async_trait::async_trait;
+use autoagents_llm::chat::{
+    ChatMessage, ChatProvider, ChatResponse, StructuredOutputFormat, Tool, Usage,
+};
+use autoagents_llm::completion::{CompletionProvider, CompletionRequest, CompletionResponse};
+use autoagents_llm::embedding::EmbeddingProvider;
+use autoagents_llm::error::LLMError;
+use autoagents_llm::models::{ModelListRequest, ModelListResponse, ModelsProvider};
+use autoagents_llm::LLMProvider;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+/// Characters per token used to estimate usage when a provider does not report it
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Error type for budgets
+#[derive(Debug, Clone, Error)]
+pub enum BudgetError {
+    #[error("Budget exceeded: {limit} limit reached after {} calls", usage.calls)]
+    Exceeded {
+        limit: BudgetLimit,
+        usage: BudgetUsage,
+    },
+
+    #[error("Budget has a cost limit but no price for model '{0}'")]
+    NoPrice(String),
+}
+
+/// The limit of a budget that was reached
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BudgetLimit {
+    InputTokens(u64),
+    OutputTokens(u64),
+    CostUsd(f64),
+}
+
+impl Display for BudgetLimit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetLimit::InputTokens(max) => write!(f, "{max} input tokens"),
+            BudgetLimit::OutputTokens(max) => write!(f, "{max} output tokens"),
+            BudgetLimit::CostUsd(max) => write!(f, "${max:.4}"),
+        }
+    }
+}
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+impl ModelPrice {
+    pub fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+        }
+    }
+
+    /// Cost of `usage` in USD
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.input_tokens as f64 * self.input_per_million
+            + usage.output_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Prices of models by name.
+///
+/// The default table holds list prices of common hosted models. A model is looked up by
+/// its exact name first and then by the longest known prefix, so dated snapshots such as
+/// `gpt-4o-2024-08-06` resolve to the price of `gpt-4o`.
+#[derive(Debug, Clone)]
+pub struct PriceTable {
+    prices: HashMap<String, ModelPrice>,
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        [
+            ("gpt-4o", 2.5, 10.0),
+            ("gpt-4o-mini", 0.15, 0.6),
+            ("gpt-4.1", 2.0, 8.0),
+            ("gpt-4.1-mini", 0.4, 1.6),
+            ("gpt-4.1-nano", 0.1, 0.4),
+            ("o3-mini", 1.1, 4.4),
+            ("claude-3-5-haiku", 0.8, 4.0),
+            ("claude-3-5-sonnet", 3.0, 15.0),
+            ("claude-3-7-sonnet", 3.0, 15.0),
+            ("claude-sonnet-4", 3.0, 15.0),
+            ("claude-opus-4", 15.0, 75.0),
+            ("gemini-1.5-flash", 0.075, 0.3),
+            ("gemini-1.5-pro", 1.25, 5.0),
+            ("gemini-2.0-flash", 0.1, 0.4),
+            ("deepseek-chat", 0.27, 1.1),
+            ("deepseek-reasoner", 0.55, 2.19),
+        ]
+        .into_iter()
+        .fold(Self::empty(), |table, (model, input, output)| {
+            table.with_price(model, ModelPrice::new(input, output))
+        })
+    }
+}
+
+impl PriceTable {
+    /// A table without any price
+    pub fn empty() -> Self {
+        Self {
+            prices: HashMap::new(),
+        }
+    }
+
+    /// Set the price of a model, replacing a known price
+    pub fn with_price<S: Into<String>>(mut self, model: S, price: ModelPrice) -> Self {
+        self.prices.insert(model.into(), price);
+        self
+    }
+
+    pub fn price(&self, model: &str) -> Option<ModelPrice> {
+        self.prices.get(model).copied().or_else(|| {
+            self.prices
+                .iter()
+                .filter(|(name, _)| model.starts_with(name.as_str()))
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, price)| *price)
+        })
+    }
+}
+
+/// Limits on the tokens and cost of a task
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Budget {
+    pub max_input_tokens: Option<u64>,
+    pub max_output_tokens: Option<u64>,
+    pub max_cost_usd: Option<f64>,
+    /// Model the cost is computed for
+    pub model: Option<String>,
+    /// Price of the model, required by a cost limit
+    pub price: Option<ModelPrice>,
+}
+
+impl Budget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_input_tokens(mut self, max: u64) -> Self {
+        self.max_input_tokens = Some(max);
+        self
+    }
+
+    pub fn max_output_tokens(mut self, max: u64) -> Self {
+        self.max_output_tokens = Some(max);
+        self
+    }
+
+    pub fn max_cost_usd(mut self, max: f64) -> Self {
+        self.max_cost_usd = Some(max);
+        self
+    }
+
+    /// Price calls with the default [`PriceTable`] entry of `model`
+    pub fn model<S: Into<String>>(self, model: S) -> Self {
+        self.model_with_prices(model, &PriceTable::default())
+    }
+
+    /// Price calls with the entry of `model` in `prices`
+    pub fn model_with_prices<S: Into<String>>(mut self, model: S, prices: &PriceTable) -> Self {
+        let model = model.into();
+        self.price = prices.price(&model);
+        self.model = Some(model);
+        self
+    }
+
+    /// The first limit `usage` has reached
+    pub fn check(&self, usage: &BudgetUsage) -> Result<(), BudgetError> {
+        let limit = if let Some(max) = self
+            .max_input_tokens
+            .filter(|max| usage.input_tokens >= *max)
+        {
+            BudgetLimit::InputTokens(max)
+        } else if let Some(max) = self
+            .max_output_tokens
+            .filter(|max| usage.output_tokens >= *max)
+        {
+            BudgetLimit::OutputTokens(max)
+        } else if let Some(max) = self.max_cost_usd.filter(|max| usage.cost_usd >= *max) {
+            BudgetLimit::CostUsd(max)
+        } else {
+            return Ok(());
+        };
+        Err(BudgetError::Exceeded {
+            limit,
+            usage: usage.clone(),
+        })
+    }
+}
+
+/// Tokens and cost consumed by a task so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetUsage {
+    pub calls: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Estimate the tokens of a request from its length
+fn estimate_usage(messages: &[ChatMessage], response: Option<&str>) -> Usage {
+    let tokens = |chars: usize| chars.div_ceil(CHARS_PER_TOKEN) as u64;
+    Usage {
+        input_tokens: tokens(messages.iter().map(|m| m.content.len()).sum()),
+        output_tokens: tokens(response.map(str::len).unwrap_or_default()),
+    }
+}
+
+/// LLM provider counting the usage of another provider against a [`Budget`]
+pub struct BudgetedLLMProvider {
+    inner: Arc<dyn LLMProvider>,
+    budget: Budget,
+    usage: Mutex<BudgetUsage>,
+    exceeded: Mutex<Option<BudgetError>>,
+    events: Option<(SubmissionId, mpsc::Sender<Event>)>,
+}
+
+impl BudgetedLLMProvider {
+    pub fn new(inner: Arc<dyn LLMProvider>, budget: Budget) -> Self {
+        Self {
+            inner,
+            budget,
+            usage: Mutex::new(BudgetUsage::default()),
+            exceeded: Mutex::new(None),
+            events: None,
+        }
+    }
+
+    /// Start counting from `usage`, the usage of an interrupted run of the task
+    pub fn with_usage(mut self, usage: BudgetUsage) -> Self {
+        self.usage = Mutex::new(usage);
+        self
+    }
+
+    /// Report the budget being exceeded as an event of the submission
+    pub fn with_events(mut self, sub_id: SubmissionId, tx_event: mpsc::Sender<Event>) -> Self {
+        self.events = Some((sub_id, tx_event));
+        self
+    }
+
+    pub fn budget(&self) -> &Budget {
+        &self.budget
+    }
+
+    /// Usage counted so far
+    pub fn usage(&self) -> BudgetUsage {
+        self.usage.lock().unwrap().clone()
+    }
+
+    /// The error calls were refused with, once the budget is exceeded
+    pub fn exceeded(&self) -> Option<BudgetError> {
+        self.exceeded.lock().unwrap().clone()
+    }
+
+    /// Refuse the call if the budget is spent
+    async fn check(&self) -> Result<(), LLMError> {
+        if self.budget.max_cost_usd.is_some() && self.budget.price.is_none() {
+            let model = self.budget.model.clone().unwrap_or_default();
+            return Err(LLMError::InvalidRequest(
+                BudgetError::NoPrice(model).to_string(),
+            ));
+        }
+        let Err(error) = self.budget.check(&self.usage()) else {
+            return Ok(());
+        };
+        let first = self
+            .exceeded
+            .lock()
+            .unwrap()
+            .replace(error.clone())
+            .is_none();
+        if first {
+            if let (Some((sub_id, tx_event)), BudgetError::Exceeded { limit, usage }) =
+                (&self.events, &error)
+            {
+                let _ = tx_event
+                    .send(Event::BudgetExceeded {
+                        sub_id: *sub_id,
+                        limit: *limit,
+                        usage: usage.clone(),
+                    })
+                    .await;
+            }
+        }
+        Err(LLMError::ProviderError(error.to_string()))
+    }
+
+    fn record(&self, usage: Usage) {
+        let mut total = self.usage.lock().unwrap();
+        total.calls += 1;
+        total.input_tokens += usage.input_tokens;
+        total.output_tokens += usage.output_tokens;
+        if let Some(price) = &self.budget.price {
+            total.cost_usd += price.cost(&usage);
+        }
+    }
+}
+
+#[async_trait]
+impl ChatProvider for BudgetedLLMProvider {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.check().await?;
+        let response = self
+            .inner
+            .chat_with_tools(messages, tools, json_schema)
+            .await?;
+        self.record(
+            response
+                .usage()
+                .unwrap_or_else(|| estimate_usage(messages, response.text().as_deref())),
+        );
+        Ok(response)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.check().await?;
+        // Streamed output is not counted, only the prompt is
+        self.record(estimate_usage(messages, None));
+        self.inner.chat_stream(messages).await
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for BudgetedLLMProvider {
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        self.check().await?;
+        let response = self.inner.complete(req, json_schema).await?;
+        let prompt = ChatMessage::user().content(req.prompt.clone()).build();
+        self.record(estimate_usage(&[prompt], Some(&response.text)));
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for BudgetedLLMProvider {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.inner.embed(input).await
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for BudgetedLLMProvider {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.inner.list_models(request).await
+    }
+}
+
+impl LLMProvider for BudgetedLLMProvider {
+    fn tools(&self) -> Option<&[Tool]> {
+        self.inner.tools()
+    }
+
+    fn supports_tool_calls(&self) -> bool {
+        self.inner.supports_tool_calls()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+
+    #[test]
+    fn test_price_table_prefix_lookup() {
+        let prices = PriceTable::default();
+        assert_eq!(
+            prices.price("gpt-4o-mini-2024-07-18"),
+            Some(ModelPrice::new(0.15, 0.6))
+        );
+        assert_eq!(prices.price("gpt-4o"), Some(ModelPrice::new(2.5, 10.0)));
+        assert!(prices.price("unknown-model").is_none());
+
+        let custom = PriceTable::empty().with_price("local", ModelPrice::new(1.0, 2.0));
+        let budget = Budget::new().model_with_prices("local", &custom);
+        assert_eq!(budget.price, Some(ModelPrice::new(1.0, 2.0)));
+        let usage = Usage {
+            input_tokens: 500_000,
+            output_tokens: 250_000,
+        };
+        assert!((ModelPrice::new(1.0, 2.0).cost(&usage) - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_budget_check() {
+        let budget = Budget::new().max_output_tokens(100).max_cost_usd(0.5);
+        assert!(budget.check(&BudgetUsage::default()).is_ok());
+        let usage = BudgetUsage {
+            calls: 3,
+            output_tokens: 100,
+            ..Default::default()
+        };
+        assert!(matches!(
+            budget.check(&usage),
+            Err(BudgetError::Exceeded {
+                limit: BudgetLimit::OutputTokens(100),
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_budgeted_provider_refuses_calls_once_spent() {
+        let inner = Arc::new(
+            ScriptedLLMProvider::with_texts(vec!["one", "two", "three"]).with_usage(Usage {
+                input_tokens: 1_000,
+                output_tokens: 400,
+            }),
+        );
+        let sub_id = uuid::Uuid::new_v4();
+        let (tx, mut rx) = mpsc::channel(10);
+        let llm = BudgetedLLMProvider::new(
+            inner.clone(),
+            Budget::new().max_cost_usd(0.01).model_with_prices(
+                "m",
+                &PriceTable::empty().with_price("m", ModelPrice::new(2.0, 10.0)),
+            ),
+        )
+        .with_events(sub_id, tx);
+        let messages = [ChatMessage::user().content("hi").build()];
+
+        // Each call costs $0.006
+        assert!(llm.chat(&messages, None).await.is_ok());
+        assert!(llm.chat(&messages, None).await.is_ok());
+        assert!(llm.chat(&messages, None).await.is_err());
+        assert!(llm.chat(&messages, None).await.is_err());
+        assert_eq!(inner.remaining(), 1);
+
+        let usage = llm.usage();
+        assert_eq!(usage.calls, 2);
+        assert_eq!(usage.input_tokens, 2_000);
+        assert!(matches!(
+            llm.exceeded(),
+            Some(BudgetError::Exceeded {
+                limit: BudgetLimit::CostUsd(_),
+                ..
+            })
+        ));
+        // The event is sent once
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(Event::BudgetExceeded { sub_id: id, .. }) if id == sub_id
+        ));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_budgeted_provider_estimates_unreported_usage() {
+        let inner = Arc::new(ScriptedLLMProvider::with_texts(vec!["12345678"]));
+        let llm = BudgetedLLMProvider::new(inner, Budget::new().max_input_tokens(1_000));
+        let messages = [ChatMessage::user().content("a".repeat(40)).build()];
+        llm.chat(&messages, None).await.unwrap();
+        let usage = llm.usage();
+        assert_eq!(usage.input_tokens, 10);
+        assert_eq!(usage.output_tokens, 2);
+    }
+
+    #[tokio::test]
+    async fn test_cost_limit_requires_price() {
+        let inner = Arc::new(ScriptedLLMProvider::with_texts(vec!["hi"]));
+        let llm = BudgetedLLMProvider::new(
+            inner,
+            Budget::new().max_cost_usd(1.0).model("some-local-model"),
+        );
+        let messages = [ChatMessage::user().content("hi").build()];
+        let error = llm.chat(&messages, None).await.unwrap_err();
+        assert!(error.to_string().contains("some-local-model"));
+    }
+
+    #[derive(Debug)]
+    struct LoopingAgent;
+
+    impl crate::agent::prebuilt::react::ReActExecutor for LoopingAgent {}
+
+    impl crate::agent::AgentDeriveT for LoopingAgent {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "Keeps calling tools"
+        }
+
+        fn output_schema(&self) -> Option<serde_json::Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "looping"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn crate::tool::ToolT>> {
+            vec![]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_budget_stops_agent() {
+        use crate::agent::{BaseAgent, IntoRunnable};
+        use crate::error::Error;
+        use crate::protocol::TaskResult;
+        use crate::runtime::Task;
+        use autoagents_test_utils::llm::{tool_call, ScriptedResponse};
+
+        let responses = (0..5)
+            .map(|i| {
+                ScriptedResponse::ToolCalls(vec![tool_call(&format!("call_{i}"), "missing", "{}")])
+            })
+            .collect();
+        let llm = Arc::new(ScriptedLLMProvider::new(responses).with_usage(Usage {
+            input_tokens: 10,
+            output_tokens: 100,
+        }));
+        let agent = BaseAgent::new(LoopingAgent, llm.clone(), None).into_runnable();
+        let task = Task::new("loop", None).with_budget(Budget::new().max_output_tokens(250));
+        let sub_id = task.submission_id;
+        let (tx, mut rx) = mpsc::channel(100);
+
+        let result = agent.run(task, tx).await;
+        assert!(matches!(
+            result,
+            Err(Error::BudgetError(BudgetError::Exceeded {
+                limit: BudgetLimit::OutputTokens(250),
+                ..
+            }))
+        ));
+        // The fourth call was refused
+        assert_eq!(llm.remaining(), 2);
+
+        let mut exceeded = false;
+        let mut failure = None;
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                Event::BudgetExceeded { usage, .. } => {
+                    assert_eq!(usage.output_tokens, 300);
+                    exceeded = true;
+                }
+                Event::TaskComplete {
+                    sub_id: id,
+                    result: TaskResult::Failure(reason),
+                } if id == sub_id => failure = Some(reason),
+                _ => {}
+            }
+        }
+        assert!(exceeded);
+        assert!(failure.unwrap().starts_with("Budget exceeded"));
+    }
+
+    #[tokio::test]
+    async fn test_resumed_task_keeps_its_budget_usage() {
+        use crate::agent::{BaseAgent, IntoRunnable};
+        use crate::checkpoint::{CheckpointStore, InMemoryCheckpointStore};
+        use crate::runtime::Task;
+        use autoagents_test_utils::llm::{tool_call, ScriptedResponse};
+
+        let usage = Usage {
+            input_tokens: 10,
+            output_tokens: 100,
+        };
+        let calls = |count: usize| -> Vec<ScriptedResponse> {
+            (0..count)
+                .map(|i| {
+                    ScriptedResponse::ToolCalls(vec![tool_call(
+                        &format!("call_{i}"),
+                        "missing",
+                        "{}",
+                    )])
+                })
+                .collect()
+        };
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let task = Task::new("loop", None).with_budget(Budget::new().max_output_tokens(250));
+        let sub_id = task.submission_id;
+
+        // The task dies on its third call, after spending 200 output tokens
+        let mut responses = calls(2);
+        responses.push(ScriptedResponse::Error("Connection reset".to_string()));
+        let llm = Arc::new(ScriptedLLMProvider::new(responses).with_usage(usage));
+        let mut agent = BaseAgent::new(LoopingAgent, llm, None);
+        agent.checkpoint_store = Some(store.clone());
+        let (tx, _rx) = mpsc::channel(100);
+        assert!(agent.into_runnable().run(task, tx).await.is_err());
+        let checkpoint = store.load(sub_id).await.unwrap().unwrap();
+        assert_eq!(checkpoint.budget_usage.unwrap().output_tokens, 200);
+
+        // Resumed, it may only make one more call
+        let llm = Arc::new(ScriptedLLMProvider::new(calls(3)).with_usage(usage));
+        let mut agent = BaseAgent::new(LoopingAgent, llm.clone(), None);
+        agent.checkpoint_store = Some(store);
+        let (tx, _rx) = mpsc::channel(100);
+        let result = agent.into_runnable().resume(sub_id, tx).await;
+        assert!(result.unwrap_err().to_string().contains("Budget exceeded"));
+        assert_eq!(llm.remaining(), 2);
+    }
+}
@@ -0,0 +1,106 @@
+use super::{Checkpoint, CheckpointError, CheckpointStore};
+use crate::protocol::SubmissionId;
+use async_trait::async_trait;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Checkpoint store writing one JSON file per submission to a directory.
+///
+/// Files are written to a temporary path and renamed into place, so a crash while
+/// saving leaves the previous checkpoint intact.
+#[derive(Debug, Clone)]
+pub struct FileCheckpointStore {
+    dir: PathBuf,
+}
+
+impl FileCheckpointStore {
+    /// Store checkpoints in `dir`, which is created on the first save
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, sub_id: SubmissionId) -> PathBuf {
+        self.dir.join(format!("{sub_id}.json"))
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for FileCheckpointStore {
+    async fn save(&self, checkpoint: &Checkpoint) -> Result<(), CheckpointError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.path(checkpoint.sub_id());
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec(checkpoint)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn load(&self, sub_id: SubmissionId) -> Result<Option<Checkpoint>, CheckpointError> {
+        match tokio::fs::read(self.path(sub_id)).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, sub_id: SubmissionId) -> Result<(), CheckpointError> {
+        match tokio::fs::remove_file(self.path(sub_id)).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<SubmissionId>, CheckpointError> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut sub_ids = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(sub_id) = path
+                    .file_stem()
+                    .and_then(|stem| Uuid::parse_str(&stem.to_string_lossy()).ok())
+                {
+                    sub_ids.push(sub_id);
+                }
+            }
+        }
+        Ok(sub_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Task;
+
+    #[tokio::test]
+    async fn test_file_store_survives_new_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileCheckpointStore::new(dir.path().join("checkpoints"));
+        assert!(store.list().await.unwrap().is_empty());
+
+        let mut checkpoint = Checkpoint::new(Task::new("long task", None), "worker");
+        checkpoint.turn = 7;
+        let sub_id = checkpoint.sub_id();
+        store.save(&checkpoint).await.unwrap();
+
+        // A store opened on the same directory after a restart sees the checkpoint
+        let reopened = FileCheckpointStore::new(dir.path().join("checkpoints"));
+        assert_eq!(reopened.list().await.unwrap(), vec![sub_id]);
+        let loaded = reopened.load(sub_id).await.unwrap().unwrap();
+        assert_eq!(loaded.turn, 7);
+        assert_eq!(loaded.task.prompt, "long task");
+
+        reopened.delete(sub_id).await.unwrap();
+        assert!(store.load(sub_id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,213 @@
+//! Checkpoints of running tasks.
+//!
+//! Executors given a [`CheckpointStore`] save the progress of a task after every turn,
+//! so a task interrupted by a crash or a restart can be resumed by its
+//! [`SubmissionId`] instead of starting over.
+//!
+//! Tool calls are run at least once. The result of every call is saved as soon as it
+//! completes and a resumed task only runs the calls without a result, but a call that was
+//! running when the task stopped runs again. Tools with side effects should tolerate it.
+use crate::budget::{BudgetUsage, BudgetedLLMProvider};
+use crate::protocol::SubmissionId;
+use crate::runtime::Task;
+use crate::tool::ToolCallResult;
+use async_trait::async_trait;
+use autoagents_llm::chat::ChatMessage;
+use autoagents_llm::ToolCall;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+mod file;
+pub use file::FileCheckpointStore;
+
+/// Error type for checkpoint operations
+#[derive(Debug, Error)]
+pub enum CheckpointError {
+    #[error("Checkpoint IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Checkpoint serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("No checkpoint found for submission {0}")]
+    NotFound(SubmissionId),
+
+    #[error("Agent '{0}' has no checkpoint store")]
+    NoStore(String),
+}
+
+/// Progress of a task, as saved by an executor at the end of a turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The task being executed
+    pub task: Task,
+    /// Name of the agent executing the task
+    pub agent_name: String,
+    /// Number of turns completed
+    pub turn: usize,
+    /// Transcript of the task so far
+    pub messages: Vec<ChatMessage>,
+    /// Tool calls completed so far
+    pub tool_calls: Vec<ToolCallResult>,
+    /// Latest intermediate response of the model
+    pub response: String,
+    /// Tool calls requested by the model whose results are not in the transcript yet
+    pub pending_tool_calls: Vec<ToolCall>,
+    /// Text the model sent along with the pending tool calls
+    pub pending_response: String,
+    /// Results of the first pending tool calls, the ones that completed
+    #[serde(default)]
+    pub pending_results: Vec<ToolCallResult>,
+    /// Tokens and cost the task consumed of its budget, if it has one
+    #[serde(default)]
+    pub budget_usage: Option<BudgetUsage>,
+}
+
+impl Checkpoint {
+    /// Checkpoint of a task that has not completed any turn
+    pub fn new(task: Task, agent_name: impl Into<String>) -> Self {
+        Self {
+            task,
+            agent_name: agent_name.into(),
+            turn: 0,
+            messages: Vec::new(),
+            tool_calls: Vec::new(),
+            response: String::new(),
+            pending_tool_calls: Vec::new(),
+            pending_response: String::new(),
+            pending_results: Vec::new(),
+            budget_usage: None,
+        }
+    }
+
+    pub fn sub_id(&self) -> SubmissionId {
+        self.task.submission_id
+    }
+}
+
+/// Storage for checkpoints, keyed by submission
+#[async_trait]
+pub trait CheckpointStore: Send + Sync + Debug {
+    /// Save a checkpoint, replacing the previous one of the same submission
+    async fn save(&self, checkpoint: &Checkpoint) -> Result<(), CheckpointError>;
+
+    /// Load the latest checkpoint of a submission
+    async fn load(&self, sub_id: SubmissionId) -> Result<Option<Checkpoint>, CheckpointError>;
+
+    /// Delete the checkpoint of a submission, deleting a missing checkpoint is not an error
+    async fn delete(&self, sub_id: SubmissionId) -> Result<(), CheckpointError>;
+
+    /// Submissions with a saved checkpoint
+    async fn list(&self) -> Result<Vec<SubmissionId>, CheckpointError>;
+}
+
+/// Checkpoint store kept in process memory, checkpoints do not survive a restart
+#[derive(Debug, Default)]
+pub struct InMemoryCheckpointStore {
+    checkpoints: RwLock<HashMap<SubmissionId, Checkpoint>>,
+}
+
+impl InMemoryCheckpointStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for InMemoryCheckpointStore {
+    async fn save(&self, checkpoint: &Checkpoint) -> Result<(), CheckpointError> {
+        self.checkpoints
+            .write()
+            .await
+            .insert(checkpoint.sub_id(), checkpoint.clone());
+        Ok(())
+    }
+
+    async fn load(&self, sub_id: SubmissionId) -> Result<Option<Checkpoint>, CheckpointError> {
+        Ok(self.checkpoints.read().await.get(&sub_id).cloned())
+    }
+
+    async fn delete(&self, sub_id: SubmissionId) -> Result<(), CheckpointError> {
+        self.checkpoints.write().await.remove(&sub_id);
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<SubmissionId>, CheckpointError> {
+        Ok(self.checkpoints.read().await.keys().copied().collect())
+    }
+}
+
+/// Store recording the budget usage of a task in every checkpoint saved for it
+pub(crate) struct BudgetedCheckpointStore {
+    inner: Arc<dyn CheckpointStore>,
+    budgeted: Arc<BudgetedLLMProvider>,
+}
+
+impl Debug for BudgetedCheckpointStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BudgetedCheckpointStore")
+            .field("inner", &self.inner)
+            .field("usage", &self.budgeted.usage())
+            .finish()
+    }
+}
+
+impl BudgetedCheckpointStore {
+    pub(crate) fn new(inner: Arc<dyn CheckpointStore>, budgeted: Arc<BudgetedLLMProvider>) -> Self {
+        Self { inner, budgeted }
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for BudgetedCheckpointStore {
+    async fn save(&self, checkpoint: &Checkpoint) -> Result<(), CheckpointError> {
+        let mut checkpoint = checkpoint.clone();
+        checkpoint.budget_usage = Some(self.budgeted.usage());
+        self.inner.save(&checkpoint).await
+    }
+
+    async fn load(&self, sub_id: SubmissionId) -> Result<Option<Checkpoint>, CheckpointError> {
+        self.inner.load(sub_id).await
+    }
+
+    async fn delete(&self, sub_id: SubmissionId) -> Result<(), CheckpointError> {
+        self.inner.delete(sub_id).await
+    }
+
+    async fn list(&self) -> Result<Vec<SubmissionId>, CheckpointError> {
+        self.inner.list().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autoagents_llm::chat::{ChatRole, MessageType};
+
+    #[tokio::test]
+    async fn test_in_memory_store_roundtrip() {
+        let store = InMemoryCheckpointStore::new();
+        let mut checkpoint = Checkpoint::new(Task::new("count to three", None), "counter");
+        checkpoint.turn = 2;
+        checkpoint.messages.push(ChatMessage {
+            role: ChatRole::User,
+            message_type: MessageType::Text,
+            content: "count to three".to_string(),
+        });
+        let sub_id = checkpoint.sub_id();
+
+        store.save(&checkpoint).await.unwrap();
+        assert_eq!(store.list().await.unwrap(), vec![sub_id]);
+        let loaded = store.load(sub_id).await.unwrap().unwrap();
+        assert_eq!(loaded.turn, 2);
+        assert_eq!(loaded.messages.len(), 1);
+
+        store.delete(sub_id).await.unwrap();
+        assert!(store.load(sub_id).await.unwrap().is_none());
+        store.delete(sub_id).await.unwrap();
+    }
+}
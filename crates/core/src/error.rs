@@ -1,9 +1,15 @@
 use autoagents_llm::error::LLMError;
 
 use crate::{
-    agent::{AgentBuildError, AgentResultError, RunnableAgentError},
+    agent::{AgentBuildError, AgentResultError, PromptError, RunnableAgentError},
+    budget::BudgetError,
+    checkpoint::CheckpointError,
     environment::EnvironmentError,
+    group_chat::GroupChatError,
+    guardrail::GuardrailError,
     runtime::RuntimeError,
+    tool::ToolRegistryError,
+    workflow::WorkflowError,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -20,4 +26,18 @@ pub enum Error {
     LLMError(#[from] LLMError),
     #[error(transparent)]
     AgentResultError(#[from] AgentResultError),
+    #[error(transparent)]
+    WorkflowError(#[from] WorkflowError),
+    #[error(transparent)]
+    GroupChatError(#[from] GroupChatError),
+    #[error(transparent)]
+    CheckpointError(#[from] CheckpointError),
+    #[error(transparent)]
+    BudgetError(#[from] BudgetError),
+    #[error(transparent)]
+    GuardrailError(#[from] GuardrailError),
+    #[error(transparent)]
+    PromptError(#[from] PromptError),
+    #[error(transparent)]
+    ToolRegistryError(#[from] ToolRegistryError),
 }
@@ -0,0 +1,489 @@
+//! Fallback between LLM providers.
+//!
+//! An agent given fallback providers through
+//! [`AgentBuilder::with_fallback_llm`](crate::agent::AgentBuilder::with_fallback_llm) calls
+//! them in order after its primary provider whenever a call fails in a way the
+//! [`FallbackPolicy`] covers. Each provider keeps the model and temperature it was built
+//! with, so a chain can step down from one model to another. Every switch is reported with
+//! an [`Event::LLMFallback`] and every answer with an [`Event::LLMResponded`] naming the
+//! provider.
+//!
+//! The chain sits inside the budget of a task, so a spent budget never triggers a fallback.
+use crate::protocol::{Event, SubmissionId};
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatProvider, ChatResponse, StructuredOutputFormat, Tool};
+use autoagents_llm::completion::{CompletionProvider, CompletionRequest, CompletionResponse};
+use autoagents_llm::embedding::EmbeddingProvider;
+use autoagents_llm::error::LLMError;
+use autoagents_llm::models::{ModelListRequest, ModelListResponse, ModelsProvider};
+use autoagents_llm::LLMProvider;
+use futures::Stream;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, LazyLock};
+use tokio::sync::mpsc;
+
+/// Name under which events report the primary provider of an agent
+pub const PRIMARY_PROVIDER: &str = "primary";
+
+/// Status codes as providers report them, e.g. `429 Too Many Requests`
+static STATUS_CODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b([45]\d{2}) [A-Z][A-Za-z ]+").unwrap());
+
+/// Why a call moved on to the next provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FallbackReason {
+    /// The request failed without a response, e.g. a connection error or timeout
+    Http,
+    /// The provider answered with status 429 or reported a rate limit
+    RateLimit,
+    /// The provider answered with a 5xx status
+    ServerError,
+    /// The provider answered with neither text nor tool calls
+    EmptyResponse,
+}
+
+impl Display for FallbackReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FallbackReason::Http => write!(f, "HTTP error"),
+            FallbackReason::RateLimit => write!(f, "rate limited"),
+            FallbackReason::ServerError => write!(f, "server error"),
+            FallbackReason::EmptyResponse => write!(f, "empty response"),
+        }
+    }
+}
+
+impl FallbackReason {
+    /// Reason to fall back on `error`, `None` for errors another provider would repeat,
+    /// such as invalid requests
+    pub fn of(error: &LLMError) -> Option<Self> {
+        let message = match error {
+            LLMError::HttpError(message)
+            | LLMError::ProviderError(message)
+            | LLMError::ResponseFormatError { message, .. } => message,
+            _ => return None,
+        };
+        let status = STATUS_CODE
+            .captures(message)
+            .and_then(|captures| captures[1].parse::<u16>().ok());
+        match status {
+            Some(429) => Some(FallbackReason::RateLimit),
+            Some(500..=599) => Some(FallbackReason::ServerError),
+            Some(_) => None,
+            None if message.to_lowercase().contains("rate limit") => {
+                Some(FallbackReason::RateLimit)
+            }
+            None if matches!(error, LLMError::HttpError(_)) => Some(FallbackReason::Http),
+            None => None,
+        }
+    }
+}
+
+/// Failures that move a call on to the next provider, all enabled by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FallbackPolicy {
+    pub on_http_error: bool,
+    pub on_rate_limit: bool,
+    pub on_server_error: bool,
+    pub on_empty_response: bool,
+}
+
+impl Default for FallbackPolicy {
+    fn default() -> Self {
+        Self {
+            on_http_error: true,
+            on_rate_limit: true,
+            on_server_error: true,
+            on_empty_response: true,
+        }
+    }
+}
+
+impl FallbackPolicy {
+    pub fn covers(&self, reason: FallbackReason) -> bool {
+        match reason {
+            FallbackReason::Http => self.on_http_error,
+            FallbackReason::RateLimit => self.on_rate_limit,
+            FallbackReason::ServerError => self.on_server_error,
+            FallbackReason::EmptyResponse => self.on_empty_response,
+        }
+    }
+}
+
+/// A provider of a fallback chain and the name events report it under
+#[derive(Clone)]
+pub struct NamedLLMProvider {
+    pub name: String,
+    pub llm: Arc<dyn LLMProvider>,
+}
+
+impl NamedLLMProvider {
+    pub fn new(name: impl Into<String>, llm: Arc<dyn LLMProvider>) -> Self {
+        Self {
+            name: name.into(),
+            llm,
+        }
+    }
+}
+
+impl Debug for NamedLLMProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// LLM provider calling a list of providers in order until one answers
+pub struct FallbackLLMProvider {
+    providers: Vec<NamedLLMProvider>,
+    policy: FallbackPolicy,
+    events: Option<(SubmissionId, mpsc::Sender<Event>)>,
+}
+
+impl FallbackLLMProvider {
+    /// Chain of `providers`, tried in order
+    pub fn new(providers: Vec<NamedLLMProvider>, policy: FallbackPolicy) -> Self {
+        assert!(!providers.is_empty(), "A fallback chain needs a provider");
+        Self {
+            providers,
+            policy,
+            events: None,
+        }
+    }
+
+    /// Report fallbacks and answering providers as events of `sub_id`
+    pub fn with_events(mut self, sub_id: SubmissionId, tx_event: mpsc::Sender<Event>) -> Self {
+        self.events = Some((sub_id, tx_event));
+        self
+    }
+
+    async fn emit(&self, event: impl FnOnce(SubmissionId) -> Event) {
+        if let Some((sub_id, tx_event)) = &self.events {
+            let _ = tx_event.send(event(*sub_id)).await;
+        }
+    }
+
+    /// Call providers in order until one gives a result the policy accepts.
+    ///
+    /// When every provider fails, the result of the last one is returned.
+    async fn call<'a, T, F, Fut>(&'a self, call: F, is_empty: fn(&T) -> bool) -> Result<T, LLMError>
+    where
+        F: Fn(&'a dyn LLMProvider) -> Fut,
+        Fut: Future<Output = Result<T, LLMError>>,
+    {
+        let last = self.providers.len() - 1;
+        for (index, provider) in self.providers.iter().enumerate() {
+            let result = call(provider.llm.as_ref()).await;
+            let reason = match &result {
+                Ok(value) if is_empty(value) => Some(FallbackReason::EmptyResponse),
+                Ok(_) => None,
+                Err(error) => FallbackReason::of(error),
+            };
+            let fall_back = reason.filter(|reason| self.policy.covers(*reason));
+            match fall_back {
+                Some(reason) if index < last => {
+                    let error = match &result {
+                        Err(error) => error.to_string(),
+                        Ok(_) => reason.to_string(),
+                    };
+                    log::warn!(
+                        "LLM provider '{}' failed ({error}), falling back to '{}'",
+                        provider.name,
+                        self.providers[index + 1].name
+                    );
+                    self.emit(|sub_id| Event::LLMFallback {
+                        sub_id,
+                        provider: provider.name.clone(),
+                        next: self.providers[index + 1].name.clone(),
+                        reason,
+                        error,
+                    })
+                    .await;
+                }
+                _ => {
+                    if result.is_ok() {
+                        self.emit(|sub_id| Event::LLMResponded {
+                            sub_id,
+                            provider: provider.name.clone(),
+                        })
+                        .await;
+                    }
+                    return result;
+                }
+            }
+        }
+        unreachable!("The last provider always returns")
+    }
+}
+
+fn is_empty_response(response: &dyn ChatResponse) -> bool {
+    response.text().is_none_or(|text| text.trim().is_empty())
+        && response.tool_calls().is_none_or(|calls| calls.is_empty())
+}
+
+#[async_trait]
+impl ChatProvider for FallbackLLMProvider {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.call(
+            |llm| llm.chat_with_tools(messages, tools, json_schema.clone()),
+            |response| is_empty_response(response.as_ref()),
+        )
+        .await
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.call(|llm| llm.chat_stream(messages), |_| false).await
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for FallbackLLMProvider {
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        self.call(
+            |llm| llm.complete(req, json_schema.clone()),
+            |response| response.text.trim().is_empty(),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for FallbackLLMProvider {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.call(
+            |llm| llm.embed(input.clone()),
+            |embeddings| embeddings.is_empty(),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for FallbackLLMProvider {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.providers[0].llm.list_models(request).await
+    }
+}
+
+impl LLMProvider for FallbackLLMProvider {
+    fn tools(&self) -> Option<&[Tool]> {
+        self.providers[0].llm.tools()
+    }
+
+    /// Native tool calls are only used when every provider in the chain supports them
+    fn supports_tool_calls(&self) -> bool {
+        self.providers
+            .iter()
+            .all(|provider| provider.llm.supports_tool_calls())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::prebuilt::react::ReActExecutor;
+    use crate::agent::{AgentDeriveT, BaseAgent, IntoRunnable};
+    use crate::protocol::TaskResult;
+    use crate::runtime::Task;
+    use crate::tool::ToolT;
+    use autoagents_test_utils::llm::{ScriptedLLMProvider, ScriptedResponse};
+    use serde_json::Value;
+
+    fn scripted(responses: Vec<ScriptedResponse>) -> Arc<ScriptedLLMProvider> {
+        Arc::new(ScriptedLLMProvider::new(responses))
+    }
+
+    #[test]
+    fn test_fallback_reason_of_errors() {
+        let reason = |error| FallbackReason::of(&error);
+        assert_eq!(
+            reason(LLMError::ResponseFormatError {
+                message: "OpenAI API returned error status: 429 Too Many Requests".into(),
+                raw_response: "{}".into(),
+            }),
+            Some(FallbackReason::RateLimit)
+        );
+        assert_eq!(
+            reason(LLMError::HttpError(
+                "HTTP status server error (502 Bad Gateway) for url (https://api)".into()
+            )),
+            Some(FallbackReason::ServerError)
+        );
+        assert_eq!(
+            reason(LLMError::HttpError(
+                "error sending request: timed out".into()
+            )),
+            Some(FallbackReason::Http)
+        );
+        assert_eq!(
+            reason(LLMError::ProviderError(
+                "Rate limit reached for model".into()
+            )),
+            Some(FallbackReason::RateLimit)
+        );
+        assert_eq!(
+            reason(LLMError::HttpError(
+                "HTTP status client error (401 Unauthorized) for url (https://api)".into()
+            )),
+            None
+        );
+        assert_eq!(reason(LLMError::InvalidRequest("bad".into())), None);
+    }
+
+    #[tokio::test]
+    async fn test_chain_skips_failed_and_empty_providers() {
+        let primary = scripted(vec![ScriptedResponse::Error(
+            "503 Service Unavailable".into(),
+        )]);
+        let secondary = scripted(vec![ScriptedResponse::Text("  ".into())]);
+        let tertiary = scripted(vec![ScriptedResponse::Text("Answer".into())]);
+        let sub_id = uuid::Uuid::new_v4();
+        let (tx, mut rx) = mpsc::channel(10);
+        let chain = FallbackLLMProvider::new(
+            vec![
+                NamedLLMProvider::new("primary", primary.clone()),
+                NamedLLMProvider::new("secondary", secondary.clone()),
+                NamedLLMProvider::new("tertiary", tertiary.clone()),
+            ],
+            FallbackPolicy::default(),
+        )
+        .with_events(sub_id, tx);
+
+        let messages = [ChatMessage::user().content("Hi").build()];
+        let response = chain.chat(&messages, None).await.unwrap();
+        assert_eq!(response.text().unwrap(), "Answer");
+        // Every provider was asked the same question
+        assert_eq!(secondary.requests()[0][0].content, "Hi");
+
+        let mut events = vec![];
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        let summary: Vec<_> = events
+            .iter()
+            .map(|event| match event {
+                Event::LLMFallback {
+                    provider,
+                    next,
+                    reason,
+                    ..
+                } => format!("{provider} -> {next}: {reason}"),
+                Event::LLMResponded { provider, .. } => format!("answered by {provider}"),
+                other => panic!("Unexpected event {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                "primary -> secondary: server error",
+                "secondary -> tertiary: empty response",
+                "answered by tertiary",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chain_stops_on_errors_outside_policy() {
+        let primary = scripted(vec![ScriptedResponse::Error(
+            "429 Too Many Requests".into(),
+        )]);
+        let backup = scripted(vec![ScriptedResponse::Text("unused".into())]);
+        let chain = FallbackLLMProvider::new(
+            vec![
+                NamedLLMProvider::new("primary", primary),
+                NamedLLMProvider::new("backup", backup.clone()),
+            ],
+            FallbackPolicy {
+                on_rate_limit: false,
+                ..Default::default()
+            },
+        );
+
+        let messages = [ChatMessage::user().content("Hi").build()];
+        let error = chain.chat(&messages, None).await.unwrap_err();
+        assert!(error.to_string().contains("429"));
+        assert_eq!(backup.remaining(), 1);
+    }
+
+    #[derive(Debug)]
+    struct AnswerAgent;
+
+    impl ReActExecutor for AnswerAgent {}
+
+    impl AgentDeriveT for AnswerAgent {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "Answers questions"
+        }
+
+        fn output_schema(&self) -> Option<Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "answer"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_falls_back_to_next_provider() {
+        let primary = scripted(vec![ScriptedResponse::Error(
+            "429 Too Many Requests".into(),
+        )]);
+        let backup = scripted(vec![ScriptedResponse::Text("42".into())]);
+        let mut agent = BaseAgent::new(AnswerAgent, primary, None);
+        agent.fallback_llms = vec![NamedLLMProvider::new("backup", backup)];
+        let (tx, mut rx) = mpsc::channel(100);
+        agent
+            .into_runnable()
+            .run(Task::new("What is six times seven?", None), tx)
+            .await
+            .unwrap();
+
+        let mut fallbacks = vec![];
+        let mut answered_by = vec![];
+        let mut output = None;
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                Event::LLMFallback {
+                    provider, reason, ..
+                } => fallbacks.push((provider, reason)),
+                Event::LLMResponded { provider, .. } => answered_by.push(provider),
+                Event::TaskComplete {
+                    result: TaskResult::Value(value),
+                    ..
+                } => output = Some(value),
+                _ => {}
+            }
+        }
+        assert_eq!(
+            fallbacks,
+            vec![(PRIMARY_PROVIDER.to_string(), FallbackReason::RateLimit)]
+        );
+        assert_eq!(answered_by, vec!["backup"]);
+        assert_eq!(output.unwrap()["response"], "42");
+    }
+}
@@ -0,0 +1,855 @@
+//! Group chat orchestration.
+//!
+//! A [`GroupChat`] lets several agents registered on a runtime take turns on a shared
+//! transcript. A manager picks who speaks next, either in round-robin order, by asking an
+//! LLM, or with a custom function, until a termination condition ends the session: the
+//! maximum number of rounds, a keyword in a message, or a judge agent deciding the
+//! discussion is over. The transcript is kept in a [`MemoryProvider`] and every message can
+//! be published on a runtime topic so other agents can follow the conversation. A group
+//! chat runs one session at a time, since the sessions would share the transcript.
+//!
+//! ```ignore
+//! let chat = GroupChat::builder("review")
+//!     .participant(author.id())
+//!     .participant(reviewer.id())
+//!     .max_rounds(6)
+//!     .terminate_on_keyword("APPROVED")
+//!     .build()?;
+//! let output = chat.run(runtime.clone(), "Review the design of the cache").await?;
+//! ```
+use crate::agent::prebuilt::react::ReActAgentOutput;
+use crate::agent::validate_output;
+use crate::error::Error;
+use crate::memory::{MemoryProvider, SlidingWindowMemory};
+use crate::protocol::{AgentID, Event, GroupChatId, TaskResult};
+use crate::runtime::{Runtime, RuntimeError, Task};
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType, StructuredOutputFormat};
+use autoagents_llm::LLMProvider;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use uuid::Uuid;
+
+const DEFAULT_MAX_ROUNDS: usize = 10;
+const DEFAULT_TRANSCRIPT_WINDOW: usize = 100;
+
+/// Custom speaker selection, returning the next speaker or `None` to end the chat
+pub type SpeakerSelector = Arc<dyn Fn(&GroupChatState) -> Option<AgentID> + Send + Sync>;
+
+/// Error types for group chats
+#[derive(Debug, thiserror::Error)]
+pub enum GroupChatError {
+    #[error("Group chat has no participants")]
+    NoParticipants,
+
+    #[error("Group chat agent not found: {0}")]
+    UnknownAgent(AgentID),
+
+    #[error("Speaker selection failed: {0}")]
+    SelectionFailed(String),
+
+    #[error("Agent '{speaker}' failed to speak: {reason}")]
+    SpeakerFailed { speaker: String, reason: String },
+
+    #[error("Memory error: {0}")]
+    MemoryError(String),
+
+    #[error("Group chat '{0}' is already running")]
+    AlreadyRunning(String),
+}
+
+/// How the manager picks the next speaker
+#[derive(Clone)]
+pub enum SpeakerSelection {
+    /// Participants speak in the order they were added
+    RoundRobin,
+    /// An LLM picks the next speaker from the participants' names and descriptions
+    Llm(Arc<dyn LLMProvider>),
+    /// A custom function picks the next speaker
+    Custom(SpeakerSelector),
+}
+
+impl Debug for SpeakerSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RoundRobin => write!(f, "RoundRobin"),
+            Self::Llm(_) => write!(f, "Llm"),
+            Self::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+/// An agent taking part in a group chat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Participant {
+    pub agent_id: AgentID,
+    pub name: String,
+    pub description: String,
+}
+
+/// A message of the group chat transcript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupChatMessage {
+    pub round: usize,
+    pub agent_id: AgentID,
+    pub speaker: String,
+    pub content: String,
+}
+
+/// Why a group chat ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TerminationReason {
+    /// The maximum number of rounds was reached
+    MaxRounds,
+    /// A message contained the termination keyword
+    Keyword(String),
+    /// The judge agent ended the discussion, with its reason
+    Judge(String),
+    /// The speaker selection returned no one
+    NoSpeaker,
+}
+
+/// State of a running group chat, as seen by a custom speaker selection
+#[derive(Debug, Clone)]
+pub struct GroupChatState {
+    /// The round about to be played, starting at 1
+    pub round: usize,
+    pub task: String,
+    pub participants: Vec<Participant>,
+    pub messages: Vec<GroupChatMessage>,
+}
+
+/// Final state of a group chat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupChatOutput {
+    pub chat_id: GroupChatId,
+    pub messages: Vec<GroupChatMessage>,
+    pub termination: TerminationReason,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpeakerChoice {
+    speaker: String,
+}
+
+/// Builder for a [`GroupChat`]
+pub struct GroupChatBuilder {
+    name: String,
+    participants: Vec<AgentID>,
+    selection: SpeakerSelection,
+    max_rounds: usize,
+    keyword: Option<String>,
+    judge: Option<AgentID>,
+    topic: Option<String>,
+    memory: Option<Box<dyn MemoryProvider>>,
+}
+
+impl GroupChatBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            participants: Vec::new(),
+            selection: SpeakerSelection::RoundRobin,
+            max_rounds: DEFAULT_MAX_ROUNDS,
+            keyword: None,
+            judge: None,
+            topic: None,
+            memory: None,
+        }
+    }
+
+    pub fn participant(mut self, agent_id: AgentID) -> Self {
+        self.participants.push(agent_id);
+        self
+    }
+
+    pub fn speaker_selection(mut self, selection: SpeakerSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// End the chat after this many rounds, each round one participant speaks
+    pub fn max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// End the chat once a message contains `keyword`
+    pub fn terminate_on_keyword(mut self, keyword: impl Into<String>) -> Self {
+        self.keyword = Some(keyword.into());
+        self
+    }
+
+    /// Ask this agent after every round whether the discussion is over.
+    ///
+    /// The judge answers with `{"terminate": bool, "reason": string}`, or with text
+    /// starting with `TERMINATE` to end the chat.
+    pub fn judge(mut self, agent_id: AgentID) -> Self {
+        self.judge = Some(agent_id);
+        self
+    }
+
+    /// Publish every message to this runtime topic
+    pub fn topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = Some(topic.into());
+        self
+    }
+
+    /// Memory holding the transcript the speakers see, a sliding window by default
+    pub fn memory(mut self, memory: Box<dyn MemoryProvider>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    pub fn build(self) -> Result<GroupChat, GroupChatError> {
+        if self.participants.is_empty() {
+            return Err(GroupChatError::NoParticipants);
+        }
+        let memory = self
+            .memory
+            .unwrap_or_else(|| Box::new(SlidingWindowMemory::new(DEFAULT_TRANSCRIPT_WINDOW)));
+        Ok(GroupChat {
+            name: self.name,
+            participants: self.participants,
+            selection: self.selection,
+            max_rounds: self.max_rounds,
+            keyword: self.keyword,
+            judge: self.judge,
+            topic: self.topic,
+            memory: Arc::new(RwLock::new(memory)),
+            running: AtomicBool::new(false),
+        })
+    }
+}
+
+/// Several agents taking turns on a shared transcript
+pub struct GroupChat {
+    name: String,
+    participants: Vec<AgentID>,
+    selection: SpeakerSelection,
+    max_rounds: usize,
+    keyword: Option<String>,
+    judge: Option<AgentID>,
+    topic: Option<String>,
+    memory: Arc<RwLock<Box<dyn MemoryProvider>>>,
+    running: AtomicBool,
+}
+
+impl Debug for GroupChat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GroupChat")
+            .field("name", &self.name)
+            .field("participants", &self.participants)
+            .field("selection", &self.selection)
+            .field("max_rounds", &self.max_rounds)
+            .field("keyword", &self.keyword)
+            .field("judge", &self.judge)
+            .field("topic", &self.topic)
+            .finish()
+    }
+}
+
+impl GroupChat {
+    pub fn builder(name: impl Into<String>) -> GroupChatBuilder {
+        GroupChatBuilder::new(name)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Memory holding the transcript of the current or last session
+    pub fn memory(&self) -> Arc<RwLock<Box<dyn MemoryProvider>>> {
+        self.memory.clone()
+    }
+
+    /// Run a chat session on `task`, starting from an empty transcript.
+    ///
+    /// Fails with [`GroupChatError::AlreadyRunning`] while another session of the chat is
+    /// running, build one group chat per concurrent session instead.
+    pub async fn run(
+        &self,
+        runtime: Arc<dyn Runtime>,
+        task: impl Into<String>,
+    ) -> Result<GroupChatOutput, Error> {
+        if self.running.swap(true, Ordering::AcqRel) {
+            return Err(GroupChatError::AlreadyRunning(self.name.clone()).into());
+        }
+        let _running = RunningGuard(&self.running);
+
+        let mut participants = Vec::with_capacity(self.participants.len());
+        for agent_id in &self.participants {
+            let agent = runtime
+                .get_agent(*agent_id)
+                .await
+                .ok_or(GroupChatError::UnknownAgent(*agent_id))?;
+            participants.push(Participant {
+                agent_id: *agent_id,
+                name: agent.name().to_string(),
+                description: agent.description().to_string(),
+            });
+        }
+
+        let chat_id = Uuid::new_v4();
+        let tx_event = runtime.event_sender();
+        send(
+            &tx_event,
+            Event::GroupChatStarted {
+                chat_id,
+                name: self.name.clone(),
+                participants: participants.iter().map(|p| p.name.clone()).collect(),
+            },
+        )
+        .await?;
+
+        let mut state = GroupChatState {
+            round: 0,
+            task: task.into(),
+            participants,
+            messages: Vec::new(),
+        };
+        {
+            let mut memory = self.memory.write().await;
+            memory.clear().await.map_err(memory_error)?;
+            memory
+                .remember(&text_message(ChatRole::User, state.task.clone()))
+                .await
+                .map_err(memory_error)?;
+        }
+
+        let mut last_speaker = None;
+        let mut termination = TerminationReason::MaxRounds;
+        for round in 1..=self.max_rounds {
+            state.round = round;
+            let Some(speaker) = self.select_speaker(&state, last_speaker).await? else {
+                termination = TerminationReason::NoSpeaker;
+                break;
+            };
+            let participant = state.participants[speaker].clone();
+
+            let prompt = format!(
+                "You are {} in the group chat '{}'.\n\nConversation so far:\n{}\n\n\
+                 It is your turn to speak. Reply with your message only.",
+                participant.name,
+                self.name,
+                self.transcript().await?
+            );
+            let result = runtime
+                .execute_task(
+                    participant.agent_id,
+                    Task::new(prompt, Some(participant.agent_id)),
+                )
+                .await?;
+            let content = match result {
+                TaskResult::Value(value) => ReActAgentOutput::response_text(value),
+                TaskResult::Failure(reason) => {
+                    return Err(GroupChatError::SpeakerFailed {
+                        speaker: participant.name,
+                        reason,
+                    }
+                    .into())
+                }
+                TaskResult::Aborted => {
+                    return Err(GroupChatError::SpeakerFailed {
+                        speaker: participant.name,
+                        reason: "Task was aborted".to_string(),
+                    }
+                    .into())
+                }
+            };
+
+            let line = format!("{}: {}", participant.name, content);
+            self.memory
+                .write()
+                .await
+                .remember(&text_message(ChatRole::Assistant, line.clone()))
+                .await
+                .map_err(memory_error)?;
+            if let Some(topic) = &self.topic {
+                runtime.publish_message(line, topic.clone()).await?;
+            }
+            send(
+                &tx_event,
+                Event::GroupChatMessage {
+                    chat_id,
+                    round,
+                    agent_id: participant.agent_id,
+                    speaker: participant.name.clone(),
+                    content: content.clone(),
+                },
+            )
+            .await?;
+            state.messages.push(GroupChatMessage {
+                round,
+                agent_id: participant.agent_id,
+                speaker: participant.name,
+                content: content.clone(),
+            });
+            last_speaker = Some(speaker);
+
+            if let Some(keyword) = &self.keyword {
+                if content.contains(keyword.as_str()) {
+                    termination = TerminationReason::Keyword(keyword.clone());
+                    break;
+                }
+            }
+            if let Some(judge) = self.judge {
+                if let Some(reason) = self.ask_judge(&runtime, judge).await? {
+                    termination = TerminationReason::Judge(reason);
+                    break;
+                }
+            }
+        }
+
+        send(
+            &tx_event,
+            Event::GroupChatEnded {
+                chat_id,
+                rounds: state.messages.len(),
+                termination: termination.clone(),
+            },
+        )
+        .await?;
+        Ok(GroupChatOutput {
+            chat_id,
+            messages: state.messages,
+            termination,
+        })
+    }
+
+    async fn transcript(&self) -> Result<String, Error> {
+        let messages = self
+            .memory
+            .read()
+            .await
+            .recall("", None)
+            .await
+            .map_err(memory_error)?;
+        Ok(messages
+            .iter()
+            .map(|m| match m.role {
+                ChatRole::User => format!("Task: {}", m.content),
+                _ => m.content.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    async fn select_speaker(
+        &self,
+        state: &GroupChatState,
+        last_speaker: Option<usize>,
+    ) -> Result<Option<usize>, Error> {
+        let participants = &state.participants;
+        match &self.selection {
+            SpeakerSelection::RoundRobin => Ok(Some(
+                last_speaker.map_or(0, |i| (i + 1) % participants.len()),
+            )),
+            SpeakerSelection::Custom(select) => match select(state) {
+                Some(agent_id) => participants
+                    .iter()
+                    .position(|p| p.agent_id == agent_id)
+                    .map(Some)
+                    .ok_or_else(|| {
+                        GroupChatError::SelectionFailed(format!("{agent_id} is not a participant"))
+                            .into()
+                    }),
+                None => Ok(None),
+            },
+            SpeakerSelection::Llm(llm) => {
+                let names: Vec<&str> = participants.iter().map(|p| p.name.as_str()).collect();
+                let format = StructuredOutputFormat {
+                    name: "NextSpeaker".to_string(),
+                    description: Some("The participant who speaks next".to_string()),
+                    schema: Some(serde_json::json!({
+                        "type": "object",
+                        "properties": {"speaker": {"type": "string", "enum": names}},
+                        "required": ["speaker"],
+                        "additionalProperties": false
+                    })),
+                    strict: Some(true),
+                };
+                let roster = participants
+                    .iter()
+                    .map(|p| format!("- {}: {}", p.name, p.description))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let messages = vec![
+                    text_message(
+                        ChatRole::System,
+                        format!(
+                            "You manage the group chat '{}'. Pick the participant who should \
+                             speak next.\n\nParticipants:\n{roster}",
+                            self.name
+                        ),
+                    ),
+                    text_message(ChatRole::User, self.transcript().await?),
+                ];
+                let response = llm
+                    .chat(&messages, Some(format.clone()))
+                    .await
+                    .map_err(|e| GroupChatError::SelectionFailed(e.to_string()))?;
+                let value = validate_output(&format, &response.text().unwrap_or_default())
+                    .map_err(|e| GroupChatError::SelectionFailed(e.to_string()))?;
+                let choice: SpeakerChoice = serde_json::from_value(value)
+                    .map_err(|e| GroupChatError::SelectionFailed(e.to_string()))?;
+                participants
+                    .iter()
+                    .position(|p| p.name == choice.speaker)
+                    .map(Some)
+                    .ok_or_else(|| GroupChatError::SelectionFailed(choice.speaker).into())
+            }
+        }
+    }
+
+    async fn ask_judge(
+        &self,
+        runtime: &Arc<dyn Runtime>,
+        judge: AgentID,
+    ) -> Result<Option<String>, Error> {
+        let prompt = format!(
+            "You judge the group chat '{}'. Decide whether the discussion has reached its \
+             goal and should end.\n\nConversation so far:\n{}\n\n\
+             Answer with {{\"terminate\": true|false, \"reason\": \"...\"}}.",
+            self.name,
+            self.transcript().await?
+        );
+        let verdict = match runtime
+            .execute_task(judge, Task::new(prompt, Some(judge)))
+            .await?
+        {
+            TaskResult::Value(Value::Object(verdict)) if verdict.contains_key("terminate") => {
+                Value::Object(verdict)
+            }
+            TaskResult::Value(value) => {
+                let text = ReActAgentOutput::response_text(value);
+                serde_json::from_str(&text).unwrap_or(Value::String(text))
+            }
+            TaskResult::Failure(reason) => {
+                return Err(GroupChatError::SpeakerFailed {
+                    speaker: "judge".to_string(),
+                    reason,
+                }
+                .into())
+            }
+            TaskResult::Aborted => return Ok(None),
+        };
+
+        Ok(match verdict {
+            Value::Bool(true) => Some(String::new()),
+            Value::String(text) if text.trim_start().to_uppercase().starts_with("TERMINATE") => {
+                Some(text)
+            }
+            Value::Object(map) if map.get("terminate") == Some(&Value::Bool(true)) => Some(
+                map.get("reason")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            ),
+            _ => None,
+        })
+    }
+}
+
+/// Marks the group chat as running until dropped
+struct RunningGuard<'a>(&'a AtomicBool);
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Text of an agent's result, the `response` of agents answering with a ReAct output
+fn text_message(role: ChatRole, content: String) -> ChatMessage {
+    ChatMessage {
+        role,
+        message_type: MessageType::Text,
+        content,
+    }
+}
+
+fn memory_error(error: autoagents_llm::error::LLMError) -> Error {
+    GroupChatError::MemoryError(error.to_string()).into()
+}
+
+async fn send(tx_event: &mpsc::Sender<Event>, event: Event) -> Result<(), Error> {
+    tx_event
+        .send(event)
+        .await
+        .map_err(|e| RuntimeError::EventError(e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::RunnableAgent;
+    use crate::runtime::SingleThreadedRuntime;
+    use async_trait::async_trait;
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    #[derive(Debug)]
+    struct ChatAgent {
+        id: AgentID,
+        name: &'static str,
+        replies: Mutex<VecDeque<&'static str>>,
+        prompts: Mutex<Vec<String>>,
+    }
+
+    impl ChatAgent {
+        fn new(name: &'static str, replies: Vec<&'static str>) -> Arc<Self> {
+            Arc::new(Self {
+                id: Uuid::new_v4(),
+                name,
+                replies: Mutex::new(replies.into()),
+                prompts: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl RunnableAgent for ChatAgent {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn description(&self) -> &'static str {
+            "Takes part in the chat"
+        }
+
+        fn id(&self) -> AgentID {
+            self.id
+        }
+
+        fn memory(&self) -> Option<Arc<RwLock<Box<dyn MemoryProvider>>>> {
+            None
+        }
+
+        async fn run(self: Arc<Self>, task: Task, tx: mpsc::Sender<Event>) -> Result<(), Error> {
+            self.prompts.lock().unwrap().push(task.prompt.clone());
+            let reply = self.replies.lock().unwrap().pop_front().unwrap_or("...");
+            // Replies written as JSON are sent as structured values
+            let reply = serde_json::from_str(reply).unwrap_or(Value::String(reply.to_string()));
+            let _ = tx
+                .send(Event::TaskComplete {
+                    sub_id: task.submission_id,
+                    result: TaskResult::Value(reply),
+                })
+                .await;
+            Ok(())
+        }
+    }
+
+    async fn runtime(agents: &[Arc<ChatAgent>]) -> Arc<SingleThreadedRuntime> {
+        let runtime = SingleThreadedRuntime::new(None);
+        for agent in agents {
+            runtime.register_agent(agent.clone()).await.unwrap();
+        }
+        let background = runtime.clone();
+        tokio::spawn(async move { background.run().await });
+        runtime
+    }
+
+    fn speakers(output: &GroupChatOutput) -> Vec<&str> {
+        output.messages.iter().map(|m| m.speaker.as_str()).collect()
+    }
+
+    #[test]
+    fn test_build_requires_participants() {
+        assert!(matches!(
+            GroupChat::builder("empty").build(),
+            Err(GroupChatError::NoParticipants)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_until_max_rounds() {
+        let alice = ChatAgent::new("alice", vec!["hi", "how are you"]);
+        let bob = ChatAgent::new("bob", vec!["hello"]);
+        let runtime = runtime(&[alice.clone(), bob.clone()]).await;
+        let chat = GroupChat::builder("chat")
+            .participant(alice.id)
+            .participant(bob.id)
+            .max_rounds(3)
+            .build()
+            .unwrap();
+
+        let output = chat.run(runtime.clone(), "Say hello").await.unwrap();
+        assert_eq!(speakers(&output), vec!["alice", "bob", "alice"]);
+        assert_eq!(output.termination, TerminationReason::MaxRounds);
+
+        // Later speakers see the shared transcript
+        let prompt = alice.prompts.lock().unwrap()[1].clone();
+        assert!(prompt.contains("Task: Say hello"));
+        assert!(prompt.contains("alice: hi\nbob: hello"));
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_react_outputs_keep_only_the_response() {
+        let alice = ChatAgent::new("alice", vec![r#"{"response": "hi", "tool_calls": []}"#]);
+        let bob = ChatAgent::new("bob", vec![r#"{"score": 3}"#]);
+        let runtime = runtime(&[alice.clone(), bob.clone()]).await;
+        let chat = GroupChat::builder("chat")
+            .participant(alice.id)
+            .participant(bob.id)
+            .max_rounds(3)
+            .build()
+            .unwrap();
+
+        let output = chat.run(runtime.clone(), "Say hello").await.unwrap();
+        assert_eq!(output.messages[0].content, "hi");
+        assert_eq!(output.messages[1].content, r#"{"score":3}"#);
+        let prompt = alice.prompts.lock().unwrap()[1].clone();
+        assert!(prompt.contains("alice: hi\nbob: {\"score\":3}"));
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_one_session_at_a_time() {
+        let alice = ChatAgent::new("alice", vec![]);
+        let runtime = runtime(std::slice::from_ref(&alice)).await;
+        let chat = GroupChat::builder("chat")
+            .participant(alice.id)
+            .max_rounds(2)
+            .build()
+            .unwrap();
+
+        let (first, second) = tokio::join!(
+            chat.run(runtime.clone(), "First"),
+            chat.run(runtime.clone(), "Second")
+        );
+        assert_eq!(first.unwrap().messages.len(), 2);
+        assert!(matches!(
+            second,
+            Err(Error::GroupChatError(GroupChatError::AlreadyRunning(_)))
+        ));
+
+        // Once the session ended the chat can run again
+        assert!(chat.run(runtime.clone(), "Third").await.is_ok());
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_keyword_termination() {
+        let alice = ChatAgent::new("alice", vec!["draft"]);
+        let bob = ChatAgent::new("bob", vec!["APPROVED"]);
+        let runtime = runtime(&[alice.clone(), bob.clone()]).await;
+        let chat = GroupChat::builder("review")
+            .participant(alice.id)
+            .participant(bob.id)
+            .terminate_on_keyword("APPROVED")
+            .build()
+            .unwrap();
+
+        let output = chat.run(runtime.clone(), "Review").await.unwrap();
+        assert_eq!(output.messages.len(), 2);
+        assert_eq!(
+            output.termination,
+            TerminationReason::Keyword("APPROVED".to_string())
+        );
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_judge_termination() {
+        let alice = ChatAgent::new("alice", vec!["one", "two"]);
+        let judge = ChatAgent::new(
+            "judge",
+            vec![
+                r#"{"terminate": false, "reason": "go on"}"#,
+                r#"{"terminate": true, "reason": "settled"}"#,
+            ],
+        );
+        let runtime = runtime(&[alice.clone(), judge.clone()]).await;
+        let chat = GroupChat::builder("debate")
+            .participant(alice.id)
+            .judge(judge.id)
+            .build()
+            .unwrap();
+
+        let output = chat.run(runtime.clone(), "Debate").await.unwrap();
+        assert_eq!(output.messages.len(), 2);
+        assert_eq!(
+            output.termination,
+            TerminationReason::Judge("settled".to_string())
+        );
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_llm_speaker_selection() {
+        let alice = ChatAgent::new("alice", vec![]);
+        let bob = ChatAgent::new("bob", vec![]);
+        let runtime = runtime(&[alice.clone(), bob.clone()]).await;
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec![
+            r#"{"speaker": "bob"}"#,
+            r#"{"speaker": "bob"}"#,
+        ]));
+        let chat = GroupChat::builder("chat")
+            .participant(alice.id)
+            .participant(bob.id)
+            .speaker_selection(SpeakerSelection::Llm(llm.clone()))
+            .max_rounds(2)
+            .build()
+            .unwrap();
+
+        let output = chat.run(runtime.clone(), "Talk").await.unwrap();
+        assert_eq!(speakers(&output), vec!["bob", "bob"]);
+        assert!(llm.requests()[0][0].content.contains("- alice:"));
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_custom_speaker_selection() {
+        let alice = ChatAgent::new("alice", vec![]);
+        let bob = ChatAgent::new("bob", vec![]);
+        let runtime = runtime(&[alice.clone(), bob.clone()]).await;
+        let bob_id = bob.id;
+        let chat = GroupChat::builder("chat")
+            .participant(alice.id)
+            .participant(bob.id)
+            .speaker_selection(SpeakerSelection::Custom(Arc::new(move |state| {
+                (state.round < 3).then_some(bob_id)
+            })))
+            .build()
+            .unwrap();
+
+        let output = chat.run(runtime.clone(), "Talk").await.unwrap();
+        assert_eq!(speakers(&output), vec!["bob", "bob"]);
+        assert_eq!(output.termination, TerminationReason::NoSpeaker);
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_messages_published_to_topic() {
+        let alice = ChatAgent::new("alice", vec!["hi"]);
+        let observer = ChatAgent::new("observer", vec![]);
+        let runtime = runtime(&[alice.clone(), observer.clone()]).await;
+        runtime
+            .subscribe(observer.id, "chat".to_string())
+            .await
+            .unwrap();
+        let chat = GroupChat::builder("chat")
+            .participant(alice.id)
+            .topic("chat")
+            .max_rounds(1)
+            .build()
+            .unwrap();
+
+        chat.run(runtime.clone(), "Talk").await.unwrap();
+        for _ in 0..50 {
+            if !observer.prompts.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(observer.prompts.lock().unwrap()[0], "alice: hi");
+        runtime.stop().await.unwrap();
+    }
+}
@@ -1,7 +1,14 @@
 pub mod agent;
+pub mod budget;
+pub mod checkpoint;
 pub mod environment;
 pub mod error;
+pub mod fallback;
+pub mod group_chat;
+pub mod guardrail;
 pub mod memory;
+pub mod middleware;
 pub mod protocol;
 pub mod runtime;
 pub mod tool;
+pub mod workflow;
@@ -0,0 +1,630 @@
+//! Hooks around the work of an agent.
+//!
+//! Middleware added with
+//! [`AgentBuilder::with_middleware`](crate::agent::AgentBuilder::with_middleware) is called
+//! when a task starts and ends, around every LLM call and around every tool call. The hooks
+//! wrap the LLM provider and the tools handed to the executor, so every executor, prebuilt
+//! or custom, goes through them. Middleware runs in the order it was added.
+//!
+//! The LLM hooks see chat calls, `chat` and `chat_with_tools`. Streaming chats, completions
+//! and embeddings are passed to the provider without calling them.
+use crate::protocol::{AgentID, SubmissionId, TaskResult};
+use crate::runtime::Task;
+use crate::tool::{
+    ArgumentsValidationError, Capability, ToolCallContext, ToolCallError, ToolRuntime, ToolT,
+};
+use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatProvider, ChatResponse, StructuredOutputFormat, Tool};
+use autoagents_llm::completion::{CompletionProvider, CompletionRequest, CompletionResponse};
+use autoagents_llm::embedding::EmbeddingProvider;
+use autoagents_llm::error::LLMError;
+use autoagents_llm::models::{ModelListRequest, ModelListResponse, ModelsProvider};
+use autoagents_llm::LLMProvider;
+use futures::Stream;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::pin::Pin;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Error type for middleware
+#[derive(Debug, Error)]
+pub enum MiddlewareError {
+    #[error("Call to tool '{tool}' was vetoed: {reason}")]
+    ToolCallVetoed { tool: String, reason: String },
+}
+
+impl From<MiddlewareError> for ToolCallError {
+    fn from(error: MiddlewareError) -> Self {
+        ToolCallError::RuntimeError(Box::new(error))
+    }
+}
+
+/// The task a hook is called for
+#[derive(Debug, Clone)]
+pub struct MiddlewareContext {
+    pub sub_id: SubmissionId,
+    pub agent_id: AgentID,
+    pub agent_name: &'static str,
+}
+
+/// Decision of a middleware about a tool call
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolCallDecision {
+    Proceed,
+    /// Refuse the call, the model receives the reason as the tool's error
+    Veto(String),
+}
+
+/// Hooks around the tasks, LLM calls and tool calls of an agent
+#[async_trait]
+pub trait Middleware: Send + Sync + Debug {
+    /// A task was handed to the agent
+    async fn on_task_start(&self, _context: &MiddlewareContext, _task: &Task) {}
+
+    /// An LLM call is about to be made, `messages` may be rewritten.
+    ///
+    /// Returning a response skips the call, for example to answer from a cache.
+    async fn before_llm_call(
+        &self,
+        _context: &MiddlewareContext,
+        _messages: &mut Vec<ChatMessage>,
+    ) -> Option<Box<dyn ChatResponse>> {
+        None
+    }
+
+    /// An LLM call returned
+    async fn after_llm_call(
+        &self,
+        _context: &MiddlewareContext,
+        _messages: &[ChatMessage],
+        _response: Result<&dyn ChatResponse, &LLMError>,
+    ) {
+    }
+
+    /// A tool is about to be called, `args` may be rewritten.
+    ///
+    /// Rewritten arguments are validated against the tool's schema again, invalid ones fail
+    /// the call.
+    async fn before_tool_call(
+        &self,
+        _context: &MiddlewareContext,
+        _tool_name: &str,
+        _args: &mut Value,
+    ) -> ToolCallDecision {
+        ToolCallDecision::Proceed
+    }
+
+    /// A tool call returned, or was vetoed
+    async fn after_tool_call(
+        &self,
+        _context: &MiddlewareContext,
+        _tool_name: &str,
+        _args: &Value,
+        _result: Result<&Value, &ToolCallError>,
+    ) {
+    }
+
+    /// The task ended
+    async fn on_task_end(&self, _context: &MiddlewareContext, _result: &TaskResult) {}
+}
+
+/// Middleware of an agent, applied on behalf of one submission
+#[derive(Debug, Clone)]
+pub(crate) struct MiddlewareChain {
+    middleware: Arc<[Arc<dyn Middleware>]>,
+    context: MiddlewareContext,
+}
+
+impl MiddlewareChain {
+    pub(crate) fn new(middleware: &[Arc<dyn Middleware>], context: MiddlewareContext) -> Self {
+        Self {
+            middleware: middleware.into(),
+            context,
+        }
+    }
+
+    pub(crate) async fn on_task_start(&self, task: &Task) {
+        for middleware in self.middleware.iter() {
+            middleware.on_task_start(&self.context, task).await;
+        }
+    }
+
+    pub(crate) async fn on_task_end(&self, result: &TaskResult) {
+        for middleware in self.middleware.iter() {
+            middleware.on_task_end(&self.context, result).await;
+        }
+    }
+
+    /// Route the LLM calls of the executor through the middleware
+    pub(crate) fn wrap_llm(&self, llm: Arc<dyn LLMProvider>) -> Arc<dyn LLMProvider> {
+        Arc::new(MiddlewareLLMProvider {
+            inner: llm,
+            chain: self.clone(),
+        })
+    }
+
+    /// Route the tool calls of the executor through the middleware
+    pub(crate) fn wrap_tools(&self, tools: Vec<Box<dyn ToolT>>) -> Vec<Box<dyn ToolT>> {
+        tools
+            .into_iter()
+            .map(|tool| {
+                Box::new(MiddlewareTool {
+                    inner: tool,
+                    chain: self.clone(),
+                }) as Box<dyn ToolT>
+            })
+            .collect()
+    }
+}
+
+/// LLM provider calling the LLM hooks around another provider
+struct MiddlewareLLMProvider {
+    inner: Arc<dyn LLMProvider>,
+    chain: MiddlewareChain,
+}
+
+#[async_trait]
+impl ChatProvider for MiddlewareLLMProvider {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let context = &self.chain.context;
+        let mut messages = messages.to_vec();
+        let mut response = None;
+        for middleware in self.chain.middleware.iter() {
+            response = middleware.before_llm_call(context, &mut messages).await;
+            if response.is_some() {
+                break;
+            }
+        }
+        let response = match response {
+            Some(response) => Ok(response),
+            None => {
+                self.inner
+                    .chat_with_tools(&messages, tools, json_schema)
+                    .await
+            }
+        };
+        for middleware in self.chain.middleware.iter() {
+            middleware
+                .after_llm_call(context, &messages, response.as_deref())
+                .await;
+        }
+        response
+    }
+
+    /// Not seen by the LLM hooks
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.inner.chat_stream(messages).await
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for MiddlewareLLMProvider {
+    /// Not seen by the LLM hooks
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+        json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        self.inner.complete(req, json_schema).await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for MiddlewareLLMProvider {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.inner.embed(input).await
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for MiddlewareLLMProvider {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.inner.list_models(request).await
+    }
+}
+
+impl LLMProvider for MiddlewareLLMProvider {
+    fn tools(&self) -> Option<&[Tool]> {
+        self.inner.tools()
+    }
+
+    fn supports_tool_calls(&self) -> bool {
+        self.inner.supports_tool_calls()
+    }
+}
+
+/// Tool calling the tool hooks around another tool
+#[derive(Debug)]
+struct MiddlewareTool {
+    inner: Box<dyn ToolT>,
+    chain: MiddlewareChain,
+}
+
+impl ToolRuntime for MiddlewareTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        self.inner.run(args)
+    }
+}
+
+#[async_trait]
+impl ToolT for MiddlewareTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn validate_args(&self, args: &Value) -> Result<(), ArgumentsValidationError> {
+        self.inner.validate_args(args)
+    }
+
+    fn returns_directly(&self) -> bool {
+        self.inner.returns_directly()
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.inner.capabilities()
+    }
+
+    async fn call(&self, args: Value, context: ToolCallContext) -> Result<Value, ToolCallError> {
+        let name = self.inner.name();
+        let mut args = args;
+        let mut veto = None;
+        for middleware in self.chain.middleware.iter() {
+            if let ToolCallDecision::Veto(reason) = middleware
+                .before_tool_call(&self.chain.context, name, &mut args)
+                .await
+            {
+                veto = Some(reason);
+                break;
+            }
+        }
+        let result = match veto {
+            Some(reason) => Err(MiddlewareError::ToolCallVetoed {
+                tool: name.to_string(),
+                reason,
+            }
+            .into()),
+            // The hooks may have rewritten the arguments the executor validated
+            None => match self.inner.validate_args(&args) {
+                Err(e @ ArgumentsValidationError::Invalid(_)) => {
+                    Err(ToolCallError::RuntimeError(Box::new(e)))
+                }
+                _ => self.inner.call(args.clone(), context).await,
+            },
+        };
+        for middleware in self.chain.middleware.iter() {
+            middleware
+                .after_tool_call(&self.chain.context, name, &args, result.as_ref())
+                .await;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::prebuilt::react::ReActExecutor;
+    use crate::agent::{AgentDeriveT, BaseAgent, IntoRunnable};
+    use crate::protocol::Event;
+    use autoagents_llm::chat::{ChatRole, MessageType};
+    use autoagents_llm::ToolCall;
+    use autoagents_test_utils::llm::{tool_call, ScriptedLLMProvider, ScriptedResponse};
+    use std::sync::Mutex;
+    use tokio::sync::mpsc;
+
+    #[derive(Debug)]
+    struct LookupTool;
+
+    impl ToolRuntime for LookupTool {
+        fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            Ok(serde_json::json!({"order": args["id"], "status": "shipped"}))
+        }
+    }
+
+    impl ToolT for LookupTool {
+        fn name(&self) -> &'static str {
+            "lookup"
+        }
+
+        fn description(&self) -> &'static str {
+            "Look up an order"
+        }
+
+        fn args_schema(&self) -> Value {
+            serde_json::json!({"type": "object", "properties": {"id": {"type": "integer"}}})
+        }
+    }
+
+    #[derive(Debug)]
+    struct CancelTool;
+
+    impl ToolRuntime for CancelTool {
+        fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            panic!("A vetoed tool must not run");
+        }
+    }
+
+    impl ToolT for CancelTool {
+        fn name(&self) -> &'static str {
+            "cancel"
+        }
+
+        fn description(&self) -> &'static str {
+            "Cancel an order"
+        }
+
+        fn args_schema(&self) -> Value {
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+    }
+
+    #[derive(Debug)]
+    struct OrdersAgent;
+
+    impl ReActExecutor for OrdersAgent {}
+
+    impl AgentDeriveT for OrdersAgent {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "Answers questions about orders"
+        }
+
+        fn output_schema(&self) -> Option<Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "orders"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![Box::new(LookupTool), Box::new(CancelTool)]
+        }
+    }
+
+    /// Records every hook, tags the system prompt, pins order ids and refuses cancellations
+    #[derive(Debug, Default)]
+    struct RecordingMiddleware {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl RecordingMiddleware {
+        fn record(&self, call: impl Into<String>) {
+            self.calls.lock().unwrap().push(call.into());
+        }
+    }
+
+    #[async_trait]
+    impl Middleware for RecordingMiddleware {
+        async fn on_task_start(&self, context: &MiddlewareContext, task: &Task) {
+            assert_eq!(context.sub_id, task.submission_id);
+            self.record(format!("task_start {}", context.agent_name));
+        }
+
+        async fn before_llm_call(
+            &self,
+            _context: &MiddlewareContext,
+            messages: &mut Vec<ChatMessage>,
+        ) -> Option<Box<dyn ChatResponse>> {
+            self.record("before_llm");
+            messages[0].content.push_str("\nAnswer in one sentence.");
+            None
+        }
+
+        async fn after_llm_call(
+            &self,
+            _context: &MiddlewareContext,
+            _messages: &[ChatMessage],
+            response: Result<&dyn ChatResponse, &LLMError>,
+        ) {
+            self.record(format!("after_llm ok={}", response.is_ok()));
+        }
+
+        async fn before_tool_call(
+            &self,
+            _context: &MiddlewareContext,
+            tool_name: &str,
+            args: &mut Value,
+        ) -> ToolCallDecision {
+            self.record(format!("before_tool {tool_name}"));
+            match tool_name {
+                "cancel" => ToolCallDecision::Veto("cancellations need a human".to_string()),
+                _ => {
+                    args["id"] = serde_json::json!(42);
+                    ToolCallDecision::Proceed
+                }
+            }
+        }
+
+        async fn after_tool_call(
+            &self,
+            _context: &MiddlewareContext,
+            tool_name: &str,
+            _args: &Value,
+            result: Result<&Value, &ToolCallError>,
+        ) {
+            self.record(format!("after_tool {tool_name} ok={}", result.is_ok()));
+        }
+
+        async fn on_task_end(&self, _context: &MiddlewareContext, result: &TaskResult) {
+            self.record(format!(
+                "task_end value={}",
+                matches!(result, TaskResult::Value(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_runs_middleware_hooks() {
+        let llm = Arc::new(ScriptedLLMProvider::new(vec![
+            ScriptedResponse::ToolCalls(vec![
+                tool_call("call_1", "lookup", r#"{"id": 7}"#),
+                tool_call("call_2", "cancel", "{}"),
+            ]),
+            ScriptedResponse::Text("Order 42 has shipped".to_string()),
+        ]));
+        let middleware = Arc::new(RecordingMiddleware::default());
+        let mut agent = BaseAgent::new(OrdersAgent, llm.clone(), None);
+        agent.middleware = vec![middleware.clone()];
+        let (tx, _rx) = mpsc::channel(100);
+        agent
+            .into_runnable()
+            .run(Task::new("Where is my order?", None), tx)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *middleware.calls.lock().unwrap(),
+            vec![
+                "task_start orders",
+                "before_llm",
+                "after_llm ok=true",
+                "before_tool lookup",
+                "after_tool lookup ok=true",
+                "before_tool cancel",
+                "after_tool cancel ok=false",
+                "before_llm",
+                "after_llm ok=true",
+                "task_end value=true",
+            ]
+        );
+
+        let requests = llm.requests();
+        assert_eq!(requests[0][0].role, ChatRole::System);
+        assert!(requests[0][0].content.ends_with("Answer in one sentence."));
+        let MessageType::ToolResult(results) = &requests[1][3].message_type else {
+            panic!("Expected tool results");
+        };
+        // The rewritten arguments reached the tool, the vetoed call reached the model as an error
+        assert!(results[0].function.arguments.contains("42"));
+        assert!(results[1]
+            .function
+            .arguments
+            .contains("cancellations need a human"));
+    }
+
+    #[derive(Debug)]
+    struct CachedResponse(String);
+
+    impl std::fmt::Display for CachedResponse {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    impl ChatResponse for CachedResponse {
+        fn text(&self) -> Option<String> {
+            Some(self.0.clone())
+        }
+
+        fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+            None
+        }
+    }
+
+    #[derive(Debug)]
+    struct CacheMiddleware;
+
+    #[async_trait]
+    impl Middleware for CacheMiddleware {
+        async fn before_llm_call(
+            &self,
+            _context: &MiddlewareContext,
+            _messages: &mut Vec<ChatMessage>,
+        ) -> Option<Box<dyn ChatResponse>> {
+            Some(Box::new(CachedResponse("From the cache".to_string())))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_can_skip_llm_call() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec!["From the model"]));
+        let mut agent = BaseAgent::new(OrdersAgent, llm.clone(), None);
+        agent.middleware = vec![Arc::new(CacheMiddleware)];
+        let task = Task::new("Where is my order?", None);
+        let sub_id = task.submission_id;
+        let (tx, mut rx) = mpsc::channel(100);
+        agent.into_runnable().run(task, tx).await.unwrap();
+
+        assert_eq!(llm.remaining(), 1);
+        let mut output = None;
+        while let Ok(event) = rx.try_recv() {
+            if let Event::TaskComplete {
+                sub_id: id,
+                result: TaskResult::Value(value),
+            } = event
+            {
+                assert_eq!(id, sub_id);
+                output = Some(value);
+            }
+        }
+        assert_eq!(output.unwrap()["response"], "From the cache");
+    }
+
+    /// Rewrites order ids to text, which the lookup tool's schema does not allow
+    #[derive(Debug)]
+    struct TextIdMiddleware;
+
+    #[async_trait]
+    impl Middleware for TextIdMiddleware {
+        async fn before_tool_call(
+            &self,
+            _context: &MiddlewareContext,
+            _tool_name: &str,
+            args: &mut Value,
+        ) -> ToolCallDecision {
+            args["id"] = serde_json::json!("forty-two");
+            ToolCallDecision::Proceed
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rewritten_arguments_are_validated() {
+        let chain = MiddlewareChain::new(
+            &[Arc::new(TextIdMiddleware)],
+            MiddlewareContext {
+                sub_id: uuid::Uuid::new_v4(),
+                agent_id: uuid::Uuid::new_v4(),
+                agent_name: "orders",
+            },
+        );
+        let tools = chain.wrap_tools(vec![Box::new(LookupTool)]);
+        let (tx, _rx) = mpsc::channel(10);
+        let context = ToolCallContext {
+            sub_id: chain.context.sub_id,
+            call_id: "call_1".to_string(),
+            tx_event: tx,
+            memory: None,
+            capabilities: None,
+        };
+
+        let error = tools[0]
+            .call(serde_json::json!({"id": 7}), context)
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Arguments do not match the tool schema"));
+    }
+}
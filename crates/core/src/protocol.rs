@@ -1,4 +1,11 @@
+use crate::agent::prebuilt::router::RouteMethod;
+use crate::budget::{BudgetLimit, BudgetUsage};
+use crate::fallback::FallbackReason;
+use crate::group_chat::TerminationReason;
+use crate::guardrail::GuardrailStage;
 use crate::runtime::Task;
+use crate::tool::output::OutputStrategy;
+use crate::tool::{ArgumentViolation, Capability};
 use autoagents_llm::chat::ChatMessage;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -15,13 +22,19 @@ pub type RuntimeID = Uuid;
 /// Event IDs are used to correlate events with their responses
 pub type EventId = Uuid;
 
+/// Group chat IDs are used to correlate the events of one group chat session
+pub type GroupChatId = Uuid;
+
+/// Workflow run IDs are used to correlate the events of one workflow execution
+pub type WorkflowRunId = Uuid;
+
 /// Protocol events represent the various events that can occur during agent execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Event {
     /// A new task has been submitted to an agent
     NewTask {
         agent_id: AgentID,
-        task: Task,
+        task: Box<Task>,
     },
 
     /// A task has started execution
@@ -64,17 +77,215 @@ pub enum Event {
         error: String,
     },
 
+    /// Tool call arguments were rejected by the tool's schema before execution
+    ToolCallValidationFailed {
+        id: String,
+        tool_name: String,
+        arguments: String,
+        violations: Vec<ArgumentViolation>,
+    },
+
     /// A turn has started
     TurnStarted {
+        sub_id: SubmissionId,
         turn_number: usize,
         max_turns: usize,
     },
 
     /// A turn has completed
     TurnCompleted {
+        sub_id: SubmissionId,
         turn_number: usize,
         final_turn: bool,
     },
+
+    /// The final response did not match the agent's output schema
+    OutputValidationFailed {
+        sub_id: SubmissionId,
+        attempt: usize,
+        error: String,
+    },
+
+    /// The agent kept repeating the same tool calls
+    ToolLoopDetected {
+        sub_id: SubmissionId,
+        /// Tools of the repeated sequence of calls
+        tools: Vec<String>,
+        repeats: usize,
+    },
+
+    /// A tool call was refused, the agent was not granted the capabilities the tool needs
+    ToolCallDenied {
+        sub_id: SubmissionId,
+        tool_name: String,
+        missing: Vec<Capability>,
+    },
+
+    /// A tool result went over its output limit and was cut down before reaching memory
+    ToolOutputLimited {
+        sub_id: SubmissionId,
+        tool_name: String,
+        strategy: OutputStrategy,
+        /// Characters of the result before it was limited
+        original_chars: usize,
+        /// Artifact holding the full result, if it was spilled
+        artifact_id: Option<String>,
+    },
+
+    /// A task reached a limit of its budget, its remaining LLM calls are refused
+    BudgetExceeded {
+        sub_id: SubmissionId,
+        limit: BudgetLimit,
+        usage: BudgetUsage,
+    },
+
+    /// An LLM provider failed and the call moved on to the next provider of the chain
+    LLMFallback {
+        sub_id: SubmissionId,
+        provider: String,
+        next: String,
+        reason: FallbackReason,
+        error: String,
+    },
+
+    /// The named provider of an agent's fallback chain answered an LLM call
+    LLMResponded {
+        sub_id: SubmissionId,
+        provider: String,
+    },
+
+    /// A guardrail rewrote or blocked content of a task
+    GuardrailTriggered {
+        sub_id: SubmissionId,
+        guardrail: String,
+        stage: GuardrailStage,
+        blocked: bool,
+        reason: String,
+    },
+
+    /// The progress of a task was checkpointed after a completed turn
+    CheckpointSaved {
+        sub_id: SubmissionId,
+        turn: usize,
+    },
+
+    /// A task was resumed from its last checkpoint
+    TaskResumed {
+        sub_id: SubmissionId,
+        agent_id: AgentID,
+        turn: usize,
+    },
+
+    /// A plan was created, or revised after a failed step
+    PlanCreated {
+        sub_id: SubmissionId,
+        revision: usize,
+        steps: Vec<String>,
+    },
+
+    /// A plan step has started
+    PlanStepStarted {
+        sub_id: SubmissionId,
+        step_index: usize,
+        description: String,
+    },
+
+    /// A plan step has completed
+    PlanStepCompleted {
+        sub_id: SubmissionId,
+        step_index: usize,
+        success: bool,
+        response: String,
+    },
+
+    /// A reflection critic reviewed a draft
+    ReflectionRoundCompleted {
+        sub_id: SubmissionId,
+        round: usize,
+        approved: bool,
+        feedback: String,
+    },
+
+    /// A router chose the agent that handles a task
+    TaskRouted {
+        sub_id: SubmissionId,
+        agent_id: AgentID,
+        agent_name: String,
+        method: RouteMethod,
+        reason: String,
+    },
+
+    /// A task was handed off to another agent, which completes it under the same submission
+    TaskHandedOff {
+        sub_id: SubmissionId,
+        agent_id: AgentID,
+        agent_name: String,
+        task: String,
+    },
+
+    /// A workflow run has started
+    WorkflowStarted {
+        workflow_id: WorkflowRunId,
+        name: String,
+    },
+
+    /// A workflow node was handed to its agent
+    WorkflowNodeStarted {
+        workflow_id: WorkflowRunId,
+        node: String,
+        agent_id: AgentID,
+        sub_id: SubmissionId,
+    },
+
+    /// A workflow node has finished
+    WorkflowNodeCompleted {
+        workflow_id: WorkflowRunId,
+        node: String,
+        sub_id: SubmissionId,
+        result: TaskResult,
+    },
+
+    /// A workflow node was skipped because none of its incoming edges were taken
+    WorkflowNodeSkipped {
+        workflow_id: WorkflowRunId,
+        node: String,
+    },
+
+    /// A workflow run has finished, with the outputs of its nodes on success
+    WorkflowCompleted {
+        workflow_id: WorkflowRunId,
+        result: TaskResult,
+    },
+
+    /// A group chat session has started
+    GroupChatStarted {
+        chat_id: GroupChatId,
+        name: String,
+        participants: Vec<String>,
+    },
+
+    /// A participant spoke in a group chat
+    GroupChatMessage {
+        chat_id: GroupChatId,
+        round: usize,
+        agent_id: AgentID,
+        speaker: String,
+        content: String,
+    },
+
+    /// A group chat session has ended
+    GroupChatEnded {
+        chat_id: GroupChatId,
+        rounds: usize,
+        termination: TerminationReason,
+    },
+
+    /// An event of a sub-agent called as a tool by the task `parent_sub_id`
+    SubAgentEvent {
+        parent_sub_id: SubmissionId,
+        agent_id: AgentID,
+        event: Box<Event>,
+    },
     PublishMessage {
         topic: String,
         message: String,
@@ -99,6 +310,7 @@ pub enum TaskResult {
 }
 
 /// Messages from the agent - used for A2A communication
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AgentMessage {
     /// The content of the message
@@ -118,7 +330,7 @@ mod tests {
         let agent_id = Uuid::new_v4();
         let event = Event::NewTask {
             agent_id,
-            task: Task::new(String::from("test"), Some(agent_id)),
+            task: Box::new(Task::new(String::from("test"), Some(agent_id))),
         };
 
         //Check if serialization and deserilization works properly
@@ -233,6 +445,7 @@ mod tests {
     #[test]
     fn test_event_serialization_turn_events() {
         let turn_started = Event::TurnStarted {
+            sub_id: Uuid::new_v4(),
             turn_number: 1,
             max_turns: 10,
         };
@@ -244,6 +457,7 @@ mod tests {
             Event::TurnStarted {
                 turn_number,
                 max_turns,
+                ..
             } => {
                 assert_eq!(turn_number, 1);
                 assert_eq!(max_turns, 10);
@@ -252,6 +466,7 @@ mod tests {
         }
 
         let turn_completed = Event::TurnCompleted {
+            sub_id: Uuid::new_v4(),
             turn_number: 1,
             final_turn: false,
         };
@@ -263,6 +478,7 @@ mod tests {
             Event::TurnCompleted {
                 turn_number,
                 final_turn,
+                ..
             } => {
                 assert_eq!(turn_number, 1);
                 assert!(!final_turn);
@@ -0,0 +1,174 @@
+use autoagents_llm::chat::{ChatMessage, ChatRole, ImageMime, MessageType};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A file sent to an agent along with the prompt of a task
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Attachment {
+    /// Raw image bytes
+    Image { mime: ImageMime, data: Vec<u8> },
+    /// Image the provider fetches itself
+    ImageUrl(String),
+    /// PDF document, providers without PDF input refuse the request
+    Pdf(Vec<u8>),
+    /// Any other file
+    File {
+        name: String,
+        mime: String,
+        data: Vec<u8>,
+    },
+}
+
+impl Attachment {
+    pub fn image(mime: ImageMime, data: impl Into<Vec<u8>>) -> Self {
+        Self::Image {
+            mime,
+            data: data.into(),
+        }
+    }
+
+    pub fn image_url(url: impl Into<String>) -> Self {
+        Self::ImageUrl(url.into())
+    }
+
+    pub fn pdf(data: impl Into<Vec<u8>>) -> Self {
+        Self::Pdf(data.into())
+    }
+
+    pub fn file(
+        name: impl Into<String>,
+        mime: impl Into<String>,
+        data: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self::File {
+            name: name.into(),
+            mime: mime.into(),
+            data: data.into(),
+        }
+    }
+
+    /// Read a file, its kind is guessed from the extension
+    pub fn from_path(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let image_mime = match extension.as_str() {
+            "png" => Some(ImageMime::PNG),
+            "jpg" | "jpeg" => Some(ImageMime::JPEG),
+            "gif" => Some(ImageMime::GIF),
+            "webp" => Some(ImageMime::WEBP),
+            _ => None,
+        };
+        if let Some(mime) = image_mime {
+            return Ok(Self::image(mime, data));
+        }
+        let mime = match extension.as_str() {
+            "pdf" => return Ok(Self::pdf(data)),
+            "txt" | "log" => "text/plain",
+            "md" => "text/markdown",
+            "csv" => "text/csv",
+            "html" | "htm" => "text/html",
+            "json" => "application/json",
+            "xml" => "application/xml",
+            "yaml" | "yml" => "application/yaml",
+            "toml" => "application/toml",
+            _ => "application/octet-stream",
+        };
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Self::file(name, mime, data))
+    }
+
+    /// Text of a file with a textual MIME type
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Attachment::File { mime, data, .. } if is_textual(mime) => {
+                std::str::from_utf8(data).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// User message carrying the attachment.
+    ///
+    /// Files with a textual MIME type are inlined as text, other files can't be shown to the
+    /// model and are replaced by a note naming them.
+    pub fn to_message(&self) -> ChatMessage {
+        let (message_type, content) = match self {
+            Attachment::Image { mime, data } => (
+                MessageType::Image((mime.clone(), data.clone())),
+                String::new(),
+            ),
+            Attachment::ImageUrl(url) => (MessageType::ImageURL(url.clone()), String::new()),
+            Attachment::Pdf(data) => (MessageType::Pdf(data.clone()), String::new()),
+            Attachment::File { name, mime, data } => {
+                let content = match self.text() {
+                    Some(text) => format!("File {name} ({mime}):\n{text}"),
+                    None => format!(
+                        "File {name} ({mime}, {} bytes) is attached but can't be displayed",
+                        data.len()
+                    ),
+                };
+                (MessageType::Text, content)
+            }
+        };
+        ChatMessage {
+            role: ChatRole::User,
+            message_type,
+            content,
+        }
+    }
+}
+
+fn is_textual(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || matches!(
+            mime,
+            "application/json" | "application/xml" | "application/yaml" | "application/toml"
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path_guesses_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("frame.JPG");
+        std::fs::write(&image, [0xff, 0xd8]).unwrap();
+        assert_eq!(
+            Attachment::from_path(&image).unwrap(),
+            Attachment::image(ImageMime::JPEG, vec![0xff, 0xd8])
+        );
+
+        let config = dir.path().join("config.toml");
+        std::fs::write(&config, "debug = true").unwrap();
+        let attachment = Attachment::from_path(&config).unwrap();
+        assert_eq!(
+            attachment,
+            Attachment::file("config.toml", "application/toml", "debug = true")
+        );
+        assert_eq!(
+            attachment.to_message().content,
+            "File config.toml (application/toml):\ndebug = true"
+        );
+    }
+
+    #[test]
+    fn test_binary_file_is_described() {
+        let message = Attachment::file("model.bin", "application/octet-stream", vec![0, 159, 146])
+            .to_message();
+        assert_eq!(message.role, ChatRole::User);
+        assert_eq!(message.message_type, MessageType::Text);
+        assert_eq!(
+            message.content,
+            "File model.bin (application/octet-stream, 3 bytes) is attached but can't be displayed"
+        );
+    }
+}
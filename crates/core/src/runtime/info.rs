@@ -0,0 +1,71 @@
+use crate::protocol::{AgentID, Event, SubmissionId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Description of a registered agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentInfo {
+    pub id: AgentID,
+    pub name: String,
+    pub description: String,
+    /// Names of the tools the agent can call
+    pub tools: Vec<String>,
+}
+
+/// A submission that started and has not completed yet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubmissionInfo {
+    pub sub_id: SubmissionId,
+    pub agent_id: AgentID,
+    pub prompt: String,
+    /// Turn the executor is in, `None` until the first turn starts
+    pub turn: Option<usize>,
+    pub max_turns: Option<usize>,
+}
+
+/// Submissions in flight, tracked from the events of the agents
+#[derive(Debug, Default)]
+pub(crate) struct SubmissionTracker {
+    submissions: HashMap<SubmissionId, SubmissionInfo>,
+}
+
+impl SubmissionTracker {
+    pub(crate) fn observe(&mut self, event: &Event) {
+        match event {
+            Event::TaskStarted {
+                sub_id,
+                agent_id,
+                task_description,
+            } => {
+                self.submissions.insert(
+                    *sub_id,
+                    SubmissionInfo {
+                        sub_id: *sub_id,
+                        agent_id: *agent_id,
+                        prompt: task_description.clone(),
+                        turn: None,
+                        max_turns: None,
+                    },
+                );
+            }
+            Event::TurnStarted {
+                sub_id,
+                turn_number,
+                max_turns,
+            } => {
+                if let Some(info) = self.submissions.get_mut(sub_id) {
+                    info.turn = Some(*turn_number);
+                    info.max_turns = Some(*max_turns);
+                }
+            }
+            Event::TaskComplete { sub_id, .. } => {
+                self.submissions.remove(sub_id);
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn submissions(&self) -> Vec<SubmissionInfo> {
+        self.submissions.values().cloned().collect()
+    }
+}
@@ -1,19 +1,30 @@
-use crate::agent::{RunnableAgent, RunnableAgentError};
+use crate::agent::{
+    resume_for_result, run_for_result, AgentState, RunnableAgent, RunnableAgentError,
+};
+use crate::budget::Budget;
 use crate::error::Error;
-use crate::protocol::{AgentID, Event, RuntimeID, SubmissionId};
+use crate::protocol::{AgentID, Event, RuntimeID, SubmissionId, TaskResult};
 use async_trait::async_trait;
+use autoagents_llm::chat::{ChatMessage, ChatRole, MessageType};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use single_threaded::InternalEvent;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 use tokio::sync::mpsc::error::SendError;
 use tokio::task::JoinError;
 use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 
+mod attachment;
+mod info;
 pub(crate) mod manager;
 mod single_threaded;
+pub use attachment::Attachment;
+pub(crate) use info::SubmissionTracker;
+pub use info::{AgentInfo, SubmissionInfo};
 pub use single_threaded::SingleThreadedRuntime;
 
 /// Error types for Session operations
@@ -48,6 +59,18 @@ pub struct Task {
     pub completed: bool,
     pub result: Option<Value>,
     agent_id: Option<AgentID>,
+    /// Limits on the tokens and cost of the task, replacing the agent's budget
+    #[serde(default)]
+    pub budget: Option<Box<Budget>>,
+    /// Values the agent's system prompt template is rendered with
+    #[serde(default)]
+    pub context: BTreeMap<String, Value>,
+    /// Images and files sent along with the prompt
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// Number of routers the task was forwarded through
+    #[serde(default)]
+    pub(crate) route_depth: usize,
 }
 
 impl Task {
@@ -58,6 +81,51 @@ impl Task {
             completed: false,
             result: None,
             agent_id,
+            budget: None,
+            context: BTreeMap::new(),
+            attachments: Vec::new(),
+            route_depth: 0,
+        }
+    }
+
+    /// Limit the tokens and cost the task may consume
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(Box::new(budget));
+        self
+    }
+
+    /// Set a value the agent's system prompt template can refer to as `task.<key>`
+    pub fn with_context(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.context.insert(key.into(), value.into());
+        self
+    }
+
+    /// Send an image or file to the agent along with the prompt
+    pub fn with_attachment(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    /// User messages of the task, the prompt followed by one message per attachment
+    pub fn messages(&self) -> Vec<ChatMessage> {
+        let mut messages = vec![ChatMessage {
+            role: ChatRole::User,
+            message_type: MessageType::Text,
+            content: self.prompt.clone(),
+        }];
+        messages.extend(self.attachments.iter().map(Attachment::to_message));
+        messages
+    }
+
+    /// Copy of the task forwarded by a router to `agent_id`, under a new submission
+    pub(crate) fn forwarded_to(&self, agent_id: AgentID) -> Self {
+        Self {
+            submission_id: Uuid::new_v4(),
+            completed: false,
+            result: None,
+            agent_id: Some(agent_id),
+            route_depth: self.route_depth + 1,
+            ..self.clone()
         }
     }
 }
@@ -69,7 +137,65 @@ pub trait Runtime: Send + Sync + 'static + Debug {
     async fn publish_message(&self, message: String, topic: String) -> Result<(), Error>;
     async fn subscribe(&self, agent_id: AgentID, topic: String) -> Result<(), Error>;
     async fn register_agent(&self, agent: Arc<dyn RunnableAgent>) -> Result<(), Error>;
+    async fn get_agent(&self, agent_id: AgentID) -> Option<Arc<dyn RunnableAgent>>;
+    async fn agents(&self) -> Vec<Arc<dyn RunnableAgent>>;
+    /// Agents subscribed to each topic
+    async fn subscriptions(&self) -> HashMap<String, Vec<AgentID>>;
+    /// Submissions of agents run by the runtime that started and have not completed
+    async fn submissions(&self) -> Vec<SubmissionInfo>;
+    /// Sender for events of agents run on behalf of the runtime, routed like agent events
+    fn event_sender(&self) -> mpsc::Sender<Event>;
     async fn take_event_receiver(&self) -> Option<ReceiverStream<Event>>;
     async fn run(&self) -> Result<(), Error>;
     async fn stop(&self) -> Result<(), Error>;
+
+    /// Descriptions of the registered agents
+    async fn agent_infos(&self) -> Vec<AgentInfo> {
+        self.agents()
+            .await
+            .iter()
+            .map(|agent| agent.info())
+            .collect()
+    }
+
+    /// Snapshot of the state of a registered agent, `None` for unknown agents or agents
+    /// without state
+    async fn agent_state(&self, agent_id: AgentID) -> Option<AgentState> {
+        self.get_agent(agent_id).await?.state_snapshot().await
+    }
+
+    /// Run a task on a registered agent and wait for its result
+    async fn execute_task(&self, agent_id: AgentID, task: Task) -> Result<TaskResult, Error> {
+        let agent = self
+            .get_agent(agent_id)
+            .await
+            .ok_or(RuntimeError::AgentNotFound(agent_id))?;
+        let runtime_tx = self.event_sender();
+        runtime_tx
+            .send(Event::NewTask {
+                agent_id,
+                task: Box::new(task.clone()),
+            })
+            .await
+            .map_err(RuntimeError::EventError)?;
+
+        run_for_result(agent, task, runtime_tx, Some).await
+    }
+
+    /// Continue a submission from its last checkpoint on a registered agent and wait
+    /// for its result
+    ///
+    /// After a restart, build the agent with the same checkpoint store and resume the
+    /// submissions the store lists.
+    async fn resume_task(
+        &self,
+        agent_id: AgentID,
+        sub_id: SubmissionId,
+    ) -> Result<TaskResult, Error> {
+        let agent = self
+            .get_agent(agent_id)
+            .await
+            .ok_or(RuntimeError::AgentNotFound(agent_id))?;
+        resume_for_result(agent, sub_id, self.event_sender(), Some).await
+    }
 }
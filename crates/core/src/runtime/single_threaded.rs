@@ -1,4 +1,4 @@
-use super::{Runtime, RuntimeError, Task};
+use super::{Runtime, RuntimeError, SubmissionInfo, SubmissionTracker, Task};
 use crate::{
     agent::RunnableAgent,
     error::Error,
@@ -42,6 +42,7 @@ pub struct SingleThreadedRuntime {
     // Agent and subscription management
     agents: Arc<RwLock<HashMap<AgentID, Arc<dyn RunnableAgent>>>>,
     subscriptions: Arc<RwLock<HashMap<String, Vec<AgentID>>>>,
+    submissions: RwLock<SubmissionTracker>,
     // Runtime state
     shutdown_flag: Arc<AtomicBool>,
     shutdown_notify: Arc<Notify>,
@@ -64,6 +65,7 @@ impl SingleThreadedRuntime {
             internal_rx: Mutex::new(Some(internal_rx)),
             agents: Arc::new(RwLock::new(HashMap::new())),
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            submissions: RwLock::new(SubmissionTracker::default()),
             shutdown_flag: Arc::new(AtomicBool::new(false)),
             shutdown_notify: Arc::new(Notify::new()),
         })
@@ -100,6 +102,7 @@ impl SingleThreadedRuntime {
     }
 
     async fn process_agent_event(&self, event: Event) -> Result<(), Error> {
+        self.submissions.write().await.observe(&event);
         match event {
             Event::PublishMessage { topic, message } => {
                 debug!("Processing publish message to topic: {topic}");
@@ -156,7 +159,7 @@ impl SingleThreadedRuntime {
             self.external_tx
                 .send(Event::NewTask {
                     agent_id,
-                    task: task.clone(),
+                    task: Box::new(task.clone()),
                 })
                 .await
                 .map_err(RuntimeError::EventError)?;
@@ -225,6 +228,26 @@ impl Runtime for SingleThreadedRuntime {
         Ok(())
     }
 
+    async fn get_agent(&self, agent_id: AgentID) -> Option<Arc<dyn RunnableAgent>> {
+        self.agents.read().await.get(&agent_id).cloned()
+    }
+
+    async fn agents(&self) -> Vec<Arc<dyn RunnableAgent>> {
+        self.agents.read().await.values().cloned().collect()
+    }
+
+    fn event_sender(&self) -> mpsc::Sender<Event> {
+        self.create_intercepting_sender()
+    }
+
+    async fn subscriptions(&self) -> HashMap<String, Vec<AgentID>> {
+        self.subscriptions.read().await.clone()
+    }
+
+    async fn submissions(&self) -> Vec<SubmissionInfo> {
+        self.submissions.read().await.submissions()
+    }
+
     async fn subscribe(&self, agent_id: AgentID, topic: String) -> Result<(), Error> {
         info!("Agent {:?} subscribing to topic: {}", agent_id, topic);
 
@@ -266,6 +289,11 @@ impl Runtime for SingleThreadedRuntime {
                     if let Err(e) = self.process_internal_event(event).await {
                         error!("Error processing internal event: {e}");
                     }
+                    // The shutdown notification fires while no one is waiting on it
+                    if self.shutdown_flag.load(Ordering::SeqCst) {
+                        info!("Runtime received shutdown signal");
+                        break;
+                    }
                 }
                 // Check for shutdown
                 _ = self.shutdown_notify.notified() => {
@@ -302,8 +330,13 @@ impl Runtime for SingleThreadedRuntime {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::prebuilt::react::ReActExecutor;
+    use crate::agent::{AgentDeriveT, BaseAgent, IntoRunnable};
     use crate::memory::MemoryProvider;
     use crate::protocol::TaskResult;
+    use crate::tool::{ToolCallError, ToolRuntime, ToolT};
+    use autoagents_test_utils::llm::ScriptedLLMProvider;
+    use serde_json::Value;
     use tokio::time::{sleep, Duration};
 
     #[derive(Debug, Clone)]
@@ -373,6 +406,40 @@ mod tests {
         assert!(agents.contains_key(&agent.id()));
     }
 
+    #[tokio::test]
+    async fn test_execute_task() {
+        let runtime = SingleThreadedRuntime::new(None);
+        let agent = Arc::new(MockAgent { id: Uuid::new_v4() });
+        runtime.register_agent(agent.clone()).await.unwrap();
+        let mut events = runtime.take_event_receiver().await.unwrap();
+        let handle = {
+            let runtime = runtime.clone();
+            tokio::spawn(async move { runtime.run().await })
+        };
+
+        let result = runtime
+            .execute_task(agent.id, Task::new("hello", Some(agent.id)))
+            .await
+            .unwrap();
+        match result {
+            TaskResult::Value(value) => {
+                assert_eq!(value["message"], "Task completed successfully")
+            }
+            other => panic!("Expected value, got {other:?}"),
+        }
+
+        use tokio_stream::StreamExt;
+        assert!(matches!(events.next().await, Some(Event::NewTask { .. })));
+
+        let missing = runtime
+            .execute_task(Uuid::new_v4(), Task::new("hello", None))
+            .await;
+        assert!(missing.is_err());
+
+        runtime.stop().await.unwrap();
+        handle.await.unwrap().unwrap();
+    }
+
     #[tokio::test]
     async fn test_subscription() {
         let runtime = SingleThreadedRuntime::new(None);
@@ -385,4 +452,175 @@ mod tests {
         assert!(subscriptions.contains_key(&topic));
         assert!(subscriptions.get(&topic).unwrap().contains(&agent_id));
     }
+
+    #[derive(Debug)]
+    struct LookupTool;
+
+    impl ToolRuntime for LookupTool {
+        fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            Ok(Value::Null)
+        }
+    }
+
+    impl ToolT for LookupTool {
+        fn name(&self) -> &'static str {
+            "lookup"
+        }
+
+        fn description(&self) -> &'static str {
+            "Look up an order"
+        }
+
+        fn args_schema(&self) -> Value {
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+    }
+
+    #[derive(Debug)]
+    struct ClerkAgent;
+
+    impl ReActExecutor for ClerkAgent {}
+
+    impl AgentDeriveT for ClerkAgent {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "Handles orders"
+        }
+
+        fn output_schema(&self) -> Option<Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "clerk"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![Box::new(LookupTool)]
+        }
+    }
+
+    /// Reports its second turn, then waits for the test to release it
+    #[derive(Debug)]
+    struct GatedAgent {
+        id: AgentID,
+        release: Arc<Notify>,
+    }
+
+    #[async_trait]
+    impl RunnableAgent for GatedAgent {
+        fn id(&self) -> AgentID {
+            self.id
+        }
+
+        fn name(&self) -> &'static str {
+            "gated"
+        }
+
+        fn description(&self) -> &'static str {
+            "Waits to be released"
+        }
+
+        fn memory(&self) -> Option<Arc<RwLock<Box<dyn MemoryProvider>>>> {
+            None
+        }
+
+        async fn run(self: Arc<Self>, task: Task, tx: mpsc::Sender<Event>) -> Result<(), Error> {
+            let sub_id = task.submission_id;
+            tx.send(Event::TaskStarted {
+                sub_id,
+                agent_id: self.id,
+                task_description: task.prompt.clone(),
+            })
+            .await
+            .unwrap();
+            tx.send(Event::TurnStarted {
+                sub_id,
+                turn_number: 1,
+                max_turns: 5,
+            })
+            .await
+            .unwrap();
+            self.release.notified().await;
+            tx.send(Event::TaskComplete {
+                sub_id,
+                result: TaskResult::Value(Value::Null),
+            })
+            .await
+            .unwrap();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_introspection() {
+        let runtime = SingleThreadedRuntime::new(None);
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(vec!["Order shipped"]));
+        let clerk = BaseAgent::new(ClerkAgent, llm, None).into_runnable();
+        let clerk_id = clerk.id();
+        let release = Arc::new(Notify::new());
+        let gated = Arc::new(GatedAgent {
+            id: Uuid::new_v4(),
+            release: release.clone(),
+        });
+        runtime.register_agent(clerk).await.unwrap();
+        runtime.register_agent(gated.clone()).await.unwrap();
+        runtime.subscribe(clerk_id, "orders".into()).await.unwrap();
+        let _events = runtime.take_event_receiver().await.unwrap();
+        let handle = {
+            let runtime = runtime.clone();
+            tokio::spawn(async move { runtime.run().await })
+        };
+
+        let infos = runtime.agent_infos().await;
+        let clerk_info = infos.iter().find(|info| info.id == clerk_id).unwrap();
+        assert_eq!(clerk_info.name, "clerk");
+        assert_eq!(clerk_info.description, "Handles orders");
+        assert_eq!(clerk_info.tools, vec!["lookup"]);
+        assert_eq!(
+            runtime.subscriptions().await,
+            HashMap::from([("orders".to_string(), vec![clerk_id])])
+        );
+
+        runtime
+            .execute_task(clerk_id, Task::new("Where is order 7?", None))
+            .await
+            .unwrap();
+        let state = runtime.agent_state(clerk_id).await.unwrap();
+        assert_eq!(state.task_history[0].prompt, "Where is order 7?");
+        let snapshot = serde_json::to_value(&state).unwrap();
+        assert_eq!(snapshot["task_history"][0]["prompt"], "Where is order 7?");
+        assert!(runtime.agent_state(gated.id).await.is_none());
+
+        let gated_run = {
+            let runtime = runtime.clone();
+            let gated_id = gated.id;
+            tokio::spawn(async move {
+                runtime
+                    .execute_task(gated_id, Task::new("Wait", None))
+                    .await
+            })
+        };
+        let submission = loop {
+            if let Some(submission) = runtime.submissions().await.pop() {
+                if submission.turn.is_some() {
+                    break submission;
+                }
+            }
+            sleep(Duration::from_millis(5)).await;
+        };
+        assert_eq!(submission.agent_id, gated.id);
+        assert_eq!(submission.prompt, "Wait");
+        assert_eq!((submission.turn, submission.max_turns), (Some(1), Some(5)));
+
+        release.notify_one();
+        gated_run.await.unwrap().unwrap();
+        while !runtime.submissions().await.is_empty() {
+            sleep(Duration::from_millis(5)).await;
+        }
+
+        runtime.stop().await.unwrap();
+        handle.await.unwrap().unwrap();
+    }
 }
@@ -0,0 +1,442 @@
+//! Capabilities tools need and agents are granted.
+//!
+//! A tool declares the capabilities it needs with [`ToolT::capabilities`]. An agent given a
+//! capability set with
+//! [`AgentBuilder::with_capabilities`](crate::agent::AgentBuilder::with_capabilities) can only
+//! call tools whose capabilities it was granted, other calls are refused with a
+//! [`CapabilityError`] the model sees as the result of the call, and reported with
+//! [`Event::ToolCallDenied`]. Agents without a capability set are granted every capability, so
+//! one [`ToolRegistry`](super::ToolRegistry) can serve agents of different trust levels.
+//!
+//! ```ignore
+//! let reviewer = AgentBuilder::new(reviewer)
+//!     .with_tool_registry(registry.clone())
+//!     .with_capabilities([Capability::FilesystemRead]);
+//! ```
+
+use super::{ArgumentsValidationError, ToolCallContext, ToolCallError, ToolRuntime, ToolT};
+use crate::protocol::Event;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Display};
+use thiserror::Error;
+
+/// Something a tool does beyond computing its result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// Read files and list directories
+    FilesystemRead,
+    /// Create, change or delete files
+    FilesystemWrite,
+    /// Make network requests
+    Network,
+    /// Start processes
+    ProcessExec,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 4] = [
+        Capability::FilesystemRead,
+        Capability::FilesystemWrite,
+        Capability::Network,
+        Capability::ProcessExec,
+    ];
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Capability::FilesystemRead => "filesystem-read",
+            Capability::FilesystemWrite => "filesystem-write",
+            Capability::Network => "network",
+            Capability::ProcessExec => "process-exec",
+        })
+    }
+}
+
+/// Capabilities granted to an agent
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilitySet(BTreeSet<Capability>);
+
+impl CapabilitySet {
+    /// A set granting no capability
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A set granting every capability
+    pub fn all() -> Self {
+        Self::from(Capability::ALL)
+    }
+
+    pub fn with(mut self, capability: Capability) -> Self {
+        self.0.insert(capability);
+        self
+    }
+
+    pub fn contains(&self, capability: Capability) -> bool {
+        self.0.contains(&capability)
+    }
+
+    /// The capabilities of `required` not in the set
+    pub fn missing(&self, required: &[Capability]) -> Vec<Capability> {
+        let missing: BTreeSet<Capability> = required
+            .iter()
+            .filter(|capability| !self.contains(**capability))
+            .copied()
+            .collect();
+        missing.into_iter().collect()
+    }
+
+    /// The capabilities in both sets
+    pub fn intersection(&self, other: &CapabilitySet) -> CapabilitySet {
+        Self(self.0.intersection(&other.0).copied().collect())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Capability> + '_ {
+        self.0.iter().copied()
+    }
+
+    /// Wrap the tools needing capabilities outside of the set so their calls are refused, the
+    /// other tools see the set in the context of their calls
+    pub(crate) fn restrict_tools(&self, tools: Vec<Box<dyn ToolT>>) -> Vec<Box<dyn ToolT>> {
+        tools
+            .into_iter()
+            .map(|tool| {
+                let missing = self.missing(&tool.capabilities());
+                if missing.is_empty() {
+                    Box::new(GrantedTool {
+                        inner: tool,
+                        granted: self.clone(),
+                    }) as Box<dyn ToolT>
+                } else {
+                    Box::new(RestrictedTool {
+                        inner: tool,
+                        missing,
+                    })
+                }
+            })
+            .collect()
+    }
+}
+
+impl FromIterator<Capability> for CapabilitySet {
+    fn from_iter<I: IntoIterator<Item = Capability>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<const N: usize> From<[Capability; N]> for CapabilitySet {
+    fn from(capabilities: [Capability; N]) -> Self {
+        capabilities.into_iter().collect()
+    }
+}
+
+/// Error type for capability checks
+#[derive(Debug, Error)]
+pub enum CapabilityError {
+    #[error("Tool '{tool}' needs capabilities the agent was not granted: {}", list(.missing))]
+    NotGranted {
+        tool: String,
+        missing: Vec<Capability>,
+    },
+}
+
+impl From<CapabilityError> for ToolCallError {
+    fn from(error: CapabilityError) -> Self {
+        ToolCallError::RuntimeError(Box::new(error))
+    }
+}
+
+fn list(capabilities: &[Capability]) -> String {
+    capabilities
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Tool needing capabilities the calling agent was not granted, its calls are refused
+#[derive(Debug)]
+struct RestrictedTool {
+    inner: Box<dyn ToolT>,
+    missing: Vec<Capability>,
+}
+
+impl RestrictedTool {
+    fn refusal(&self) -> CapabilityError {
+        CapabilityError::NotGranted {
+            tool: self.inner.name().to_string(),
+            missing: self.missing.clone(),
+        }
+    }
+}
+
+impl ToolRuntime for RestrictedTool {
+    fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+        Err(self.refusal().into())
+    }
+}
+
+#[async_trait]
+impl ToolT for RestrictedTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn validate_args(&self, args: &Value) -> Result<(), ArgumentsValidationError> {
+        self.inner.validate_args(args)
+    }
+
+    fn returns_directly(&self) -> bool {
+        self.inner.returns_directly()
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.inner.capabilities()
+    }
+
+    async fn call(&self, _args: Value, context: ToolCallContext) -> Result<Value, ToolCallError> {
+        let _ = context
+            .tx_event
+            .send(Event::ToolCallDenied {
+                sub_id: context.sub_id,
+                tool_name: self.inner.name().to_string(),
+                missing: self.missing.clone(),
+            })
+            .await;
+        Err(self.refusal().into())
+    }
+}
+
+/// Tool the calling agent was granted the capabilities of. Its calls carry the capabilities of
+/// the agent, for tools that only learn what they need when called
+#[derive(Debug)]
+struct GrantedTool {
+    inner: Box<dyn ToolT>,
+    granted: CapabilitySet,
+}
+
+impl ToolRuntime for GrantedTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        self.inner.execute(args)
+    }
+}
+
+#[async_trait]
+impl ToolT for GrantedTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn validate_args(&self, args: &Value) -> Result<(), ArgumentsValidationError> {
+        self.inner.validate_args(args)
+    }
+
+    fn returns_directly(&self) -> bool {
+        self.inner.returns_directly()
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.inner.capabilities()
+    }
+
+    async fn call(
+        &self,
+        args: Value,
+        mut context: ToolCallContext,
+    ) -> Result<Value, ToolCallError> {
+        context.capabilities = Some(self.granted.clone());
+        self.inner.call(args, context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::prebuilt::react::ReActExecutor;
+    use crate::agent::{AgentDeriveT, AgentTool, BaseAgent, IntoRunnable};
+    use crate::protocol::TaskResult;
+    use crate::runtime::Task;
+    use crate::tool::ToolRegistry;
+    use autoagents_llm::chat::MessageType;
+    use autoagents_test_utils::llm::{tool_call, ScriptedLLMProvider, ScriptedResponse};
+    use serde_json::json;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    /// A tool pretending to fetch a page
+    #[derive(Debug)]
+    struct FetchTool;
+
+    impl ToolRuntime for FetchTool {
+        fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            Ok(json!("<html>Hello</html>"))
+        }
+    }
+
+    impl ToolT for FetchTool {
+        fn name(&self) -> &'static str {
+            "fetch"
+        }
+
+        fn description(&self) -> &'static str {
+            "Fetch a web page"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object", "properties": {}})
+        }
+
+        fn capabilities(&self) -> Vec<Capability> {
+            vec![Capability::Network]
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct BrowsingAgent;
+
+    impl ReActExecutor for BrowsingAgent {}
+
+    impl AgentDeriveT for BrowsingAgent {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "Browses the web"
+        }
+
+        fn output_schema(&self) -> Option<Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "browser"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![]
+        }
+    }
+
+    #[test]
+    fn test_capability_set() {
+        let set = CapabilitySet::from([Capability::FilesystemRead]).with(Capability::Network);
+        assert!(set.contains(Capability::Network));
+        assert_eq!(
+            set.missing(&[Capability::ProcessExec, Capability::Network]),
+            vec![Capability::ProcessExec]
+        );
+        assert!(CapabilitySet::all().missing(&Capability::ALL).is_empty());
+        assert_eq!(
+            serde_json::to_value(&set).unwrap(),
+            json!(["filesystem-read", "network"])
+        );
+        assert_eq!(Capability::ProcessExec.to_string(), "process-exec");
+    }
+
+    /// Run the browsing agent asking for one fetch, with the tools of `registry`
+    async fn browse(
+        registry: &ToolRegistry,
+        capabilities: Option<CapabilitySet>,
+    ) -> (Arc<ScriptedLLMProvider>, Vec<Event>) {
+        let llm = Arc::new(ScriptedLLMProvider::new(vec![
+            ScriptedResponse::ToolCalls(vec![tool_call("call_1", "fetch", "{}")]),
+            ScriptedResponse::Text("Done".to_string()),
+        ]));
+        let mut agent = BaseAgent::new(BrowsingAgent, llm.clone(), None);
+        agent.tool_registry = Some(registry.clone());
+        agent.capabilities = capabilities;
+        let (tx, mut rx) = mpsc::channel(100);
+        agent
+            .into_runnable()
+            .run(Task::new("Fetch the page", None), tx)
+            .await
+            .unwrap();
+        let events = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+        (llm, events)
+    }
+
+    fn tool_result(llm: &ScriptedLLMProvider) -> String {
+        let request = &llm.requests()[1];
+        let MessageType::ToolResult(results) = &request[3].message_type else {
+            panic!("Expected a tool result");
+        };
+        results[0].function.arguments.clone()
+    }
+
+    #[tokio::test]
+    async fn test_agents_share_registry_with_different_grants() {
+        let registry = ToolRegistry::new();
+        registry.register(FetchTool).unwrap();
+
+        // Without a capability set every tool can be called
+        let (llm, events) = browse(&registry, None).await;
+        assert!(tool_result(&llm).contains("Hello"));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, Event::ToolCallDenied { .. })));
+
+        let (llm, events) = browse(&registry, Some([Capability::FilesystemRead].into())).await;
+        let result = tool_result(&llm);
+        assert!(result.contains("not granted: network"));
+        assert!(!result.contains("Hello"));
+        let denied: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::ToolCallDenied {
+                    tool_name, missing, ..
+                } => Some((tool_name.as_str(), missing.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(denied, vec![("fetch", vec![Capability::Network])]);
+        // The refusal is handed to the model, the task goes on
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::TaskComplete {
+                result: TaskResult::Value(_),
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn test_agent_tool_needs_capabilities_of_the_agent() {
+        let registry = ToolRegistry::new();
+        registry.register(FetchTool).unwrap();
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(Vec::<String>::new()));
+        let mut agent = BaseAgent::new(BrowsingAgent, llm, None);
+        agent.tool_registry = Some(registry);
+        let browser = AgentTool::new(agent.clone().into_runnable());
+        assert_eq!(browser.capabilities(), vec![Capability::Network]);
+
+        // A manager without network access can't browse through the sub-agent
+        let manager = CapabilitySet::from([Capability::FilesystemRead]);
+        let tools = manager.restrict_tools(vec![Box::new(browser)]);
+        assert!(tools[0].run(json!({"task": "Fetch the page"})).is_err());
+
+        // The sub-agent only uses the capabilities it was granted
+        agent.capabilities = Some(CapabilitySet::new());
+        let browser = AgentTool::new(agent.into_runnable());
+        assert!(browser.capabilities().is_empty());
+    }
+}
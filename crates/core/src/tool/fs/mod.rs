@@ -0,0 +1,332 @@
+//! File system tools confined to a root directory.
+//!
+//! Every path a tool receives is resolved against the root of an [`FsSandbox`]: `..`
+//! components are rejected, absolute paths must lie under the root and symlinks may not lead
+//! out of it. Directory traversal skips files ignored by `.gitignore` and `.ignore` files and
+//! never follows symlinks.
+//!
+//! ```ignore
+//! let sandbox = FsSandbox::from_environment(env.config())?.read_only();
+//! let agent = AgentBuilder::new(agent);
+//! let agent = sandbox.tools().into_iter().fold(agent, |agent, tool| agent.with_tool(tool));
+//! ```
+
+use super::{ToolCallError, ToolRegistry, ToolRegistryError, ToolT};
+use crate::environment::EnvironmentConfig;
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+mod tools;
+
+pub use tools::{
+    EditFileTool, GrepTool, ListDirTool, ReadFileTool, SearchFilesTool, WriteFileTool,
+};
+
+/// Largest file the tools read or write by default
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+/// Most entries a listing, search or grep returns by default
+pub const DEFAULT_MAX_RESULTS: usize = 200;
+
+/// Error type for file system tools
+#[derive(Debug, Error)]
+pub enum FsError {
+    #[error("Path '{0}' is outside of the root directory")]
+    OutsideRoot(String),
+
+    #[error("Path '{0}' contains '..', paths must stay inside the root directory")]
+    ParentTraversal(String),
+
+    #[error("Path '{0}' does not exist")]
+    NotFound(String),
+
+    #[error("File system is read-only")]
+    ReadOnly,
+
+    #[error("'{path}' is {size} bytes, the limit is {limit} bytes")]
+    TooLarge { path: String, size: u64, limit: u64 },
+
+    #[error("Invalid pattern: {0}")]
+    InvalidPattern(String),
+
+    #[error("'{path}' contains the text to replace {count} times, expected exactly once")]
+    AmbiguousEdit { path: String, count: usize },
+
+    #[error("The text to replace is empty")]
+    EmptyEdit,
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<FsError> for ToolCallError {
+    fn from(error: FsError) -> Self {
+        ToolCallError::RuntimeError(Box::new(error))
+    }
+}
+
+/// Root directory the file system tools are confined to, with their limits
+#[derive(Debug, Clone)]
+pub struct FsSandbox {
+    root: PathBuf,
+    read_only: bool,
+    max_file_size: u64,
+    max_results: usize,
+    respect_gitignore: bool,
+}
+
+impl FsSandbox {
+    /// Confine tools to `root`, which must be an existing directory
+    pub fn new(root: impl AsRef<Path>) -> Result<Self, FsError> {
+        let root = root.as_ref();
+        let root = root
+            .canonicalize()
+            .map_err(|_| FsError::NotFound(root.display().to_string()))?;
+        Ok(Self {
+            root,
+            read_only: false,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            max_results: DEFAULT_MAX_RESULTS,
+            respect_gitignore: true,
+        })
+    }
+
+    /// Confine tools to the working directory of an environment
+    pub fn from_environment(config: &EnvironmentConfig) -> Result<Self, FsError> {
+        Self::new(&config.working_dir)
+    }
+
+    /// Refuse writes and edits
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Set the largest file, in bytes, the tools read or write
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Set the most entries a listing, search or grep returns
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = max_results;
+        self
+    }
+
+    /// Whether traversal skips files ignored by `.gitignore` and `.ignore` files
+    pub fn with_gitignore(mut self, respect: bool) -> Self {
+        self.respect_gitignore = respect;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// The tools of the sandbox, without the writing tools when it is read-only
+    pub fn tools(&self) -> Vec<Box<dyn ToolT>> {
+        let mut tools: Vec<Box<dyn ToolT>> = vec![
+            Box::new(ReadFileTool::new(self.clone())),
+            Box::new(ListDirTool::new(self.clone())),
+            Box::new(SearchFilesTool::new(self.clone())),
+            Box::new(GrepTool::new(self.clone())),
+        ];
+        if !self.read_only {
+            tools.push(Box::new(WriteFileTool::new(self.clone())));
+            tools.push(Box::new(EditFileTool::new(self.clone())));
+        }
+        tools
+    }
+
+    /// Register the tools of the sandbox, see [`FsSandbox::tools`]
+    pub fn register(&self, registry: &ToolRegistry) -> Result<(), ToolRegistryError> {
+        registry.register(ReadFileTool::new(self.clone()))?;
+        registry.register(ListDirTool::new(self.clone()))?;
+        registry.register(SearchFilesTool::new(self.clone()))?;
+        registry.register(GrepTool::new(self.clone()))?;
+        if !self.read_only {
+            registry.register(WriteFileTool::new(self.clone()))?;
+            registry.register(EditFileTool::new(self.clone()))?;
+        }
+        Ok(())
+    }
+
+    /// Resolve a path given to a tool to a path under the root.
+    ///
+    /// The path may not exist yet, the part of it that exists must not lead out of the root
+    /// through a symlink.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, FsError> {
+        let requested = Path::new(path);
+        let relative = if requested.is_absolute() {
+            requested
+                .strip_prefix(&self.root)
+                .map_err(|_| FsError::OutsideRoot(path.to_string()))?
+        } else {
+            requested
+        };
+
+        let mut resolved = self.root.clone();
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => resolved.push(part),
+                Component::CurDir => {}
+                Component::ParentDir => return Err(FsError::ParentTraversal(path.to_string())),
+                Component::RootDir | Component::Prefix(_) => {
+                    return Err(FsError::OutsideRoot(path.to_string()))
+                }
+            }
+        }
+
+        // Symlinks are resolved on the part of the path that exists
+        let mut existing = resolved.as_path();
+        let mut missing = Vec::new();
+        while existing.symlink_metadata().is_err() {
+            let Some(parent) = existing.parent() else {
+                break;
+            };
+            missing.extend(existing.file_name());
+            existing = parent;
+        }
+        // A dangling symlink can't be checked, it may point anywhere
+        let mut canonical = existing
+            .canonicalize()
+            .map_err(|_| FsError::OutsideRoot(path.to_string()))?;
+        if !canonical.starts_with(&self.root) {
+            return Err(FsError::OutsideRoot(path.to_string()));
+        }
+        canonical.extend(missing.iter().rev());
+        Ok(canonical)
+    }
+
+    /// Path relative to the root, for showing to the model
+    pub(crate) fn display(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        if relative.as_os_str().is_empty() {
+            return ".".to_string();
+        }
+        relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Fail when a file is larger than the size limit
+    pub(crate) fn check_size(&self, path: &Path, size: u64) -> Result<(), FsError> {
+        if size > self.max_file_size {
+            return Err(FsError::TooLarge {
+                path: self.display(path),
+                size,
+                limit: self.max_file_size,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_writable(&self) -> Result<(), FsError> {
+        if self.read_only {
+            return Err(FsError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn max_results(&self) -> usize {
+        self.max_results
+    }
+
+    /// Walk a directory, `max_depth` of `Some(1)` lists its direct entries only
+    pub(crate) fn walk(&self, dir: &Path, max_depth: Option<usize>) -> ignore::Walk {
+        let respect = self.respect_gitignore;
+        ignore::WalkBuilder::new(dir)
+            .max_depth(max_depth)
+            .hidden(false)
+            .follow_links(false)
+            .require_git(false)
+            .git_global(false)
+            .git_ignore(respect)
+            .git_exclude(respect)
+            .ignore(respect)
+            .parents(respect)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_stays_in_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = FsSandbox::new(dir.path()).unwrap();
+        let root = sandbox.root().to_path_buf();
+
+        assert_eq!(
+            sandbox.resolve("src/main.rs").unwrap(),
+            root.join("src/main.rs")
+        );
+        assert_eq!(sandbox.resolve("./notes").unwrap(), root.join("notes"));
+        assert_eq!(
+            sandbox
+                .resolve(root.join("a.txt").to_str().unwrap())
+                .unwrap(),
+            root.join("a.txt")
+        );
+        assert!(matches!(
+            sandbox.resolve("src/../../etc/passwd"),
+            Err(FsError::ParentTraversal(_))
+        ));
+        assert!(matches!(
+            sandbox.resolve("/etc/passwd"),
+            Err(FsError::OutsideRoot(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_rejects_symlink_escape() {
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "hunter2").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("inner"), dir.path().join("alias")).unwrap();
+        let sandbox = FsSandbox::new(dir.path()).unwrap();
+
+        assert!(matches!(
+            sandbox.resolve("link/secret.txt"),
+            Err(FsError::OutsideRoot(_))
+        ));
+        assert!(matches!(
+            sandbox.resolve("link/new.txt"),
+            Err(FsError::OutsideRoot(_))
+        ));
+        assert!(matches!(
+            sandbox.resolve("alias/new.txt"),
+            Err(FsError::OutsideRoot(_))
+        ));
+        assert!(sandbox.resolve("inner/new.txt").is_ok());
+    }
+
+    #[test]
+    fn test_from_environment_and_read_only_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = EnvironmentConfig {
+            working_dir: dir.path().to_path_buf(),
+        };
+        let sandbox = FsSandbox::from_environment(&config).unwrap();
+        assert_eq!(sandbox.tools().len(), 6);
+
+        let registry = ToolRegistry::new();
+        sandbox.read_only().register(&registry).unwrap();
+        assert_eq!(
+            registry.names(),
+            vec!["grep", "list_dir", "read_file", "search_files"]
+        );
+    }
+}
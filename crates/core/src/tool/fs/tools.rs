@@ -0,0 +1,561 @@
+use super::{FsError, FsSandbox};
+use crate::tool::{Capability, ToolCallError, ToolRuntime, ToolT};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::Path;
+
+fn current_dir() -> String {
+    ".".to_string()
+}
+
+/// Read a text file, failing on files over the size limit
+fn read_text(sandbox: &FsSandbox, path: &Path) -> Result<String, FsError> {
+    let metadata = std::fs::metadata(path).map_err(|_| FsError::NotFound(sandbox.display(path)))?;
+    sandbox.check_size(path, metadata.len())?;
+    Ok(std::fs::read_to_string(path)?)
+}
+
+/// Files under `dir`, skipping ignored files and symlinks
+fn files(sandbox: &FsSandbox, dir: &Path) -> impl Iterator<Item = ignore::DirEntry> {
+    sandbox
+        .walk(dir, None)
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+}
+
+#[derive(Deserialize)]
+struct ReadFileArgs {
+    path: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+/// Read a text file, optionally a range of its lines
+#[derive(Debug, Clone)]
+pub struct ReadFileTool {
+    sandbox: FsSandbox,
+}
+
+impl ReadFileTool {
+    pub fn new(sandbox: FsSandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+impl ToolRuntime for ReadFileTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let args: ReadFileArgs = serde_json::from_value(args)?;
+        let path = self.sandbox.resolve(&args.path)?;
+        let content = read_text(&self.sandbox, &path)?;
+        if args.offset.is_none() && args.limit.is_none() {
+            return Ok(Value::String(content));
+        }
+        let lines: Vec<&str> = content
+            .lines()
+            .skip(args.offset.unwrap_or(1).saturating_sub(1))
+            .take(args.limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok(Value::String(lines.join("\n")))
+    }
+}
+
+impl ToolT for ReadFileTool {
+    fn name(&self) -> &'static str {
+        "read_file"
+    }
+
+    fn description(&self) -> &'static str {
+        "Read a text file. Use offset and limit to read a range of lines of a large file."
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::FilesystemRead]
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": "Path of the file, relative to the root"},
+                "offset": {"type": "integer", "minimum": 1, "description": "First line to read, from 1"},
+                "limit": {"type": "integer", "minimum": 0, "description": "Number of lines to read"}
+            },
+            "required": ["path"]
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct WriteFileArgs {
+    path: String,
+    content: String,
+}
+
+/// Create or overwrite a file, creating its parent directories
+#[derive(Debug, Clone)]
+pub struct WriteFileTool {
+    sandbox: FsSandbox,
+}
+
+impl WriteFileTool {
+    pub fn new(sandbox: FsSandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+impl ToolRuntime for WriteFileTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let args: WriteFileArgs = serde_json::from_value(args)?;
+        self.sandbox.check_writable()?;
+        let path = self.sandbox.resolve(&args.path)?;
+        self.sandbox.check_size(&path, args.content.len() as u64)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(FsError::from)?;
+        }
+        std::fs::write(&path, &args.content).map_err(FsError::from)?;
+        Ok(json!({
+            "path": self.sandbox.display(&path),
+            "bytes": args.content.len()
+        }))
+    }
+}
+
+impl ToolT for WriteFileTool {
+    fn name(&self) -> &'static str {
+        "write_file"
+    }
+
+    fn description(&self) -> &'static str {
+        "Create a file or replace its whole content"
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::FilesystemWrite]
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": "Path of the file, relative to the root"},
+                "content": {"type": "string", "description": "New content of the file"}
+            },
+            "required": ["path", "content"]
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct EditFileArgs {
+    path: String,
+    old_text: String,
+    new_text: String,
+    #[serde(default)]
+    replace_all: bool,
+}
+
+/// Replace text in a file
+#[derive(Debug, Clone)]
+pub struct EditFileTool {
+    sandbox: FsSandbox,
+}
+
+impl EditFileTool {
+    pub fn new(sandbox: FsSandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+impl ToolRuntime for EditFileTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let args: EditFileArgs = serde_json::from_value(args)?;
+        if args.old_text.is_empty() {
+            return Err(FsError::EmptyEdit.into());
+        }
+        self.sandbox.check_writable()?;
+        let path = self.sandbox.resolve(&args.path)?;
+        let content = read_text(&self.sandbox, &path)?;
+        let count = content.matches(&args.old_text).count();
+        if count == 0 || (count > 1 && !args.replace_all) {
+            return Err(FsError::AmbiguousEdit {
+                path: self.sandbox.display(&path),
+                count,
+            }
+            .into());
+        }
+        let edited = content.replace(&args.old_text, &args.new_text);
+        self.sandbox.check_size(&path, edited.len() as u64)?;
+        std::fs::write(&path, edited).map_err(FsError::from)?;
+        Ok(json!({
+            "path": self.sandbox.display(&path),
+            "replacements": count
+        }))
+    }
+}
+
+impl ToolT for EditFileTool {
+    fn name(&self) -> &'static str {
+        "edit_file"
+    }
+
+    fn description(&self) -> &'static str {
+        "Replace text in a file. The text to replace must occur exactly once unless replace_all \
+         is set."
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::FilesystemRead, Capability::FilesystemWrite]
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": "Path of the file, relative to the root"},
+                "old_text": {"type": "string", "description": "Exact text to replace"},
+                "new_text": {"type": "string", "description": "Text to put in its place"},
+                "replace_all": {"type": "boolean", "description": "Replace every occurrence"}
+            },
+            "required": ["path", "old_text", "new_text"]
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ListDirArgs {
+    #[serde(default = "current_dir")]
+    path: String,
+}
+
+/// List the entries of a directory
+#[derive(Debug, Clone)]
+pub struct ListDirTool {
+    sandbox: FsSandbox,
+}
+
+impl ListDirTool {
+    pub fn new(sandbox: FsSandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+impl ToolRuntime for ListDirTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let args: ListDirArgs = serde_json::from_value(args)?;
+        let dir = self.sandbox.resolve(&args.path)?;
+        if !dir.is_dir() {
+            return Err(FsError::NotFound(args.path).into());
+        }
+        let mut entries: Vec<Value> = self
+            .sandbox
+            .walk(&dir, Some(1))
+            .filter_map(Result::ok)
+            .filter(|entry| entry.depth() == 1)
+            .map(|entry| {
+                let kind = match entry.file_type() {
+                    Some(kind) if kind.is_dir() => "dir",
+                    Some(kind) if kind.is_symlink() => "symlink",
+                    _ => "file",
+                };
+                let size = entry.metadata().map(|m| m.len()).unwrap_or_default();
+                json!({
+                    "path": self.sandbox.display(entry.path()),
+                    "kind": kind,
+                    "size": size
+                })
+            })
+            .collect();
+        let truncated = entries.len() > self.sandbox.max_results();
+        entries.truncate(self.sandbox.max_results());
+        Ok(json!({"entries": entries, "truncated": truncated}))
+    }
+}
+
+impl ToolT for ListDirTool {
+    fn name(&self) -> &'static str {
+        "list_dir"
+    }
+
+    fn description(&self) -> &'static str {
+        "List the files and directories in a directory"
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::FilesystemRead]
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": "Directory to list, the root by default"}
+            }
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchFilesArgs {
+    pattern: String,
+    #[serde(default = "current_dir")]
+    path: String,
+}
+
+/// Find files by glob pattern
+#[derive(Debug, Clone)]
+pub struct SearchFilesTool {
+    sandbox: FsSandbox,
+}
+
+impl SearchFilesTool {
+    pub fn new(sandbox: FsSandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+impl ToolRuntime for SearchFilesTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let args: SearchFilesArgs = serde_json::from_value(args)?;
+        let pattern = glob::Pattern::new(&args.pattern)
+            .map_err(|e| FsError::InvalidPattern(e.to_string()))?;
+        // Patterns without a directory match file names anywhere below the path
+        let by_name = !args.pattern.contains('/');
+        let dir = self.sandbox.resolve(&args.path)?;
+
+        let mut matches = Vec::new();
+        let mut truncated = false;
+        for entry in files(&self.sandbox, &dir) {
+            let path = self.sandbox.display(entry.path());
+            let name = entry.file_name().to_string_lossy();
+            if pattern.matches(&path) || (by_name && pattern.matches(&name)) {
+                if matches.len() == self.sandbox.max_results() {
+                    truncated = true;
+                    break;
+                }
+                matches.push(path);
+            }
+        }
+        Ok(json!({"files": matches, "truncated": truncated}))
+    }
+}
+
+impl ToolT for SearchFilesTool {
+    fn name(&self) -> &'static str {
+        "search_files"
+    }
+
+    fn description(&self) -> &'static str {
+        "Find files whose path matches a glob pattern, such as '*.rs' or 'src/**/*.toml'"
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::FilesystemRead]
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": {"type": "string", "description": "Glob pattern to match"},
+                "path": {"type": "string", "description": "Directory to search, the root by default"}
+            },
+            "required": ["pattern"]
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct GrepArgs {
+    pattern: String,
+    #[serde(default = "current_dir")]
+    path: String,
+    glob: Option<String>,
+}
+
+/// Search the content of files with a regular expression
+#[derive(Debug, Clone)]
+pub struct GrepTool {
+    sandbox: FsSandbox,
+}
+
+impl GrepTool {
+    pub fn new(sandbox: FsSandbox) -> Self {
+        Self { sandbox }
+    }
+}
+
+impl ToolRuntime for GrepTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let args: GrepArgs = serde_json::from_value(args)?;
+        let regex =
+            Regex::new(&args.pattern).map_err(|e| FsError::InvalidPattern(e.to_string()))?;
+        let glob = args
+            .glob
+            .as_deref()
+            .map(glob::Pattern::new)
+            .transpose()
+            .map_err(|e| FsError::InvalidPattern(e.to_string()))?;
+        let dir = self.sandbox.resolve(&args.path)?;
+
+        let mut matches = Vec::new();
+        let mut truncated = false;
+        'files: for entry in files(&self.sandbox, &dir) {
+            let name = entry.file_name().to_string_lossy();
+            if glob.as_ref().is_some_and(|glob| !glob.matches(&name)) {
+                continue;
+            }
+            // Binary and oversized files are skipped
+            let Ok(content) = read_text(&self.sandbox, entry.path()) else {
+                continue;
+            };
+            for (index, line) in content.lines().enumerate() {
+                if regex.is_match(line) {
+                    if matches.len() == self.sandbox.max_results() {
+                        truncated = true;
+                        break 'files;
+                    }
+                    matches.push(json!({
+                        "path": self.sandbox.display(entry.path()),
+                        "line": index + 1,
+                        "text": line
+                    }));
+                }
+            }
+        }
+        Ok(json!({"matches": matches, "truncated": truncated}))
+    }
+}
+
+impl ToolT for GrepTool {
+    fn name(&self) -> &'static str {
+        "grep"
+    }
+
+    fn description(&self) -> &'static str {
+        "Search the lines of files for a regular expression"
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::FilesystemRead]
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": {"type": "string", "description": "Regular expression to search for"},
+                "path": {"type": "string", "description": "File or directory to search, the root by default"},
+                "glob": {"type": "string", "description": "Only search files whose name matches this glob, such as '*.rs'"}
+            },
+            "required": ["pattern"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox() -> (tempfile::TempDir, FsSandbox) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("target")).unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(
+            dir.path().join("src/main.rs"),
+            "fn main() {\n    println!(\"hi\");\n}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "pub fn hi() {}\n").unwrap();
+        std::fs::write(dir.path().join("target/out.rs"), "fn hi() {}\n").unwrap();
+        let sandbox = FsSandbox::new(dir.path()).unwrap();
+        (dir, sandbox)
+    }
+
+    #[test]
+    fn test_read_and_write() {
+        let (_dir, sandbox) = sandbox();
+        let write = WriteFileTool::new(sandbox.clone());
+        let result = write
+            .run(json!({"path": "docs/notes.md", "content": "one\ntwo\nthree"}))
+            .unwrap();
+        assert_eq!(result, json!({"path": "docs/notes.md", "bytes": 13}));
+
+        let read = ReadFileTool::new(sandbox.clone());
+        assert_eq!(
+            read.run(json!({"path": "docs/notes.md", "offset": 2, "limit": 1}))
+                .unwrap(),
+            "two"
+        );
+        let error = read.run(json!({"path": "../etc/passwd"})).unwrap_err();
+        assert!(error.to_string().contains("contains '..'"));
+
+        let small = ReadFileTool::new(sandbox.clone().with_max_file_size(4));
+        let error = small.run(json!({"path": "docs/notes.md"})).unwrap_err();
+        assert!(error.to_string().contains("13 bytes, the limit is 4 bytes"));
+
+        let read_only = WriteFileTool::new(sandbox.read_only());
+        let error = read_only
+            .run(json!({"path": "a.txt", "content": ""}))
+            .unwrap_err();
+        assert!(error.to_string().contains("read-only"));
+    }
+
+    #[test]
+    fn test_edit() {
+        let (dir, sandbox) = sandbox();
+        let edit = EditFileTool::new(sandbox);
+        let error = edit
+            .run(json!({"path": "src/main.rs", "old_text": "i", "new_text": "o"}))
+            .unwrap_err();
+        assert!(error.to_string().contains("3 times"));
+        let error = edit
+            .run(json!({"path": "src/main.rs", "old_text": "", "new_text": "o"}))
+            .unwrap_err();
+        assert!(error.to_string().contains("text to replace is empty"));
+
+        let result = edit
+            .run(json!({"path": "src/main.rs", "old_text": "\"hi\"", "new_text": "\"ho\""}))
+            .unwrap();
+        assert_eq!(result["replacements"], 1);
+        assert!(std::fs::read_to_string(dir.path().join("src/main.rs"))
+            .unwrap()
+            .contains("println!(\"ho\")"));
+    }
+
+    #[test]
+    fn test_traversal_respects_gitignore() {
+        let (_dir, sandbox) = sandbox();
+        let listed = ListDirTool::new(sandbox.clone()).run(json!({})).unwrap();
+        let paths: Vec<&str> = listed["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(paths, vec![".gitignore", "src"]);
+
+        let found = SearchFilesTool::new(sandbox.clone())
+            .run(json!({"pattern": "*.rs"}))
+            .unwrap();
+        assert_eq!(found["files"], json!(["src/lib.rs", "src/main.rs"]));
+
+        let grep = GrepTool::new(sandbox.clone().with_max_results(1));
+        let found = grep
+            .run(json!({"pattern": r"fn \w+", "glob": "*.rs"}))
+            .unwrap();
+        assert_eq!(
+            found,
+            json!({
+                "matches": [{"path": "src/lib.rs", "line": 1, "text": "pub fn hi() {}"}],
+                "truncated": true
+            })
+        );
+
+        let unfiltered = SearchFilesTool::new(sandbox.with_gitignore(false))
+            .run(json!({"pattern": "**/out.rs"}))
+            .unwrap();
+        assert_eq!(unfiltered["files"], json!(["target/out.rs"]));
+    }
+}
@@ -0,0 +1,537 @@
+//! Tool making HTTP requests to allowlisted hosts.
+//!
+//! Requests, and every redirect they follow, may only go to hosts of the allowlist. Response
+//! bodies are read up to a size limit. JSON responses are passed through as JSON, HTML pages
+//! are reduced to their text and other responses are returned as text.
+
+use super::{Capability, ToolCallContext, ToolCallError, ToolRuntime, ToolT};
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{redirect, Method, Url};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Time a request may take by default
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Largest response body read by default
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+
+const MAX_REDIRECTS: usize = 10;
+/// Headers the model may not set, they would change where and how the request is sent
+const RESERVED_HEADERS: &[&str] = &["host", "content-length", "transfer-encoding", "connection"];
+
+/// Error type for the HTTP tool
+#[derive(Debug, Error)]
+pub enum HttpToolError {
+    #[error("HTTP tool can only be called by an async executor")]
+    SyncCall,
+
+    #[error("Invalid URL '{0}'")]
+    InvalidUrl(String),
+
+    #[error("Host of '{0}' is not in the allowlist")]
+    HostNotAllowed(String),
+
+    #[error("Unsupported method '{0}', use GET or POST")]
+    UnsupportedMethod(String),
+
+    #[error("Invalid header '{0}'")]
+    InvalidHeader(String),
+
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+}
+
+impl From<HttpToolError> for ToolCallError {
+    fn from(error: HttpToolError) -> Self {
+        ToolCallError::RuntimeError(Box::new(error))
+    }
+}
+
+/// Hosts requests may go to. A rule is a host such as `api.example.com`, a host and port such
+/// as `localhost:8080`, or a wildcard such as `*.example.com` matching its subdomains
+#[derive(Debug, Clone, Default)]
+struct HostAllowlist {
+    rules: Vec<String>,
+}
+
+impl HostAllowlist {
+    fn allows(&self, url: &Url) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_ascii_lowercase();
+        let with_port = url.port().map(|port| format!("{host}:{port}"));
+        self.rules.iter().any(|rule| match rule.strip_prefix("*.") {
+            Some(domain) => host.ends_with(&format!(".{domain}")),
+            None => *rule == host || Some(rule) == with_port.as_ref(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct HttpArgs {
+    url: String,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    body: Option<Value>,
+}
+
+/// Make GET and POST requests to allowlisted hosts.
+///
+/// Returns the status, final URL, content type and body of the response as JSON. An error
+/// status is a result for the model to read, not a tool error. Without any allowed host
+/// every request is refused. The model can't set the headers given to
+/// [`HttpTool::with_header`], nor `Host`, `Content-Length`, `Transfer-Encoding` or
+/// `Connection`.
+#[derive(Debug, Clone)]
+pub struct HttpTool {
+    allowlist: Arc<HostAllowlist>,
+    headers: BTreeMap<String, String>,
+    timeout: Duration,
+    max_response_bytes: usize,
+}
+
+impl Default for HttpTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpTool {
+    pub fn new() -> Self {
+        Self {
+            allowlist: Arc::new(HostAllowlist::default()),
+            headers: BTreeMap::new(),
+            timeout: DEFAULT_TIMEOUT,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+        }
+    }
+
+    /// Allow requests to a host, see [`HttpTool`] for the forms a rule takes
+    pub fn allow_host(mut self, rule: &str) -> Self {
+        Arc::make_mut(&mut self.allowlist)
+            .rules
+            .push(rule.to_ascii_lowercase());
+        self
+    }
+
+    /// Send a header with every request, such as an API key the model should not see
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Fail requests taking longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Read at most `bytes` of a response body
+    pub fn with_max_response_bytes(mut self, bytes: usize) -> Self {
+        self.max_response_bytes = bytes;
+        self
+    }
+
+    /// Whether requests to `url` are allowed
+    pub fn allows(&self, url: &str) -> bool {
+        Url::parse(url).is_ok_and(|url| self.allowlist.allows(&url))
+    }
+
+    fn client(&self) -> Result<reqwest::Client, HttpToolError> {
+        let allowlist = self.allowlist.clone();
+        let policy = redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if allowlist.allows(attempt.url()) {
+                attempt.follow()
+            } else {
+                let url = attempt.url().to_string();
+                attempt.error(HttpToolError::HostNotAllowed(url))
+            }
+        });
+        Ok(reqwest::Client::builder()
+            .redirect(policy)
+            .timeout(self.timeout)
+            .build()?)
+    }
+
+    async fn request(&self, args: HttpArgs) -> Result<Value, HttpToolError> {
+        let url = Url::parse(&args.url).map_err(|_| HttpToolError::InvalidUrl(args.url.clone()))?;
+        if !self.allowlist.allows(&url) {
+            return Err(HttpToolError::HostNotAllowed(args.url));
+        }
+        let method = match args
+            .method
+            .as_deref()
+            .map(str::to_ascii_uppercase)
+            .as_deref()
+        {
+            None | Some("GET") => Method::GET,
+            Some("POST") => Method::POST,
+            Some(other) => return Err(HttpToolError::UnsupportedMethod(other.to_string())),
+        };
+
+        // Configured headers go last, so nothing the model sends replaces them
+        if let Some(name) = args.headers.keys().find(|name| {
+            RESERVED_HEADERS.contains(&name.to_ascii_lowercase().as_str())
+                || self
+                    .headers
+                    .keys()
+                    .any(|configured| configured.eq_ignore_ascii_case(name))
+        }) {
+            return Err(HttpToolError::InvalidHeader(name.clone()));
+        }
+        let mut headers = HeaderMap::new();
+        for (name, value) in args.headers.iter().chain(&self.headers) {
+            let invalid = || HttpToolError::InvalidHeader(name.clone());
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?,
+                HeaderValue::from_str(value).map_err(|_| invalid())?,
+            );
+        }
+        let mut request = self.client()?.request(method, url).headers(headers);
+        request = match args.body {
+            Some(Value::String(body)) => request.body(body),
+            Some(body) => request.json(&body),
+            None => request,
+        };
+
+        let mut response = request.send().await?;
+        let status = response.status().as_u16();
+        let final_url = response.url().to_string();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+
+        let mut body = Vec::new();
+        let mut truncated = false;
+        while let Some(chunk) = response.chunk().await? {
+            let room = self.max_response_bytes - body.len();
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let text = String::from_utf8_lossy(&body);
+        let mime = content_type.to_ascii_lowercase();
+        let body = if mime.contains("json") && !truncated {
+            serde_json::from_str(&text).unwrap_or_else(|_| Value::String(text.into_owned()))
+        } else if mime.contains("html") {
+            Value::String(html_to_text(&text))
+        } else {
+            Value::String(text.into_owned())
+        };
+        Ok(json!({
+            "status": status,
+            "url": final_url,
+            "content_type": content_type,
+            "body": body,
+            "truncated": truncated,
+        }))
+    }
+}
+
+static HIDDEN_ELEMENTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<!--.*?-->|<(script|style|head|noscript|template|svg)\b.*?</(script|style|head|noscript|template|svg)\s*>")
+        .unwrap()
+});
+static BLOCK_TAGS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)</?(p|div|br|li|ul|ol|h[1-6]|tr|table|section|article|header|footer|nav|main|aside|pre|blockquote|hr|dt|dd)\b[^>]*>")
+        .unwrap()
+});
+static TAGS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+static ENTITIES: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap());
+
+/// Text of an HTML page, one line per block element
+pub fn html_to_text(html: &str) -> String {
+    let html = HIDDEN_ELEMENTS.replace_all(html, "");
+    let html = BLOCK_TAGS.replace_all(&html, "\n");
+    let text = TAGS.replace_all(&html, "");
+    let text = ENTITIES.replace_all(&text, |captures: &regex::Captures| {
+        let entity = &captures[1];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        decoded.map_or_else(|| captures[0].to_string(), String::from)
+    });
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl ToolRuntime for HttpTool {
+    fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+        Err(HttpToolError::SyncCall.into())
+    }
+}
+
+#[async_trait]
+impl ToolT for HttpTool {
+    fn name(&self) -> &'static str {
+        "http_request"
+    }
+
+    fn description(&self) -> &'static str {
+        "Make an HTTP GET or POST request. Returns the status and body of the response, HTML \
+         pages are reduced to their text."
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::Network]
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {"type": "string", "description": "URL to request"},
+                "method": {"type": "string", "enum": ["GET", "POST"], "description": "GET by default"},
+                "headers": {
+                    "type": "object",
+                    "additionalProperties": {"type": "string"},
+                    "description": "Headers of the request, Host and connection headers can't be set"
+                },
+                "body": {"description": "Body of a POST request, objects are sent as JSON"}
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn call(&self, args: Value, _context: ToolCallContext) -> Result<Value, ToolCallError> {
+        let args: HttpArgs = serde_json::from_value(args)?;
+        Ok(self.request(args).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve canned responses on a local port, requests to `/echo` get their method and body
+    /// back
+    async fn stub_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                // Read the head, then as much body as the content length says
+                let (head, body) = loop {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    if read == 0 && request.is_empty() {
+                        break (String::new(), String::new());
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request).into_owned();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|line| {
+                                line.to_ascii_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|value| value.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if body.len() >= length || read == 0 {
+                            break (head.to_string(), body.to_string());
+                        }
+                    }
+                };
+                let request_line = head.lines().next().unwrap_or("GET /").to_string();
+                let path = request_line.split(' ').nth(1).unwrap();
+                let (status, content_type, body, location) = match path {
+                    "/json" => (
+                        "200 OK",
+                        "application/json",
+                        r#"{"answer": 42}"#.to_string(),
+                        None,
+                    ),
+                    "/page" => (
+                        "200 OK",
+                        "text/html; charset=utf-8",
+                        "<html><head><title>T</title><style>p {}</style></head><body>\
+                         <h1>Hello &amp; welcome</h1><p>First   line</p><script>x()</script>\
+                         <ul><li>one</li><li>two&#33;</li></ul></body></html>"
+                            .to_string(),
+                        None,
+                    ),
+                    "/big" => ("200 OK", "text/plain", "x".repeat(100), None),
+                    "/headers" => ("200 OK", "text/plain", head.to_ascii_lowercase(), None),
+                    "/echo" => (
+                        "201 Created",
+                        "text/plain",
+                        format!("{} {}", request_line.split(' ').next().unwrap(), body),
+                        None,
+                    ),
+                    "/escape" => (
+                        "302 Found",
+                        "text/plain",
+                        String::new(),
+                        Some(format!("http://localhost:{port}/json")),
+                    ),
+                    _ => ("404 Not Found", "text/plain", "missing".to_string(), None),
+                };
+                let location = location
+                    .map(|location| format!("Location: {location}\r\n"))
+                    .unwrap_or_default();
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\n{location}\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    async fn get(tool: &HttpTool, url: String) -> Result<Value, HttpToolError> {
+        tool.request(serde_json::from_value(json!({ "url": url })).unwrap())
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_json_html_and_size_cap() {
+        let port = stub_server().await;
+        let tool = HttpTool::new().allow_host(&format!("127.0.0.1:{port}"));
+        let base = format!("http://127.0.0.1:{port}");
+
+        let response = get(&tool, format!("{base}/json")).await.unwrap();
+        assert_eq!(response["status"], 200);
+        assert_eq!(response["body"], json!({"answer": 42}));
+
+        let response = get(&tool, format!("{base}/page")).await.unwrap();
+        assert_eq!(response["body"], "Hello & welcome\nFirst line\none\ntwo!");
+
+        let capped = tool.clone().with_max_response_bytes(10);
+        let response = get(&capped, format!("{base}/big")).await.unwrap();
+        assert_eq!(response["body"], "x".repeat(10));
+        assert_eq!(response["truncated"], true);
+
+        let response = get(&tool, format!("{base}/missing")).await.unwrap();
+        assert_eq!(response["status"], 404);
+    }
+
+    #[tokio::test]
+    async fn test_post() {
+        let port = stub_server().await;
+        let tool = HttpTool::new().allow_host("127.0.0.1");
+        let args = json!({
+            "url": format!("http://127.0.0.1:{port}/echo"),
+            "method": "post",
+            "body": {"name": "Ada"}
+        });
+        let response = tool
+            .request(serde_json::from_value(args).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response["status"], 201);
+        assert_eq!(response["body"], r#"POST {"name":"Ada"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_configured_headers_cannot_be_overridden() {
+        let port = stub_server().await;
+        let tool = HttpTool::new()
+            .allow_host("127.0.0.1")
+            .with_header("Authorization", "Bearer secret");
+        let request = |headers: Value| {
+            serde_json::from_value::<HttpArgs>(json!({
+                "url": format!("http://127.0.0.1:{port}/headers"),
+                "headers": headers
+            }))
+            .unwrap()
+        };
+
+        let response = tool
+            .request(request(json!({"X-Trace": "7"})))
+            .await
+            .unwrap();
+        let head = response["body"].as_str().unwrap();
+        assert!(head.contains("authorization: bearer secret"));
+        assert!(head.contains("x-trace: 7"));
+
+        for name in [
+            "authorization",
+            "Host",
+            "Content-Length",
+            "transfer-encoding",
+            "Connection",
+        ] {
+            assert!(
+                matches!(
+                    tool.request(request(json!({ name: "x" }))).await,
+                    Err(HttpToolError::InvalidHeader(header)) if header == name
+                ),
+                "{name}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hosts_outside_the_allowlist_are_refused() {
+        let port = stub_server().await;
+        let tool = HttpTool::new()
+            .allow_host("127.0.0.1")
+            .allow_host("*.example.com");
+        assert!(tool.allows("https://api.example.com/v1"));
+        assert!(!tool.allows("https://example.com.evil.io/"));
+        assert!(!tool.allows("file:///etc/passwd"));
+
+        assert!(matches!(
+            get(&tool, format!("http://localhost:{port}/json")).await,
+            Err(HttpToolError::HostNotAllowed(_))
+        ));
+        // Redirects may not leave the allowlist either
+        let error = get(&tool, format!("http://127.0.0.1:{port}/escape"))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, HttpToolError::Request(_)));
+        let mut source = std::error::Error::source(&error);
+        let mut refused = false;
+        while let Some(error) = source {
+            refused |= error.to_string().contains("not in the allowlist");
+            source = error.source();
+        }
+        assert!(refused);
+
+        assert!(HttpTool::new().execute(json!({})).is_err());
+    }
+}
@@ -1,9 +1,29 @@
+use crate::memory::MemoryProvider;
+use crate::protocol::{Event, SubmissionId};
+use async_trait::async_trait;
 use autoagents_llm::chat::{FunctionTool, Tool};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt::Debug;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+mod capability;
+#[cfg(feature = "tools-fs")]
+pub mod fs;
+#[cfg(feature = "tools-http")]
+pub mod http;
+pub mod output;
+mod registry;
 mod runtime;
+mod shared;
+#[cfg(feature = "tools-shell")]
+pub mod shell;
+mod validation;
+pub use capability::{Capability, CapabilityError, CapabilitySet};
+pub use registry::{ToolRegistry, ToolRegistryError, NAMESPACE_SEPARATOR};
 pub use runtime::ToolRuntime;
+pub use shared::SharedTool;
+pub use validation::{validate_arguments, ArgumentViolation, ArgumentsValidationError};
 
 #[cfg(feature = "wasm")]
 pub use runtime::{WasmRuntime, WasmRuntimeError};
@@ -16,6 +36,32 @@ pub struct ToolCallResult {
     pub result: Value,
 }
 
+/// The task a tool call is made on behalf of
+#[derive(Clone)]
+pub struct ToolCallContext {
+    /// Submission of the task that requested the call
+    pub sub_id: SubmissionId,
+    /// ID of the tool call as returned by the LLM
+    pub call_id: String,
+    /// Channel of the calling agent, for tools that report their own progress
+    pub tx_event: mpsc::Sender<Event>,
+    /// Memory of the calling agent
+    pub memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
+    /// Capabilities granted to the calling agent, `None` if it was granted every capability
+    pub capabilities: Option<CapabilitySet>,
+}
+
+impl Debug for ToolCallContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolCallContext")
+            .field("sub_id", &self.sub_id)
+            .field("call_id", &self.call_id)
+            .field("memory", &self.memory.is_some())
+            .field("capabilities", &self.capabilities)
+            .finish()
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ToolCallError {
     #[error("Runtime Error {0}")]
@@ -25,6 +71,7 @@ pub enum ToolCallError {
     SerdeError(#[from] serde_json::Error),
 }
 
+#[async_trait]
 pub trait ToolT: Send + Sync + Debug + ToolRuntime {
     /// The name of the tool.
     fn name(&self) -> &'static str;
@@ -32,10 +79,27 @@ pub trait ToolT: Send + Sync + Debug + ToolRuntime {
     fn description(&self) -> &'static str;
     /// Return a description of the expected arguments.
     fn args_schema(&self) -> Value;
+    /// Validate the arguments (in JSON) against `args_schema` before the tool is run.
+    fn validate_args(&self, args: &Value) -> Result<(), ArgumentsValidationError> {
+        validate_arguments(&self.args_schema(), args)
+    }
     /// Run the tool with the given arguments (in JSON) and return the result (in JSON).
     fn run(&self, args: Value) -> Result<Value, ToolCallError> {
         self.execute(args)
     }
+    /// Whether a successful result ends the task as the agent's final response,
+    /// instead of being handed back to the LLM.
+    fn returns_directly(&self) -> bool {
+        false
+    }
+    /// The capabilities the tool needs, an agent can only call it if it was granted all of them.
+    fn capabilities(&self) -> Vec<Capability> {
+        Vec::new()
+    }
+    /// Run the tool from an executor, override for tools that need to await or emit events.
+    async fn call(&self, args: Value, _context: ToolCallContext) -> Result<Value, ToolCallError> {
+        self.run(args)
+    }
 }
 
 pub trait ToolInputT {
@@ -260,6 +324,20 @@ mod tests {
         assert_eq!(output["doubled_value"], 84);
     }
 
+    #[test]
+    fn test_mock_tool_validate_args() {
+        let tool = MockTool::new("validate_tool", "Validation test");
+        assert!(tool
+            .validate_args(&json!({"name": "test", "value": 42}))
+            .is_ok());
+
+        let error = tool
+            .validate_args(&json!({"name": "test", "value": "42"}))
+            .unwrap_err();
+        assert_eq!(error.violations().len(), 1);
+        assert_eq!(error.violations()[0].path, "/value");
+    }
+
     #[test]
     fn test_mock_tool_debug() {
         let tool = MockTool::new("debug_tool", "Debug test");
@@ -0,0 +1,794 @@
+//! Limits on the size of tool results.
+//!
+//! Limits configured with
+//! [`AgentBuilder::with_tool_output_limits`](crate::agent::AgentBuilder::with_tool_output_limits)
+//! apply to every tool result before it is stored in memory and sent back to the model. A
+//! result over its limit is cut down to its head and tail, summarized by the agent's LLM, or
+//! spilled to an [`ArtifactStore`] and replaced with a preview referencing the artifact by id.
+//! Agents with spilling limits get a [`ReadArtifactTool`] to read spilled results page by page.
+//! Every limited result is reported with [`Event::ToolOutputLimited`].
+//!
+//! ```ignore
+//! let limits = ToolOutputLimits::new()
+//!     .with_limit(OutputLimit::truncate(8_000))
+//!     .with_tool_limit("grep", OutputLimit::spill(4_000))
+//!     .with_tool_limit("http_request", OutputLimit::summarize(2_000));
+//! let agent = AgentBuilder::new(agent).with_tool_output_limits(limits);
+//! ```
+
+use super::{
+    ArgumentsValidationError, Capability, ToolCallContext, ToolCallError, ToolRuntime, ToolT,
+};
+use crate::protocol::Event;
+use async_trait::async_trait;
+use autoagents_llm::chat::ChatMessage;
+use autoagents_llm::LLMProvider;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Most characters of a result handed to the LLM for summarizing
+pub const MAX_SUMMARY_INPUT: usize = 100_000;
+/// Characters of an artifact the read_artifact tool returns by default
+pub const DEFAULT_ARTIFACT_PAGE: usize = 4_000;
+/// Artifacts an [`InMemoryArtifactStore`] keeps by default
+pub const DEFAULT_MAX_ARTIFACTS: usize = 64;
+/// Bytes of artifacts an [`InMemoryArtifactStore`] keeps by default
+pub const DEFAULT_MAX_ARTIFACT_BYTES: usize = 32 * 1024 * 1024;
+
+/// Error type for artifact stores
+#[derive(Debug, Error)]
+pub enum ArtifactError {
+    #[error("No artifact with id '{0}'")]
+    NotFound(String),
+
+    #[error("Artifact storage error: {0}")]
+    Storage(String),
+
+    #[error("Artifact of {bytes} bytes is over the store's limit of {max} bytes")]
+    TooLarge { bytes: usize, max: usize },
+
+    #[error("Artifacts can only be read by an async executor")]
+    SyncCall,
+}
+
+impl From<ArtifactError> for ToolCallError {
+    fn from(error: ArtifactError) -> Self {
+        ToolCallError::RuntimeError(Box::new(error))
+    }
+}
+
+/// What happens to a tool result over its limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputStrategy {
+    /// Keep the head and the tail of the result, with a marker in place of the rest
+    Truncate,
+    /// Replace the result with a summary written by the agent's LLM
+    Summarize,
+    /// Store the result as an artifact and keep a preview referencing it
+    Spill,
+}
+
+/// Size limit of tool results, in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputLimit {
+    pub max_chars: usize,
+    pub strategy: OutputStrategy,
+}
+
+impl OutputLimit {
+    pub fn truncate(max_chars: usize) -> Self {
+        Self {
+            max_chars,
+            strategy: OutputStrategy::Truncate,
+        }
+    }
+
+    pub fn summarize(max_chars: usize) -> Self {
+        Self {
+            max_chars,
+            strategy: OutputStrategy::Summarize,
+        }
+    }
+
+    pub fn spill(max_chars: usize) -> Self {
+        Self {
+            max_chars,
+            strategy: OutputStrategy::Spill,
+        }
+    }
+}
+
+/// Output limits of an agent's tools: a limit for all tools and limits for single tools
+#[derive(Debug, Clone)]
+pub struct ToolOutputLimits {
+    global: Option<OutputLimit>,
+    per_tool: HashMap<String, OutputLimit>,
+    artifacts: Arc<dyn ArtifactStore>,
+}
+
+impl Default for ToolOutputLimits {
+    fn default() -> Self {
+        Self {
+            global: None,
+            per_tool: HashMap::new(),
+            artifacts: Arc::new(InMemoryArtifactStore::new()),
+        }
+    }
+}
+
+impl ToolOutputLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the results of every tool without a limit of its own
+    pub fn with_limit(mut self, limit: OutputLimit) -> Self {
+        self.global = Some(limit);
+        self
+    }
+
+    /// Limit the results of the tool named `tool`
+    pub fn with_tool_limit(mut self, tool: impl Into<String>, limit: OutputLimit) -> Self {
+        self.per_tool.insert(tool.into(), limit);
+        self
+    }
+
+    /// Store spilled results in `store` instead of in memory
+    pub fn with_artifact_store(mut self, store: Arc<dyn ArtifactStore>) -> Self {
+        self.artifacts = store;
+        self
+    }
+
+    /// The limit applying to the results of the tool named `tool`
+    pub fn limit_for(&self, tool: &str) -> Option<OutputLimit> {
+        self.per_tool.get(tool).copied().or(self.global)
+    }
+
+    pub fn artifacts(&self) -> Arc<dyn ArtifactStore> {
+        self.artifacts.clone()
+    }
+
+    fn spills(&self) -> bool {
+        self.global
+            .iter()
+            .chain(self.per_tool.values())
+            .any(|limit| limit.strategy == OutputStrategy::Spill)
+    }
+
+    /// Wrap tools so their results are limited, with the tool reading artifacts if results
+    /// may be spilled
+    pub(crate) fn limit_tools(
+        &self,
+        tools: Vec<Box<dyn ToolT>>,
+        llm: Arc<dyn LLMProvider>,
+    ) -> Vec<Box<dyn ToolT>> {
+        let mut limited: Vec<Box<dyn ToolT>> = tools
+            .into_iter()
+            .map(|tool| match self.limit_for(tool.name()) {
+                Some(limit) => Box::new(LimitedTool {
+                    inner: tool,
+                    limit,
+                    llm: llm.clone(),
+                    artifacts: self.artifacts.clone(),
+                }) as Box<dyn ToolT>,
+                None => tool,
+            })
+            .collect();
+        if self.spills() && !limited.iter().any(|tool| tool.name() == READ_ARTIFACT) {
+            limited.push(Box::new(ReadArtifactTool::new(self.artifacts.clone())));
+        }
+        limited
+    }
+}
+
+/// Storage for tool results too large to keep in memory, keyed by artifact id
+#[async_trait]
+pub trait ArtifactStore: Send + Sync + Debug {
+    /// Store the full result of a tool call and return the id of the artifact
+    async fn save(&self, tool_name: &str, content: String) -> Result<String, ArtifactError>;
+
+    async fn load(&self, id: &str) -> Result<Option<String>, ArtifactError>;
+}
+
+/// Artifact store keeping artifacts in memory, for as long as the store lives.
+///
+/// The store holds at most [`DEFAULT_MAX_ARTIFACTS`] artifacts of [`DEFAULT_MAX_ARTIFACT_BYTES`]
+/// in total unless configured otherwise. Saving past either limit evicts the oldest artifacts,
+/// reading an evicted artifact finds nothing. An artifact larger than the byte limit is refused,
+/// and the result is truncated instead of spilled.
+#[derive(Debug)]
+pub struct InMemoryArtifactStore {
+    artifacts: RwLock<StoredArtifacts>,
+    max_artifacts: usize,
+    max_bytes: usize,
+}
+
+#[derive(Debug, Default)]
+struct StoredArtifacts {
+    by_id: HashMap<String, String>,
+    /// Ids from the oldest artifact to the newest
+    order: VecDeque<String>,
+    bytes: usize,
+}
+
+impl StoredArtifacts {
+    fn evict_oldest(&mut self) {
+        if let Some(id) = self.order.pop_front() {
+            if let Some(content) = self.by_id.remove(&id) {
+                self.bytes -= content.len();
+            }
+        }
+    }
+}
+
+impl Default for InMemoryArtifactStore {
+    fn default() -> Self {
+        Self {
+            artifacts: RwLock::default(),
+            max_artifacts: DEFAULT_MAX_ARTIFACTS,
+            max_bytes: DEFAULT_MAX_ARTIFACT_BYTES,
+        }
+    }
+}
+
+impl InMemoryArtifactStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `count` artifacts, evicting the oldest
+    pub fn with_max_artifacts(mut self, count: usize) -> Self {
+        self.max_artifacts = count;
+        self
+    }
+
+    /// Keep at most `bytes` of artifacts, evicting the oldest
+    pub fn with_max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = bytes;
+        self
+    }
+
+    /// Number of artifacts held
+    pub async fn len(&self) -> usize {
+        self.artifacts.read().await.order.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
+
+#[async_trait]
+impl ArtifactStore for InMemoryArtifactStore {
+    async fn save(&self, tool_name: &str, content: String) -> Result<String, ArtifactError> {
+        if content.len() > self.max_bytes || self.max_artifacts == 0 {
+            return Err(ArtifactError::TooLarge {
+                bytes: content.len(),
+                max: self.max_bytes,
+            });
+        }
+        let id = format!("{tool_name}-{}", Uuid::new_v4().simple());
+        let mut artifacts = self.artifacts.write().await;
+        while artifacts.order.len() >= self.max_artifacts
+            || artifacts.bytes + content.len() > self.max_bytes
+        {
+            artifacts.evict_oldest();
+        }
+        artifacts.bytes += content.len();
+        artifacts.order.push_back(id.clone());
+        artifacts.by_id.insert(id.clone(), content);
+        Ok(id)
+    }
+
+    async fn load(&self, id: &str) -> Result<Option<String>, ArtifactError> {
+        Ok(self.artifacts.read().await.by_id.get(id).cloned())
+    }
+}
+
+/// Keep the head and the tail of `text`, at most `max_chars` characters of it, with a marker
+/// counting the characters left out
+pub fn truncate_middle(text: &str, max_chars: usize) -> String {
+    let total = text.chars().count();
+    if total <= max_chars {
+        return text.to_string();
+    }
+    let head: String = text.chars().take(max_chars - max_chars / 2).collect();
+    let tail: String = text.chars().skip(total - max_chars / 2).collect();
+    format!(
+        "{head}\n... [{} characters truncated] ...\n{tail}",
+        total - max_chars
+    )
+}
+
+/// Text of a tool result, strings as they are and other values as JSON
+fn result_text(result: &Value) -> String {
+    match result {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Tool whose results are cut down to a limit
+struct LimitedTool {
+    inner: Box<dyn ToolT>,
+    limit: OutputLimit,
+    llm: Arc<dyn LLMProvider>,
+    artifacts: Arc<dyn ArtifactStore>,
+}
+
+impl Debug for LimitedTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LimitedTool")
+            .field("inner", &self.inner)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl LimitedTool {
+    /// Summary of `text` by the LLM, `None` if the LLM fails
+    async fn summarize(&self, text: &str) -> Option<String> {
+        let prompt = format!(
+            "Summarize the following output of the tool `{}` in at most {} characters. \
+             Keep the names, numbers, paths and errors needed to continue the task.\n\n{}",
+            self.inner.name(),
+            self.limit.max_chars,
+            truncate_middle(text, MAX_SUMMARY_INPUT)
+        );
+        let response = self
+            .llm
+            .chat(&[ChatMessage::user().content(prompt).build()], None)
+            .await
+            .ok()?;
+        response.text().filter(|summary| !summary.trim().is_empty())
+    }
+
+    /// Apply the limit to `text`, returning the limited text and the id of its artifact
+    async fn limit(&self, text: &str) -> (String, Option<String>) {
+        let total = text.chars().count();
+        let max_chars = self.limit.max_chars;
+        match self.limit.strategy {
+            OutputStrategy::Truncate => {}
+            OutputStrategy::Summarize => {
+                if let Some(summary) = self.summarize(text).await {
+                    let summary = truncate_middle(summary.trim(), max_chars);
+                    return (
+                        format!("[Summary of {total} characters of output]\n{summary}"),
+                        None,
+                    );
+                }
+            }
+            OutputStrategy::Spill => {
+                let saved = self
+                    .artifacts
+                    .save(self.inner.name(), text.to_string())
+                    .await;
+                if let Ok(id) = saved {
+                    let preview: String = text.chars().take(max_chars).collect();
+                    let notice = format!(
+                        "\n... [Output of {total} characters stored as artifact '{id}', \
+                         read it with the {READ_ARTIFACT} tool] ..."
+                    );
+                    return (preview + &notice, Some(id));
+                }
+            }
+        }
+        // Results that could not be summarized or spilled are truncated
+        (truncate_middle(text, max_chars), None)
+    }
+}
+
+impl ToolRuntime for LimitedTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        // Sync calls don't reach memory through the executor, only the size is limited
+        let result = self.inner.run(args)?;
+        let text = result_text(&result);
+        if text.chars().count() <= self.limit.max_chars {
+            return Ok(result);
+        }
+        Ok(Value::String(truncate_middle(&text, self.limit.max_chars)))
+    }
+}
+
+#[async_trait]
+impl ToolT for LimitedTool {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn validate_args(&self, args: &Value) -> Result<(), ArgumentsValidationError> {
+        self.inner.validate_args(args)
+    }
+
+    fn returns_directly(&self) -> bool {
+        self.inner.returns_directly()
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.inner.capabilities()
+    }
+
+    async fn call(&self, args: Value, context: ToolCallContext) -> Result<Value, ToolCallError> {
+        let sub_id = context.sub_id;
+        let tx_event = context.tx_event.clone();
+        let result = self.inner.call(args, context).await?;
+        let text = result_text(&result);
+        let original_chars = text.chars().count();
+        if original_chars <= self.limit.max_chars {
+            return Ok(result);
+        }
+        let (limited, artifact_id) = self.limit(&text).await;
+        let _ = tx_event
+            .send(Event::ToolOutputLimited {
+                sub_id,
+                tool_name: self.inner.name().to_string(),
+                strategy: self.limit.strategy,
+                original_chars,
+                artifact_id,
+            })
+            .await;
+        Ok(Value::String(limited))
+    }
+}
+
+const READ_ARTIFACT: &str = "read_artifact";
+
+#[derive(Debug, Deserialize)]
+struct ReadArtifactArgs {
+    id: String,
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+/// Tool reading a page of a spilled tool result
+#[derive(Debug)]
+pub struct ReadArtifactTool {
+    artifacts: Arc<dyn ArtifactStore>,
+    page: usize,
+}
+
+impl ReadArtifactTool {
+    pub fn new(artifacts: Arc<dyn ArtifactStore>) -> Self {
+        Self {
+            artifacts,
+            page: DEFAULT_ARTIFACT_PAGE,
+        }
+    }
+
+    /// Set the most characters returned by one call
+    pub fn with_page_size(mut self, chars: usize) -> Self {
+        self.page = chars;
+        self
+    }
+}
+
+impl ToolRuntime for ReadArtifactTool {
+    fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+        Err(ArtifactError::SyncCall.into())
+    }
+}
+
+#[async_trait]
+impl ToolT for ReadArtifactTool {
+    fn name(&self) -> &'static str {
+        READ_ARTIFACT
+    }
+
+    fn description(&self) -> &'static str {
+        "Read part of a tool output stored as an artifact. Give the artifact id and the \
+         character offset to start reading at."
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "string", "description": "Id of the artifact"},
+                "offset": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Character to start reading at, 0 by default"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Most characters to read"
+                }
+            },
+            "required": ["id"]
+        })
+    }
+
+    async fn call(&self, args: Value, _context: ToolCallContext) -> Result<Value, ToolCallError> {
+        let args: ReadArtifactArgs = serde_json::from_value(args)?;
+        let content = self
+            .artifacts
+            .load(&args.id)
+            .await?
+            .ok_or(ArtifactError::NotFound(args.id))?;
+        let total = content.chars().count();
+        let limit = args.limit.unwrap_or(self.page).min(self.page);
+        // Offsets come from the model, one past the end reads an empty page
+        let text: String = if args.offset < total {
+            content.chars().skip(args.offset).take(limit).collect()
+        } else {
+            String::new()
+        };
+        let end = args.offset.saturating_add(limit).min(total);
+        Ok(json!({
+            "content": text,
+            "offset": args.offset,
+            "total_chars": total,
+            "next_offset": (end < total).then_some(end),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::prebuilt::react::ReActExecutor;
+    use crate::agent::{AgentDeriveT, BaseAgent, IntoRunnable};
+    use crate::memory::SlidingWindowMemory;
+    use crate::runtime::Task;
+    use autoagents_test_utils::llm::{tool_call, ScriptedLLMProvider, ScriptedResponse};
+    use tokio::sync::mpsc;
+
+    /// A tool answering with a long listing
+    #[derive(Debug)]
+    struct ListingTool;
+
+    impl ToolRuntime for ListingTool {
+        fn execute(&self, _args: Value) -> Result<Value, ToolCallError> {
+            let lines: Vec<String> = (0..1000).map(|i| format!("line {i}")).collect();
+            Ok(Value::String(lines.join("\n")))
+        }
+    }
+
+    impl ToolT for ListingTool {
+        fn name(&self) -> &'static str {
+            "listing"
+        }
+
+        fn description(&self) -> &'static str {
+            "List lines"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object", "properties": {}})
+        }
+    }
+
+    fn context(tx_event: mpsc::Sender<Event>) -> ToolCallContext {
+        ToolCallContext {
+            sub_id: Uuid::new_v4(),
+            call_id: "call_1".to_string(),
+            tx_event,
+            memory: None,
+            capabilities: None,
+        }
+    }
+
+    #[test]
+    fn test_truncate_middle() {
+        assert_eq!(truncate_middle("short", 10), "short");
+        assert_eq!(
+            truncate_middle("abcdefghij", 4),
+            "ab\n... [6 characters truncated] ...\nij"
+        );
+        assert_eq!(
+            truncate_middle("ééééé", 3),
+            "éé\n... [2 characters truncated] ...\né"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_limits_by_strategy() {
+        let llm = Arc::new(ScriptedLLMProvider::new(vec![
+            ScriptedResponse::Text("1000 lines numbered 0 to 999".to_string()),
+            ScriptedResponse::Error("down".to_string()),
+        ]));
+        let (tx, mut rx) = mpsc::channel(10);
+
+        let limits = ToolOutputLimits::new()
+            .with_limit(OutputLimit::truncate(100))
+            .with_tool_limit("other", OutputLimit::summarize(100));
+        let tools = limits.limit_tools(vec![Box::new(ListingTool)], llm.clone());
+        assert_eq!(tools.len(), 1);
+        let result = tools[0].call(json!({}), context(tx.clone())).await.unwrap();
+        let text = result.as_str().unwrap();
+        assert!(text.starts_with("line 0\nline 1"));
+        assert!(text.ends_with("line 998\nline 999"));
+        assert!(text.contains("characters truncated"));
+
+        let limits = ToolOutputLimits::new().with_tool_limit("listing", OutputLimit::summarize(50));
+        let tools = limits.limit_tools(vec![Box::new(ListingTool)], llm.clone());
+        let result = tools[0].call(json!({}), context(tx.clone())).await.unwrap();
+        assert_eq!(
+            result,
+            "[Summary of 8889 characters of output]\n1000 lines numbered 0 to 999"
+        );
+        // Without a summary the result is truncated
+        let result = tools[0].call(json!({}), context(tx.clone())).await.unwrap();
+        assert!(result.as_str().unwrap().contains("characters truncated"));
+
+        let strategies: Vec<OutputStrategy> = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|event| match event {
+                Event::ToolOutputLimited {
+                    tool_name,
+                    strategy,
+                    original_chars,
+                    ..
+                } => {
+                    assert_eq!(tool_name, "listing");
+                    assert_eq!(original_chars, 8889);
+                    strategy
+                }
+                other => panic!("unexpected event {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            strategies,
+            vec![
+                OutputStrategy::Truncate,
+                OutputStrategy::Summarize,
+                OutputStrategy::Summarize
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_spill_and_read_artifact() {
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(Vec::<String>::new()));
+        let (tx, mut rx) = mpsc::channel(10);
+        let limits = ToolOutputLimits::new().with_limit(OutputLimit::spill(20));
+        let tools = limits.limit_tools(vec![Box::new(ListingTool)], llm);
+        assert_eq!(tools[1].name(), "read_artifact");
+
+        let result = tools[0].call(json!({}), context(tx.clone())).await.unwrap();
+        assert!(result
+            .as_str()
+            .unwrap()
+            .starts_with("line 0\nline 1\nline 2"));
+        let Some(Event::ToolOutputLimited {
+            artifact_id: Some(id),
+            ..
+        }) = rx.try_recv().ok()
+        else {
+            panic!("expected a spilled result");
+        };
+        assert!(result.as_str().unwrap().contains(&id));
+
+        let page = tools[1]
+            .call(
+                json!({"id": id, "offset": 7, "limit": 6}),
+                context(tx.clone()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(page["content"], "line 1");
+        assert_eq!(page["total_chars"], 8889);
+        assert_eq!(page["next_offset"], 13);
+
+        let past_end = tools[1]
+            .call(json!({"id": id, "offset": usize::MAX}), context(tx.clone()))
+            .await
+            .unwrap();
+        assert_eq!(past_end["content"], "");
+        assert_eq!(past_end["next_offset"], Value::Null);
+
+        let missing = tools[1].call(json!({"id": "listing-0"}), context(tx)).await;
+        assert!(missing.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_evicts_oldest_artifacts() {
+        let store = InMemoryArtifactStore::new().with_max_artifacts(2);
+        let first = store.save("a", "first".to_string()).await.unwrap();
+        let second = store.save("a", "second".to_string()).await.unwrap();
+        let third = store.save("a", "third".to_string()).await.unwrap();
+        assert_eq!(store.len().await, 2);
+        assert_eq!(store.load(&first).await.unwrap(), None);
+        assert_eq!(store.load(&second).await.unwrap().unwrap(), "second");
+        assert_eq!(store.load(&third).await.unwrap().unwrap(), "third");
+
+        let store = InMemoryArtifactStore::new().with_max_bytes(10);
+        let first = store.save("a", "abcd".to_string()).await.unwrap();
+        let second = store.save("a", "efgh".to_string()).await.unwrap();
+        let third = store.save("a", "ijkl".to_string()).await.unwrap();
+        assert_eq!(store.load(&first).await.unwrap(), None);
+        assert!(store.load(&second).await.unwrap().is_some());
+        assert!(store.load(&third).await.unwrap().is_some());
+        assert!(matches!(
+            store.save("a", "x".repeat(11)).await,
+            Err(ArtifactError::TooLarge { bytes: 11, max: 10 })
+        ));
+        assert_eq!(store.len().await, 2);
+
+        // A result too large for the store is truncated
+        let llm = Arc::new(ScriptedLLMProvider::with_texts(Vec::<String>::new()));
+        let (tx, mut rx) = mpsc::channel(10);
+        let limits = ToolOutputLimits::new()
+            .with_limit(OutputLimit::spill(20))
+            .with_artifact_store(Arc::new(store));
+        let tools = limits.limit_tools(vec![Box::new(ListingTool)], llm);
+        let result = tools[0].call(json!({}), context(tx)).await.unwrap();
+        assert!(result.as_str().unwrap().contains("characters truncated"));
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(Event::ToolOutputLimited {
+                artifact_id: None,
+                ..
+            })
+        ));
+    }
+
+    #[derive(Debug)]
+    struct ListingAgent;
+
+    impl ReActExecutor for ListingAgent {}
+
+    impl AgentDeriveT for ListingAgent {
+        type Output = String;
+
+        fn description(&self) -> &'static str {
+            "Reads listings"
+        }
+
+        fn output_schema(&self) -> Option<Value> {
+            None
+        }
+
+        fn name(&self) -> &'static str {
+            "lister"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn ToolT>> {
+            vec![Box::new(ListingTool)]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_stores_limited_results() {
+        let llm = Arc::new(ScriptedLLMProvider::new(vec![
+            ScriptedResponse::ToolCalls(vec![tool_call("call_1", "listing", "{}")]),
+            ScriptedResponse::Text("The listing has 1000 lines".to_string()),
+        ]));
+        let memory = Box::new(SlidingWindowMemory::new(20));
+        let mut agent = BaseAgent::new(ListingAgent, llm.clone(), Some(memory));
+        agent.tool_output_limits = Some(ToolOutputLimits::new().with_limit(OutputLimit::spill(20)));
+        let memory = agent.memory().unwrap();
+        let (tx, _rx) = mpsc::channel(100);
+        agent
+            .into_runnable()
+            .run(Task::new("How long is the listing?", None), tx)
+            .await
+            .unwrap();
+
+        // The model only sees the preview
+        let requests = llm.requests();
+        let prompt = serde_json::to_string(&requests[1]).unwrap();
+        assert!(prompt.contains("stored as artifact 'listing-"));
+        assert!(!prompt.contains("line 999"));
+        // Memory holds the preview as well
+        let stored = memory.read().await.recall("", None).await.unwrap();
+        let stored = serde_json::to_string(&stored).unwrap();
+        assert!(stored.contains("read_artifact"));
+        assert!(!stored.contains("line 999"));
+    }
+}
@@ -0,0 +1,311 @@
+use super::{
+    ArgumentsValidationError, Capability, SharedTool, ToolCallContext, ToolCallError, ToolRuntime,
+    ToolT,
+};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use thiserror::Error;
+
+/// Separator between the namespace and the name of a namespaced tool
+pub const NAMESPACE_SEPARATOR: &str = "__";
+
+/// Error type for tool registries
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ToolRegistryError {
+    #[error("A tool named '{0}' is already registered")]
+    NameTaken(String),
+
+    #[error("No tool named '{0}' is registered")]
+    NotFound(String),
+}
+
+/// Tools shared by agents, which can change while the agents run.
+///
+/// Unlike the tools an agent declares, registered tools are instances, so they can hold state
+/// such as database handles, API clients or root paths. A registry is a handle, clones share
+/// the same tools. Agents see the tools registered when a task starts, see
+/// [`AgentBuilder::with_tool_registry`](crate::agent::AgentBuilder::with_tool_registry).
+#[derive(Debug, Clone, Default)]
+pub struct ToolRegistry {
+    tools: Arc<RwLock<BTreeMap<String, Entry>>>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    tool: SharedTool,
+    /// Name of the tool this entry is an alias of
+    alias_of: Option<String>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a tool under its own name
+    pub fn register(&self, tool: impl ToolT + 'static) -> Result<(), ToolRegistryError> {
+        self.insert(SharedTool::new(Arc::new(tool)), None)
+    }
+
+    /// Register a tool under `name` instead of its own name
+    pub fn register_as(
+        &self,
+        name: &str,
+        tool: impl ToolT + 'static,
+    ) -> Result<(), ToolRegistryError> {
+        self.insert(renamed(name, SharedTool::new(Arc::new(tool))), None)
+    }
+
+    /// Register a tool as `<namespace>__<name>`, to tell apart tools of the same name from
+    /// different sources
+    pub fn register_namespaced(
+        &self,
+        namespace: &str,
+        tool: impl ToolT + 'static,
+    ) -> Result<(), ToolRegistryError> {
+        let name = format!("{namespace}{NAMESPACE_SEPARATOR}{}", tool.name());
+        self.register_as(&name, tool)
+    }
+
+    /// Make a registered tool callable under `alias` as well
+    pub fn alias(&self, name: &str, alias: &str) -> Result<(), ToolRegistryError> {
+        let entry = self
+            .tools
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ToolRegistryError::NotFound(name.to_string()))?;
+        let target = entry.alias_of.unwrap_or_else(|| name.to_string());
+        self.insert(renamed(alias, entry.tool), Some(target))
+    }
+
+    /// Remove a tool along with its aliases, or remove a single alias
+    pub fn remove(&self, name: &str) -> Option<SharedTool> {
+        let mut tools = self.tools.write().unwrap();
+        let removed = tools.remove(name)?;
+        if removed.alias_of.is_none() {
+            tools.retain(|_, entry| entry.alias_of.as_deref() != Some(name));
+        }
+        Some(removed.tool)
+    }
+
+    pub fn get(&self, name: &str) -> Option<SharedTool> {
+        self.tools
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|entry| entry.tool.clone())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tools.read().unwrap().contains_key(name)
+    }
+
+    /// Names of the registered tools, in alphabetical order
+    pub fn names(&self) -> Vec<String> {
+        self.tools.read().unwrap().keys().cloned().collect()
+    }
+
+    /// The registered tools as they are now
+    pub fn tools(&self) -> Vec<Box<dyn ToolT>> {
+        let tools: Vec<SharedTool> = self
+            .tools
+            .read()
+            .unwrap()
+            .values()
+            .map(|entry| entry.tool.clone())
+            .collect();
+        SharedTool::boxed(&tools)
+    }
+
+    fn insert(&self, tool: SharedTool, alias_of: Option<String>) -> Result<(), ToolRegistryError> {
+        let mut tools = self.tools.write().unwrap();
+        let name = tool.name().to_string();
+        if tools.contains_key(&name) {
+            return Err(ToolRegistryError::NameTaken(name));
+        }
+        tools.insert(name, Entry { tool, alias_of });
+        Ok(())
+    }
+}
+
+fn renamed(name: &str, tool: SharedTool) -> SharedTool {
+    SharedTool::new(Arc::new(RenamedTool {
+        name: intern(name),
+        inner: tool,
+    }))
+}
+
+/// Static copy of a tool name. Every distinct name is allocated once and kept for the life
+/// of the process, registering a tool under the same name again reuses it.
+fn intern(name: &str) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut names = NAMES.get_or_init(Default::default).lock().unwrap();
+    if let Some(name) = names.get(name) {
+        return name;
+    }
+    let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+    names.insert(name);
+    name
+}
+
+/// A tool exposed under another name
+#[derive(Debug)]
+struct RenamedTool {
+    name: &'static str,
+    inner: SharedTool,
+}
+
+impl ToolRuntime for RenamedTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        self.inner.run(args)
+    }
+}
+
+#[async_trait]
+impl ToolT for RenamedTool {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.inner.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.inner.args_schema()
+    }
+
+    fn validate_args(&self, args: &Value) -> Result<(), ArgumentsValidationError> {
+        self.inner.validate_args(args)
+    }
+
+    fn returns_directly(&self) -> bool {
+        self.inner.returns_directly()
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.inner.capabilities()
+    }
+
+    async fn call(&self, args: Value, context: ToolCallContext) -> Result<Value, ToolCallError> {
+        self.inner.call(args, context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A tool with state, answering with its greeting
+    #[derive(Debug)]
+    struct GreetTool {
+        greeting: String,
+    }
+
+    impl GreetTool {
+        fn new(greeting: &str) -> Self {
+            Self {
+                greeting: greeting.to_string(),
+            }
+        }
+    }
+
+    impl ToolRuntime for GreetTool {
+        fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+            Ok(json!(format!(
+                "{} {}",
+                self.greeting,
+                args["name"].as_str().unwrap()
+            )))
+        }
+    }
+
+    impl ToolT for GreetTool {
+        fn name(&self) -> &'static str {
+            "greet"
+        }
+
+        fn description(&self) -> &'static str {
+            "Greet someone"
+        }
+
+        fn args_schema(&self) -> Value {
+            json!({"type": "object", "properties": {"name": {"type": "string"}}})
+        }
+    }
+
+    #[test]
+    fn test_register_and_remove() {
+        let registry = ToolRegistry::new();
+        let handle = registry.clone();
+        registry.register(GreetTool::new("Hello")).unwrap();
+        assert_eq!(
+            registry.register(GreetTool::new("Hi")),
+            Err(ToolRegistryError::NameTaken("greet".to_string()))
+        );
+
+        // Clones share the tools
+        let tool = handle.get("greet").unwrap();
+        assert_eq!(tool.run(json!({"name": "Ada"})).unwrap(), "Hello Ada");
+
+        assert!(handle.remove("greet").is_some());
+        assert!(registry.tools().is_empty());
+        assert!(registry.remove("greet").is_none());
+    }
+
+    #[test]
+    fn test_namespaces_and_aliases() {
+        let registry = ToolRegistry::new();
+        registry
+            .register_namespaced("english", GreetTool::new("Hello"))
+            .unwrap();
+        registry
+            .register_namespaced("french", GreetTool::new("Bonjour"))
+            .unwrap();
+        registry.alias("french__greet", "saluer").unwrap();
+        assert_eq!(
+            registry.alias("german__greet", "gruessen"),
+            Err(ToolRegistryError::NotFound("german__greet".to_string()))
+        );
+
+        assert_eq!(
+            registry.names(),
+            vec!["english__greet", "french__greet", "saluer"]
+        );
+        let tools = registry.tools();
+        assert_eq!(tools[2].name(), "saluer");
+        assert_eq!(tools[2].description(), "Greet someone");
+        assert_eq!(tools[2].run(json!({"name": "Ada"})).unwrap(), "Bonjour Ada");
+    }
+
+    #[test]
+    fn test_remove_drops_aliases() {
+        let registry = ToolRegistry::new();
+        registry.register(GreetTool::new("Hello")).unwrap();
+        registry.alias("greet", "hello").unwrap();
+        registry.alias("hello", "hi").unwrap();
+        registry
+            .register_as("welcome", GreetTool::new("Welcome"))
+            .unwrap();
+        registry.alias("welcome", "hey").unwrap();
+
+        // Removing an alias keeps the tool
+        assert!(registry.remove("hey").is_some());
+        assert!(registry.contains("welcome"));
+
+        assert!(registry.remove("greet").is_some());
+        assert_eq!(registry.names(), vec!["welcome"]);
+    }
+
+    #[test]
+    fn test_renamed_tools_share_interned_names() {
+        let first = renamed("lookup", SharedTool::new(Arc::new(GreetTool::new("Hi"))));
+        let second = renamed("lookup", SharedTool::new(Arc::new(GreetTool::new("Hey"))));
+        assert!(std::ptr::eq(first.name(), second.name()));
+    }
+}
@@ -0,0 +1,72 @@
+use super::{
+    ArgumentsValidationError, Capability, ToolCallContext, ToolCallError, ToolRuntime, ToolT,
+};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// A cloneable handle to a tool, for handing the same tool out more than once
+#[derive(Debug, Clone)]
+pub struct SharedTool(Arc<dyn ToolT>);
+
+impl SharedTool {
+    pub fn new(tool: Arc<dyn ToolT>) -> Self {
+        Self(tool)
+    }
+
+    /// Box every shared tool so the set can be passed to an executor
+    pub fn boxed(tools: &[SharedTool]) -> Vec<Box<dyn ToolT>> {
+        tools
+            .iter()
+            .cloned()
+            .map(|t| Box::new(t) as Box<dyn ToolT>)
+            .collect()
+    }
+}
+
+impl From<Box<dyn ToolT>> for SharedTool {
+    fn from(tool: Box<dyn ToolT>) -> Self {
+        Self(Arc::from(tool))
+    }
+}
+
+impl ToolRuntime for SharedTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        self.0.run(args)
+    }
+}
+
+#[async_trait]
+impl ToolT for SharedTool {
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn description(&self) -> &'static str {
+        self.0.description()
+    }
+
+    fn args_schema(&self) -> Value {
+        self.0.args_schema()
+    }
+
+    fn validate_args(&self, args: &Value) -> Result<(), ArgumentsValidationError> {
+        self.0.validate_args(args)
+    }
+
+    fn run(&self, args: Value) -> Result<Value, ToolCallError> {
+        self.0.run(args)
+    }
+
+    fn returns_directly(&self) -> bool {
+        self.0.returns_directly()
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.0.capabilities()
+    }
+
+    async fn call(&self, args: Value, context: ToolCallContext) -> Result<Value, ToolCallError> {
+        self.0.call(args, context).await
+    }
+}
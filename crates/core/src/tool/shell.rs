@@ -0,0 +1,510 @@
+//! Tool running commands in a working directory.
+//!
+//! Commands are run directly, not through a shell, so the allow and deny rules see the
+//! program and arguments that actually run. The environment of the command is cleared except
+//! for the variables passed through, which keeps API keys and other secrets of the host
+//! process away from it. Programs are named, not given as paths, and looked up on the `PATH`
+//! passed to the command, so a rule allowing `cargo` can't be met by a `cargo` the model put
+//! somewhere else.
+
+use super::{Capability, ToolCallContext, ToolCallError, ToolRuntime, ToolT};
+use crate::environment::EnvironmentConfig;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Environment variables passed to commands by default
+pub const DEFAULT_PASSED_ENV: &[&str] =
+    &["PATH", "HOME", "LANG", "LC_ALL", "TERM", "TMPDIR", "USER"];
+/// Time a command may run by default
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Output kept of each of stdout and stderr by default
+pub const DEFAULT_MAX_OUTPUT: usize = 16 * 1024;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How long output is still read after the command exits
+const OUTPUT_GRACE: Duration = Duration::from_secs(1);
+
+/// Error type for the shell tool
+#[derive(Debug, Error)]
+pub enum ShellError {
+    #[error("Command '{0}' is denied")]
+    Denied(String),
+
+    #[error("Command '{0}' is not in the allowlist")]
+    NotAllowed(String),
+
+    #[error("Program '{0}' must be a name looked up on PATH, not a path")]
+    ProgramPath(String),
+
+    #[error("Failed to run '{command}': {source}")]
+    Spawn {
+        command: String,
+        source: std::io::Error,
+    },
+}
+
+impl From<ShellError> for ToolCallError {
+    fn from(error: ShellError) -> Self {
+        ToolCallError::RuntimeError(Box::new(error))
+    }
+}
+
+#[derive(Deserialize)]
+struct ShellArgs {
+    program: String,
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+/// Run a program with arguments in a working directory.
+///
+/// Returns the exit code, whether the command timed out and its stdout and stderr as JSON. A
+/// command failing is a result for the model to read, not a tool error. Rules given to
+/// [`ShellTool::allow`] and [`ShellTool::deny`] are command prefixes: `"cargo"` matches every
+/// cargo command, `"git push"` only pushes. Rules only compare the leading words, so
+/// `"git push"` does not match `git -C . push`; refuse what must not run by leaving it out of
+/// the allowlist rather than with a deny rule. On Unix a command runs in its own process group,
+/// which is killed as a whole when it times out.
+#[derive(Debug, Clone)]
+pub struct ShellTool {
+    working_dir: PathBuf,
+    allow: Vec<Vec<String>>,
+    deny: Vec<Vec<String>>,
+    passed_env: Vec<String>,
+    env: BTreeMap<String, String>,
+    timeout: Duration,
+    max_output: usize,
+}
+
+impl ShellTool {
+    pub fn new(working_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            working_dir: working_dir.into(),
+            allow: vec![],
+            deny: vec![],
+            passed_env: DEFAULT_PASSED_ENV
+                .iter()
+                .map(|var| var.to_string())
+                .collect(),
+            env: BTreeMap::new(),
+            timeout: DEFAULT_TIMEOUT,
+            max_output: DEFAULT_MAX_OUTPUT,
+        }
+    }
+
+    /// Run commands in the working directory of an environment
+    pub fn from_environment(config: &EnvironmentConfig) -> Self {
+        Self::new(&config.working_dir)
+    }
+
+    /// Allow commands starting with `rule`, once a rule is allowed every other command is
+    /// refused
+    pub fn allow(mut self, rule: &str) -> Self {
+        self.allow.push(rule_tokens(rule));
+        self
+    }
+
+    /// Refuse commands starting with `rule`, denials win over the allowlist. Options placed
+    /// before the words of the rule are not skipped, so a deny rule is not a sandbox
+    pub fn deny(mut self, rule: &str) -> Self {
+        self.deny.push(rule_tokens(rule));
+        self
+    }
+
+    /// Pass an environment variable of this process on to commands
+    pub fn pass_env(mut self, name: impl Into<String>) -> Self {
+        self.passed_env.push(name.into());
+        self
+    }
+
+    /// Set an environment variable for commands
+    pub fn with_env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(name.into(), value.into());
+        self
+    }
+
+    /// Kill commands running longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Keep at most `bytes` of each of stdout and stderr, the middle of longer output is cut
+    pub fn with_max_output(mut self, bytes: usize) -> Self {
+        self.max_output = bytes;
+        self
+    }
+
+    pub fn working_dir(&self) -> &Path {
+        &self.working_dir
+    }
+
+    /// Check a command against the deny and allow rules, the program has to be a bare name
+    pub fn check(&self, program: &str, args: &[String]) -> Result<(), ShellError> {
+        if program.is_empty() || program.contains(['/', std::path::MAIN_SEPARATOR]) {
+            return Err(ShellError::ProgramPath(program.to_string()));
+        }
+        let command: Vec<&str> = std::iter::once(program)
+            .chain(args.iter().map(String::as_str))
+            .collect();
+        let line = command.join(" ");
+        let matches = |rule: &Vec<String>| {
+            rule.len() <= command.len() && rule.iter().zip(&command).all(|(a, b)| a == b)
+        };
+        if self.deny.iter().any(matches) {
+            return Err(ShellError::Denied(line));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(matches) {
+            return Err(ShellError::NotAllowed(line));
+        }
+        Ok(())
+    }
+
+    fn run(&self, args: ShellArgs) -> Result<Value, ShellError> {
+        self.check(&args.program, &args.args)?;
+        let command_line = std::iter::once(args.program.as_str())
+            .chain(args.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut command = Command::new(&args.program);
+        command
+            .args(&args.args)
+            .current_dir(&self.working_dir)
+            .env_clear()
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        for name in &self.passed_env {
+            if let Ok(value) = std::env::var(name) {
+                command.env(name, value);
+            }
+        }
+        command.envs(&self.env);
+        // Processes started by the command join its group, so a timeout kills them too
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+        let started = Instant::now();
+        let spawn_error = |source| ShellError::Spawn {
+            command: command_line.clone(),
+            source,
+        };
+        let mut child = command.spawn().map_err(spawn_error)?;
+        let stdout = Capture::start(child.stdout.take(), self.max_output);
+        let stderr = Capture::start(child.stderr.take(), self.max_output);
+
+        let mut timed_out = false;
+        let status = loop {
+            if let Some(status) = child.try_wait().map_err(spawn_error)? {
+                break Some(status);
+            }
+            if started.elapsed() >= self.timeout {
+                kill_process_group(&mut child);
+                let _ = child.wait();
+                timed_out = true;
+                break None;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+
+        let exit_code = status.and_then(|status| status.code());
+        let deadline = Instant::now() + OUTPUT_GRACE;
+        Ok(json!({
+            "command": command_line,
+            "exit_code": exit_code,
+            "success": exit_code == Some(0),
+            "timed_out": timed_out,
+            "duration_ms": started.elapsed().as_millis() as u64,
+            "stdout": stdout.finish(deadline),
+            "stderr": stderr.finish(deadline),
+        }))
+    }
+}
+
+/// Kill the command and the processes it started
+fn kill_process_group(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    {
+        // The command leads its own group, whose id is its pid
+        // SAFETY: kill has no memory effects, a negative pid names the process group
+        if unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) } == 0 {
+            return;
+        }
+    }
+    let _ = child.kill();
+}
+
+fn rule_tokens(rule: &str) -> Vec<String> {
+    rule.split_whitespace().map(String::from).collect()
+}
+
+/// Output of a stream, read on a thread until the stream closes
+struct Capture {
+    output: Arc<Mutex<TruncatedOutput>>,
+    reader: JoinHandle<()>,
+}
+
+impl Capture {
+    fn start<R: Read + Send + 'static>(stream: Option<R>, limit: usize) -> Self {
+        let output = Arc::new(Mutex::new(TruncatedOutput::new(limit)));
+        let shared = output.clone();
+        let reader = std::thread::spawn(move || {
+            let Some(mut stream) = stream else {
+                return;
+            };
+            let mut buffer = [0u8; 8192];
+            while let Ok(read) = stream.read(&mut buffer) {
+                if read == 0 {
+                    break;
+                }
+                shared.lock().unwrap().push(&buffer[..read]);
+            }
+        });
+        Self { output, reader }
+    }
+
+    /// The output once the stream closes, or what was read by `deadline`. Processes started
+    /// by the command can keep the stream open after it exits
+    fn finish(self, deadline: Instant) -> String {
+        while !self.reader.is_finished() && Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        self.output.lock().unwrap().text()
+    }
+}
+
+/// Output of a stream, the middle is dropped once it grows over the limit
+struct TruncatedOutput {
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    limit: usize,
+    total: usize,
+}
+
+impl TruncatedOutput {
+    fn new(limit: usize) -> Self {
+        Self {
+            head: Vec::new(),
+            tail: VecDeque::new(),
+            limit,
+            total: 0,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.total += bytes.len();
+        let head_limit = self.limit / 2;
+        let tail_limit = self.limit - head_limit;
+        let to_head = head_limit.saturating_sub(self.head.len()).min(bytes.len());
+        self.head.extend_from_slice(&bytes[..to_head]);
+        self.tail.extend(&bytes[to_head..]);
+        while self.tail.len() > tail_limit {
+            self.tail.pop_front();
+        }
+    }
+
+    fn text(&self) -> String {
+        let head = String::from_utf8_lossy(&self.head);
+        let tail = String::from_utf8_lossy(self.tail.as_slices().0).into_owned()
+            + &String::from_utf8_lossy(self.tail.as_slices().1);
+        let dropped = self.total - self.head.len() - self.tail.len();
+        if dropped == 0 {
+            return format!("{head}{tail}");
+        }
+        format!("{head}\n... [{dropped} bytes truncated] ...\n{tail}")
+    }
+}
+
+impl ToolRuntime for ShellTool {
+    fn execute(&self, args: Value) -> Result<Value, ToolCallError> {
+        let args: ShellArgs = serde_json::from_value(args)?;
+        Ok(self.run(args)?)
+    }
+}
+
+#[async_trait]
+impl ToolT for ShellTool {
+    fn name(&self) -> &'static str {
+        "shell"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run a program with arguments in the working directory, such as a build or the tests. \
+         Returns the exit code, stdout and stderr. Commands run without a shell, so pipes, \
+         redirections and globs are not expanded."
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::ProcessExec]
+    }
+
+    fn args_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "program": {"type": "string", "description": "Program to run, such as 'cargo'"},
+                "args": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Arguments of the program, such as ['test', '--workspace']"
+                }
+            },
+            "required": ["program"]
+        })
+    }
+
+    async fn call(&self, args: Value, _context: ToolCallContext) -> Result<Value, ToolCallError> {
+        // Commands block while they run, keep them off the async workers
+        let tool = self.clone();
+        tokio::task::spawn_blocking(move || tool.execute(args))
+            .await
+            .map_err(|e| ToolCallError::RuntimeError(Box::new(e)))?
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn shell(dir: &Path) -> ShellTool {
+        ShellTool::new(dir)
+    }
+
+    /// Whether a process is alive, a killed process waiting to be reaped is not
+    fn running(pid: libc::pid_t) -> bool {
+        // SAFETY: signal 0 only checks the process exists
+        let exists = unsafe { libc::kill(pid, 0) } == 0;
+        let zombie = std::fs::read_to_string(format!("/proc/{pid}/stat"))
+            .map(|stat| stat.contains(") Z "))
+            .unwrap_or(false);
+        exists && !zombie
+    }
+
+    #[test]
+    fn test_runs_command_and_reports_exit_code() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("hello.txt"), "hello").unwrap();
+        let result = shell(dir.path())
+            .execute(json!({"program": "cat", "args": ["hello.txt"]}))
+            .unwrap();
+        assert_eq!(result["exit_code"], 0);
+        assert_eq!(result["success"], true);
+        assert_eq!(result["stdout"], "hello");
+
+        let result = shell(dir.path())
+            .execute(json!({"program": "sh", "args": ["-c", "echo oops >&2; exit 3"]}))
+            .unwrap();
+        assert_eq!(result["exit_code"], 3);
+        assert_eq!(result["success"], false);
+        assert_eq!(result["stderr"], "oops\n");
+    }
+
+    #[test]
+    fn test_allow_and_deny_rules() {
+        let tool = ShellTool::new(".")
+            .allow("git")
+            .allow("cargo")
+            .deny("git push");
+        assert!(tool.check("git", &["status".to_string()]).is_ok());
+        assert!(tool.check("cargo", &["test".to_string()]).is_ok());
+        assert!(matches!(
+            tool.check("git", &["push".to_string(), "origin".to_string()]),
+            Err(ShellError::Denied(command)) if command == "git push origin"
+        ));
+        assert!(matches!(
+            tool.check("rm", &["-rf".to_string()]),
+            Err(ShellError::NotAllowed(_))
+        ));
+        let error = tool.execute(json!({"program": "rm"})).unwrap_err();
+        assert!(error.to_string().contains("not in the allowlist"));
+    }
+
+    #[test]
+    fn test_programs_must_be_names() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("evil")).unwrap();
+        std::fs::write(dir.path().join("evil/git"), "#!/bin/sh\necho pwned\n").unwrap();
+        let tool = ShellTool::new(dir.path()).allow("git");
+        for program in ["evil/git", "./evil/git", "/usr/bin/git", ""] {
+            assert!(
+                matches!(
+                    tool.check(program, &[]),
+                    Err(ShellError::ProgramPath(path)) if path == program
+                ),
+                "{program}"
+            );
+        }
+        let error = tool.execute(json!({"program": "./evil/git"})).unwrap_err();
+        assert!(error.to_string().contains("not a path"));
+    }
+
+    #[test]
+    fn test_environment_is_scrubbed() {
+        // Cargo sets this for the test process
+        assert!(std::env::var("CARGO_PKG_NAME").is_ok());
+        let result = shell(Path::new("."))
+            .with_env("GREETING", "hi")
+            .execute(json!({"program": "env"}))
+            .unwrap();
+        let stdout = result["stdout"].as_str().unwrap();
+        assert!(stdout.contains("GREETING=hi"));
+        assert!(!stdout.contains("CARGO_PKG_NAME"));
+
+        let result = shell(Path::new("."))
+            .pass_env("CARGO_PKG_NAME")
+            .execute(json!({"program": "env"}))
+            .unwrap();
+        assert!(result["stdout"]
+            .as_str()
+            .unwrap()
+            .contains("CARGO_PKG_NAME=autoagents-core"));
+    }
+
+    #[test]
+    fn test_timeout_and_truncation() {
+        let result = shell(Path::new("."))
+            .with_timeout(Duration::from_millis(100))
+            .execute(json!({"program": "sleep", "args": ["5"]}))
+            .unwrap();
+        assert_eq!(result["timed_out"], true);
+        assert_eq!(result["exit_code"], Value::Null);
+        assert!(result["duration_ms"].as_u64().unwrap() < 5000);
+
+        // A process left behind by the command does not hold the result back
+        let result = shell(Path::new("."))
+            .with_timeout(Duration::from_millis(100))
+            .execute(json!({"program": "sh", "args": ["-c", "echo started; sleep 5"]}))
+            .unwrap();
+        assert_eq!(result["timed_out"], true);
+        assert_eq!(result["stdout"], "started\n");
+
+        // Processes started by the command are killed with it
+        let result = shell(Path::new("."))
+            .with_timeout(Duration::from_millis(200))
+            .execute(json!({"program": "sh", "args": ["-c", "sleep 30 & echo $!; wait"]}))
+            .unwrap();
+        assert_eq!(result["timed_out"], true);
+        let pid: libc::pid_t = result["stdout"].as_str().unwrap().trim().parse().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while running(pid) && Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        assert!(!running(pid));
+
+        let result = shell(Path::new("."))
+            .with_max_output(8)
+            .execute(json!({"program": "printf", "args": ["0123456789abcdef"]}))
+            .unwrap();
+        assert_eq!(result["stdout"], "0123\n... [8 bytes truncated] ...\ncdef");
+    }
+}
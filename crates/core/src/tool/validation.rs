@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A single way in which tool arguments fail to match the tool's `args_schema`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArgumentViolation {
+    /// JSON pointer to the offending value inside the arguments ("" for the root)
+    pub path: String,
+    /// JSON pointer to the schema keyword that rejected the value
+    pub schema_path: String,
+    /// Human readable description of the violation
+    pub message: String,
+}
+
+/// Error returned when tool arguments do not satisfy the tool's `args_schema`
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ArgumentsValidationError {
+    /// The arguments violate one or more schema constraints
+    #[error("Arguments do not match the tool schema: {}", format_violations(.0))]
+    Invalid(Vec<ArgumentViolation>),
+
+    /// The tool's `args_schema` is not a valid JSON Schema
+    #[error("Invalid tool schema: {0}")]
+    InvalidSchema(String),
+}
+
+impl ArgumentsValidationError {
+    /// The violations found, empty if the schema itself could not be compiled
+    pub fn violations(&self) -> &[ArgumentViolation] {
+        match self {
+            Self::Invalid(violations) => violations,
+            Self::InvalidSchema(_) => &[],
+        }
+    }
+
+    /// Machine readable representation returned to the model so it can correct the call
+    pub fn to_json(&self, tool_name: &str) -> Value {
+        match self {
+            Self::Invalid(violations) => serde_json::json!({
+                "error": format!("Invalid arguments for tool '{tool_name}'"),
+                "violations": violations,
+            }),
+            Self::InvalidSchema(message) => serde_json::json!({
+                "error": format!("Invalid schema for tool '{tool_name}': {message}"),
+            }),
+        }
+    }
+}
+
+fn format_violations(violations: &[ArgumentViolation]) -> String {
+    violations
+        .iter()
+        .map(|v| {
+            if v.path.is_empty() {
+                v.message.clone()
+            } else {
+                format!("{}: {}", v.path, v.message)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Validate `args` against a JSON Schema, collecting every violation
+pub fn validate_arguments(schema: &Value, args: &Value) -> Result<(), ArgumentsValidationError> {
+    let validator = jsonschema::validator_for(schema)
+        .map_err(|e| ArgumentsValidationError::InvalidSchema(e.to_string()))?;
+
+    let violations: Vec<ArgumentViolation> = validator
+        .iter_errors(args)
+        .map(|e| ArgumentViolation {
+            path: e.instance_path.as_str().to_string(),
+            schema_path: e.schema_path.as_str().to_string(),
+            message: e.to_string(),
+        })
+        .collect();
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(ArgumentsValidationError::Invalid(violations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "mode": {"type": "string", "enum": ["fast", "slow"]},
+                "options": {
+                    "type": "object",
+                    "properties": {"depth": {"type": "integer"}},
+                    "required": ["depth"]
+                },
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["name"]
+        })
+    }
+
+    #[test]
+    fn test_valid_arguments() {
+        let args = json!({
+            "name": "test",
+            "mode": "fast",
+            "options": {"depth": 2},
+            "tags": ["a", "b"]
+        });
+        assert!(validate_arguments(&schema(), &args).is_ok());
+    }
+
+    #[test]
+    fn test_missing_required() {
+        let err = validate_arguments(&schema(), &json!({})).unwrap_err();
+        let violations = err.violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "");
+        assert!(violations[0].schema_path.ends_with("required"));
+        assert!(violations[0].message.contains("name"));
+    }
+
+    #[test]
+    fn test_wrong_type_and_enum() {
+        let args = json!({"name": 42, "mode": "medium"});
+        let err = validate_arguments(&schema(), &args).unwrap_err();
+        let paths: Vec<&str> = err.violations().iter().map(|v| v.path.as_str()).collect();
+        assert!(paths.contains(&"/name"));
+        assert!(paths.contains(&"/mode"));
+    }
+
+    #[test]
+    fn test_nested_object_and_array() {
+        let args = json!({
+            "name": "test",
+            "options": {"depth": "deep"},
+            "tags": ["a", 1]
+        });
+        let err = validate_arguments(&schema(), &args).unwrap_err();
+        let paths: Vec<&str> = err.violations().iter().map(|v| v.path.as_str()).collect();
+        assert!(paths.contains(&"/options/depth"));
+        assert!(paths.contains(&"/tags/1"));
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        let err = validate_arguments(&json!({"type": "bool"}), &json!(true)).unwrap_err();
+        assert!(matches!(err, ArgumentsValidationError::InvalidSchema(_)));
+        assert!(err.violations().is_empty());
+    }
+
+    #[test]
+    fn test_error_json() {
+        let err = validate_arguments(&schema(), &json!({"name": 1})).unwrap_err();
+        let value = err.to_json("my_tool");
+        assert_eq!(value["error"], "Invalid arguments for tool 'my_tool'");
+        assert_eq!(value["violations"][0]["path"], "/name");
+        assert!(err.to_string().contains("/name"));
+    }
+}
@@ -0,0 +1,669 @@
+use crate::error::Error;
+use crate::protocol::{AgentID, Event, TaskResult, WorkflowRunId};
+use crate::runtime::{Runtime, RuntimeError, Task};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+type EdgeCondition = Arc<dyn Fn(&Value) -> bool + Send + Sync>;
+
+/// Error types for workflow construction and execution
+#[derive(Debug, thiserror::Error)]
+pub enum WorkflowError {
+    #[error("Workflow has no nodes")]
+    Empty,
+
+    #[error("Duplicate workflow node: {0}")]
+    DuplicateNode(String),
+
+    #[error("Unknown workflow node: {0}")]
+    UnknownNode(String),
+
+    #[error("Workflow contains a cycle through: {}", .0.join(", "))]
+    Cycle(Vec<String>),
+
+    #[error("Workflow node '{node}' failed: {reason}")]
+    NodeFailed { node: String, reason: String },
+}
+
+/// Execution status of a workflow node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeStatus {
+    Pending,
+    Running,
+    Completed,
+    Skipped,
+    Failed,
+}
+
+/// A node of the workflow, running a task on an agent registered on the runtime
+#[derive(Debug, Clone)]
+pub struct WorkflowNode {
+    pub name: String,
+    pub agent_id: AgentID,
+}
+
+struct Edge {
+    from: usize,
+    to: usize,
+    condition: Option<EdgeCondition>,
+}
+
+/// Builder for a [`Workflow`]
+///
+/// Nodes without incoming edges receive the workflow input. A node runs once all its
+/// predecessors have finished and at least one of its incoming edges was taken; its input
+/// is the output of the predecessor, or a JSON object keyed by node name when several
+/// predecessors fan in. Nodes whose incoming edges are all skipped are skipped as well.
+pub struct WorkflowBuilder {
+    name: String,
+    nodes: Vec<WorkflowNode>,
+    edges: Vec<(String, String, Option<EdgeCondition>)>,
+}
+
+impl WorkflowBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Add a node running on the given agent
+    pub fn node(mut self, name: impl Into<String>, agent_id: AgentID) -> Self {
+        self.nodes.push(WorkflowNode {
+            name: name.into(),
+            agent_id,
+        });
+        self
+    }
+
+    /// Run `to` after `from`
+    pub fn edge(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.edges.push((from.into(), to.into(), None));
+        self
+    }
+
+    /// Run `to` after `from` only if `condition` holds for the output of `from`
+    pub fn edge_if<F>(
+        mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        condition: F,
+    ) -> Self
+    where
+        F: Fn(&Value) -> bool + Send + Sync + 'static,
+    {
+        self.edges
+            .push((from.into(), to.into(), Some(Arc::new(condition))));
+        self
+    }
+
+    /// Run the nodes one after another
+    pub fn sequence<S: AsRef<str>>(mut self, nodes: &[S]) -> Self {
+        for pair in nodes.windows(2) {
+            self = self.edge(pair[0].as_ref(), pair[1].as_ref());
+        }
+        self
+    }
+
+    pub fn build(self) -> Result<Workflow, WorkflowError> {
+        if self.nodes.is_empty() {
+            return Err(WorkflowError::Empty);
+        }
+
+        let mut index = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if index.insert(node.name.clone(), i).is_some() {
+                return Err(WorkflowError::DuplicateNode(node.name.clone()));
+            }
+        }
+
+        let mut edges = Vec::with_capacity(self.edges.len());
+        for (from, to, condition) in self.edges {
+            let from = *index.get(&from).ok_or(WorkflowError::UnknownNode(from))?;
+            let to = *index.get(&to).ok_or(WorkflowError::UnknownNode(to))?;
+            edges.push(Edge {
+                from,
+                to,
+                condition,
+            });
+        }
+
+        let mut incoming = vec![Vec::new(); self.nodes.len()];
+        let mut outgoing = vec![Vec::new(); self.nodes.len()];
+        for (i, edge) in edges.iter().enumerate() {
+            outgoing[edge.from].push(i);
+            incoming[edge.to].push(i);
+        }
+
+        let workflow = Workflow {
+            name: self.name,
+            nodes: self.nodes,
+            edges,
+            incoming,
+            outgoing,
+        };
+        workflow.check_acyclic()?;
+        Ok(workflow)
+    }
+}
+
+/// Final state of a workflow run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowOutput {
+    pub workflow_id: WorkflowRunId,
+    /// Output of every completed node
+    pub outputs: HashMap<String, Value>,
+    /// Status of every node
+    pub statuses: HashMap<String, NodeStatus>,
+}
+
+impl WorkflowOutput {
+    pub fn output(&self, node: &str) -> Option<&Value> {
+        self.outputs.get(node)
+    }
+
+    pub fn status(&self, node: &str) -> Option<NodeStatus> {
+        self.statuses.get(node).copied()
+    }
+}
+
+/// A DAG of agent tasks executed on a [`Runtime`]
+pub struct Workflow {
+    name: String,
+    nodes: Vec<WorkflowNode>,
+    edges: Vec<Edge>,
+    incoming: Vec<Vec<usize>>,
+    outgoing: Vec<Vec<usize>>,
+}
+
+impl Debug for Workflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let edges: Vec<(&str, &str, bool)> = self
+            .edges
+            .iter()
+            .map(|e| {
+                (
+                    self.nodes[e.from].name.as_str(),
+                    self.nodes[e.to].name.as_str(),
+                    e.condition.is_some(),
+                )
+            })
+            .collect();
+        f.debug_struct("Workflow")
+            .field("name", &self.name)
+            .field("nodes", &self.nodes)
+            .field("edges", &edges)
+            .finish()
+    }
+}
+
+impl Workflow {
+    pub fn builder(name: impl Into<String>) -> WorkflowBuilder {
+        WorkflowBuilder::new(name)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn nodes(&self) -> &[WorkflowNode] {
+        &self.nodes
+    }
+
+    fn check_acyclic(&self) -> Result<(), WorkflowError> {
+        let mut in_degree: Vec<usize> = self.incoming.iter().map(Vec::len).collect();
+        let mut queue: VecDeque<usize> = (0..self.nodes.len())
+            .filter(|i| in_degree[*i] == 0)
+            .collect();
+        let mut visited = 0;
+        while let Some(node) = queue.pop_front() {
+            visited += 1;
+            for edge in &self.outgoing[node] {
+                let to = self.edges[*edge].to;
+                in_degree[to] -= 1;
+                if in_degree[to] == 0 {
+                    queue.push_back(to);
+                }
+            }
+        }
+
+        if visited == self.nodes.len() {
+            Ok(())
+        } else {
+            Err(WorkflowError::Cycle(
+                (0..self.nodes.len())
+                    .filter(|i| in_degree[*i] > 0)
+                    .map(|i| self.nodes[i].name.clone())
+                    .collect(),
+            ))
+        }
+    }
+
+    fn node_input(
+        &self,
+        node: usize,
+        input: &str,
+        taken: &[Option<bool>],
+        outputs: &[Option<Value>],
+    ) -> String {
+        let sources: Vec<usize> = self.incoming[node]
+            .iter()
+            .filter(|e| taken[**e] == Some(true))
+            .map(|e| self.edges[*e].from)
+            .collect();
+        match sources.as_slice() {
+            [] => input.to_string(),
+            [source] => match &outputs[*source] {
+                Some(Value::String(text)) => text.clone(),
+                Some(value) => value.to_string(),
+                None => String::new(),
+            },
+            sources => {
+                let merged: serde_json::Map<String, Value> = sources
+                    .iter()
+                    .map(|s| {
+                        (
+                            self.nodes[*s].name.clone(),
+                            outputs[*s].clone().unwrap_or(Value::Null),
+                        )
+                    })
+                    .collect();
+                Value::Object(merged).to_string()
+            }
+        }
+    }
+
+    fn output(
+        &self,
+        workflow_id: WorkflowRunId,
+        statuses: &[NodeStatus],
+        outputs: Vec<Option<Value>>,
+    ) -> WorkflowOutput {
+        WorkflowOutput {
+            workflow_id,
+            outputs: self
+                .nodes
+                .iter()
+                .zip(outputs)
+                .filter_map(|(node, output)| output.map(|o| (node.name.clone(), o)))
+                .collect(),
+            statuses: self
+                .nodes
+                .iter()
+                .zip(statuses)
+                .map(|(node, status)| (node.name.clone(), *status))
+                .collect(),
+        }
+    }
+
+    /// Execute the workflow on the runtime, running independent nodes in parallel
+    pub async fn run(
+        &self,
+        runtime: Arc<dyn Runtime>,
+        input: impl Into<String>,
+    ) -> Result<WorkflowOutput, Error> {
+        let input = input.into();
+        let workflow_id = Uuid::new_v4();
+        let tx_event = runtime.event_sender();
+        send(
+            &tx_event,
+            Event::WorkflowStarted {
+                workflow_id,
+                name: self.name.clone(),
+            },
+        )
+        .await?;
+
+        // None while the source of an edge has not finished, then whether it was taken
+        let mut taken: Vec<Option<bool>> = vec![None; self.edges.len()];
+        let mut statuses = vec![NodeStatus::Pending; self.nodes.len()];
+        let mut outputs: Vec<Option<Value>> = vec![None; self.nodes.len()];
+        let mut running = JoinSet::new();
+
+        loop {
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for (i, node) in self.nodes.iter().enumerate() {
+                    if statuses[i] != NodeStatus::Pending
+                        || self.incoming[i].iter().any(|e| taken[*e].is_none())
+                    {
+                        continue;
+                    }
+
+                    if !self.incoming[i].is_empty()
+                        && !self.incoming[i].iter().any(|e| taken[*e] == Some(true))
+                    {
+                        statuses[i] = NodeStatus::Skipped;
+                        for edge in &self.outgoing[i] {
+                            taken[*edge] = Some(false);
+                        }
+                        changed = true;
+                        send(
+                            &tx_event,
+                            Event::WorkflowNodeSkipped {
+                                workflow_id,
+                                node: node.name.clone(),
+                            },
+                        )
+                        .await?;
+                        continue;
+                    }
+
+                    let task = Task::new(
+                        self.node_input(i, &input, &taken, &outputs),
+                        Some(node.agent_id),
+                    );
+                    statuses[i] = NodeStatus::Running;
+                    send(
+                        &tx_event,
+                        Event::WorkflowNodeStarted {
+                            workflow_id,
+                            node: node.name.clone(),
+                            agent_id: node.agent_id,
+                            sub_id: task.submission_id,
+                        },
+                    )
+                    .await?;
+
+                    let runtime = runtime.clone();
+                    let agent_id = node.agent_id;
+                    running.spawn(async move {
+                        let sub_id = task.submission_id;
+                        (i, sub_id, runtime.execute_task(agent_id, task).await)
+                    });
+                }
+            }
+
+            let Some(joined) = running.join_next().await else {
+                break;
+            };
+            let (i, sub_id, result) = joined.map_err(RuntimeError::TaskJoinError)?;
+            let node = &self.nodes[i];
+            let result = match result {
+                Ok(result) => result,
+                Err(e) => TaskResult::Failure(e.to_string()),
+            };
+            send(
+                &tx_event,
+                Event::WorkflowNodeCompleted {
+                    workflow_id,
+                    node: node.name.clone(),
+                    sub_id,
+                    result: result.clone(),
+                },
+            )
+            .await?;
+
+            match result {
+                TaskResult::Value(value) => {
+                    statuses[i] = NodeStatus::Completed;
+                    for edge in &self.outgoing[i] {
+                        let condition = &self.edges[*edge].condition;
+                        taken[*edge] = Some(condition.as_ref().is_none_or(|c| c(&value)));
+                    }
+                    outputs[i] = Some(value);
+                }
+                failure => {
+                    statuses[i] = NodeStatus::Failed;
+                    running.abort_all();
+                    let reason = match failure {
+                        TaskResult::Failure(reason) => reason,
+                        _ => "Task was aborted".to_string(),
+                    };
+                    send(
+                        &tx_event,
+                        Event::WorkflowCompleted {
+                            workflow_id,
+                            result: TaskResult::Failure(reason.clone()),
+                        },
+                    )
+                    .await?;
+                    return Err(WorkflowError::NodeFailed {
+                        node: node.name.clone(),
+                        reason,
+                    }
+                    .into());
+                }
+            }
+        }
+
+        let output = self.output(workflow_id, &statuses, outputs);
+        send(
+            &tx_event,
+            Event::WorkflowCompleted {
+                workflow_id,
+                result: TaskResult::Value(
+                    serde_json::to_value(&output.outputs).unwrap_or_default(),
+                ),
+            },
+        )
+        .await?;
+        Ok(output)
+    }
+}
+
+async fn send(tx_event: &mpsc::Sender<Event>, event: Event) -> Result<(), Error> {
+    tx_event
+        .send(event)
+        .await
+        .map_err(|e| RuntimeError::EventError(e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::RunnableAgent;
+    use crate::memory::MemoryProvider;
+    use crate::runtime::SingleThreadedRuntime;
+    use async_trait::async_trait;
+    use tokio::sync::RwLock;
+    use tokio_stream::StreamExt;
+
+    #[derive(Debug)]
+    struct AppendAgent {
+        id: AgentID,
+        suffix: &'static str,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl RunnableAgent for AppendAgent {
+        fn name(&self) -> &'static str {
+            "append"
+        }
+
+        fn description(&self) -> &'static str {
+            "Appends a suffix to the task"
+        }
+
+        fn id(&self) -> AgentID {
+            self.id
+        }
+
+        fn memory(&self) -> Option<Arc<RwLock<Box<dyn MemoryProvider>>>> {
+            None
+        }
+
+        async fn run(self: Arc<Self>, task: Task, tx: mpsc::Sender<Event>) -> Result<(), Error> {
+            let result = if self.fail {
+                TaskResult::Failure("append failed".to_string())
+            } else {
+                TaskResult::Value(Value::String(format!("{}{}", task.prompt, self.suffix)))
+            };
+            let _ = tx
+                .send(Event::TaskComplete {
+                    sub_id: task.submission_id,
+                    result,
+                })
+                .await;
+            Ok(())
+        }
+    }
+
+    async fn runtime_with(
+        agents: &[(&'static str, bool)],
+    ) -> (Arc<SingleThreadedRuntime>, Vec<AgentID>) {
+        let runtime = SingleThreadedRuntime::new(None);
+        let mut ids = vec![];
+        for (suffix, fail) in agents {
+            let agent = Arc::new(AppendAgent {
+                id: Uuid::new_v4(),
+                suffix,
+                fail: *fail,
+            });
+            ids.push(agent.id);
+            runtime.register_agent(agent).await.unwrap();
+        }
+        let background = runtime.clone();
+        tokio::spawn(async move { background.run().await });
+        (runtime, ids)
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let a = Uuid::new_v4();
+        assert!(matches!(
+            WorkflowBuilder::new("empty").build(),
+            Err(WorkflowError::Empty)
+        ));
+        assert!(matches!(
+            Workflow::builder("dup").node("a", a).node("a", a).build(),
+            Err(WorkflowError::DuplicateNode(_))
+        ));
+        assert!(matches!(
+            Workflow::builder("unknown").node("a", a).edge("a", "b").build(),
+            Err(WorkflowError::UnknownNode(name)) if name == "b"
+        ));
+        match Workflow::builder("cycle")
+            .node("a", a)
+            .node("b", a)
+            .node("c", a)
+            .sequence(&["a", "b", "c", "b"])
+            .build()
+        {
+            Err(WorkflowError::Cycle(nodes)) => assert_eq!(nodes, vec!["b", "c"]),
+            other => panic!("Expected cycle, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sequential_workflow() {
+        let (runtime, ids) = runtime_with(&[("-a", false), ("-b", false)]).await;
+        let mut events = runtime.take_event_receiver().await.unwrap();
+        let workflow = Workflow::builder("seq")
+            .node("first", ids[0])
+            .node("second", ids[1])
+            .sequence(&["first", "second"])
+            .build()
+            .unwrap();
+
+        let output = workflow.run(runtime.clone(), "start").await.unwrap();
+        assert_eq!(output.output("second").unwrap(), "start-a-b");
+        assert_eq!(output.status("first"), Some(NodeStatus::Completed));
+
+        let mut seen = vec![];
+        while let Some(event) = events.next().await {
+            let done = matches!(event, Event::WorkflowCompleted { .. });
+            seen.push(event);
+            if done {
+                break;
+            }
+        }
+        assert!(matches!(seen[0], Event::WorkflowStarted { .. }));
+        assert!(seen
+            .iter()
+            .any(|e| matches!(e, Event::WorkflowNodeStarted { node, .. } if node == "second")));
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_and_fan_in() {
+        let (runtime, ids) =
+            runtime_with(&[("-a", false), ("-b", false), ("-c", false), ("-d", false)]).await;
+        let workflow = Workflow::builder("diamond")
+            .node("a", ids[0])
+            .node("b", ids[1])
+            .node("c", ids[2])
+            .node("d", ids[3])
+            .edge("a", "b")
+            .edge("a", "c")
+            .edge("b", "d")
+            .edge("c", "d")
+            .build()
+            .unwrap();
+
+        let output = workflow.run(runtime.clone(), "x").await.unwrap();
+        let merged: Value = serde_json::from_str(
+            output
+                .output("d")
+                .unwrap()
+                .as_str()
+                .unwrap()
+                .trim_end_matches("-d"),
+        )
+        .unwrap();
+        assert_eq!(merged["b"], "x-a-b");
+        assert_eq!(merged["c"], "x-a-c");
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_conditional_edges() {
+        let (runtime, ids) = runtime_with(&[
+            ("-a", false),
+            ("-yes", false),
+            ("-no", false),
+            ("-end", false),
+        ])
+        .await;
+        let workflow = Workflow::builder("branch")
+            .node("a", ids[0])
+            .node("yes", ids[1])
+            .node("no", ids[2])
+            .node("after_no", ids[3])
+            .edge_if("a", "yes", |v| {
+                v.as_str().is_some_and(|s| s.starts_with("go"))
+            })
+            .edge_if("a", "no", |v| {
+                !v.as_str().is_some_and(|s| s.starts_with("go"))
+            })
+            .edge("no", "after_no")
+            .build()
+            .unwrap();
+
+        let output = workflow.run(runtime.clone(), "go").await.unwrap();
+        assert_eq!(output.output("yes").unwrap(), "go-a-yes");
+        assert_eq!(output.status("no"), Some(NodeStatus::Skipped));
+        assert_eq!(output.status("after_no"), Some(NodeStatus::Skipped));
+        runtime.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_node_failure() {
+        let (runtime, ids) = runtime_with(&[("-a", true), ("-b", false)]).await;
+        let workflow = Workflow::builder("failing")
+            .node("a", ids[0])
+            .node("b", ids[1])
+            .edge("a", "b")
+            .build()
+            .unwrap();
+
+        match workflow.run(runtime.clone(), "x").await {
+            Err(Error::WorkflowError(WorkflowError::NodeFailed { node, reason })) => {
+                assert_eq!(node, "a");
+                assert_eq!(reason, "append failed");
+            }
+            other => panic!("Expected node failure, got {other:?}"),
+        }
+        runtime.stop().await.unwrap();
+    }
+}
@@ -13,6 +13,7 @@ pub(crate) struct AgentAttributes {
     pub(crate) description: LitStr,
     pub(crate) tools: Option<Vec<Ident>>,
     pub(crate) output: Option<Type>,
+    pub(crate) system_prompt: Option<LitStr>,
 }
 
 #[derive(EnumString, Display)]
@@ -25,6 +26,8 @@ pub(crate) enum AgentAttributeKeys {
     Tools,
     #[strum(serialize = "output")]
     Output,
+    #[strum(serialize = "system_prompt")]
+    SystemPrompt,
     Unknown(String),
 }
 
@@ -35,6 +38,7 @@ impl From<Ident> for AgentAttributeKeys {
             "description" => Self::Description,
             "tools" => Self::Tools,
             "output" => Self::Output,
+            "system_prompt" => Self::SystemPrompt,
             other => Self::Unknown(other.to_string()),
         }
     }
@@ -46,6 +50,7 @@ impl Parse for AgentAttributes {
         let mut description = None;
         let mut tools = None;
         let mut output = None;
+        let mut system_prompt = None;
 
         while !input.is_empty() {
             let key: Ident = input.parse()?;
@@ -64,6 +69,9 @@ impl Parse for AgentAttributes {
                 AgentAttributeKeys::Output => {
                     output = Some(input.parse::<Type>()?);
                 }
+                AgentAttributeKeys::SystemPrompt => {
+                    system_prompt = Some(input.parse::<LitStr>()?);
+                }
                 AgentAttributeKeys::Tools => {
                     // Parse a bracketed list of identifiers
                     let content;
@@ -98,6 +106,7 @@ impl Parse for AgentAttributes {
             })?,
             output,
             tools,
+            system_prompt,
         })
     }
 }
@@ -115,6 +124,14 @@ impl AgentParser {
         let tool_idents = agent_attrs.tools.unwrap_or_default();
         let output_type = agent_attrs.output;
 
+        let system_prompt_impl = agent_attrs.system_prompt.map(|template| {
+            quote! {
+                fn system_prompt(&self) -> Option<&'static str> {
+                    Some(#template)
+                }
+            }
+        });
+
         let quoted_output_type = match &output_type {
             Some(output_ty) => quote! { #output_ty },
             None => quote! { String },
@@ -160,6 +177,8 @@ impl AgentParser {
                         ),*
                     ]
                 }
+
+                #system_prompt_impl
             }
 
             impl std::fmt::Debug for #struct_name {
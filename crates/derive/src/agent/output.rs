@@ -21,7 +21,7 @@ pub(crate) struct OutputSchemaProperty {
     _type: String,
     description: Option<String>,
     #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
-    _enum: Option<Vec<String>>,
+    _enum: Option<Vec<serde_json::Value>>,
 }
 
 #[derive(Debug, Serialize, Default)]
@@ -176,7 +176,7 @@ impl OutputParser {
                 description: schema.description.map(|lit| lit.value()),
                 _enum: schema
                     .choice
-                    .map(|choices| choices.iter().map(|choice| choice.to_string()).collect()),
+                    .map(|choices| choices.iter().map(|choice| choice.to_json()).collect()),
             })
         } else {
             // Default property without attributes
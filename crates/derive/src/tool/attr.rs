@@ -1,6 +1,8 @@
 use strum::{Display, EnumString};
 use syn::{
+    bracketed,
     parse::{Parse, ParseStream},
+    punctuated::Punctuated,
     Ident, LitStr, Result, Token, Type,
 };
 
@@ -8,6 +10,8 @@ pub(crate) struct ToolAttributes {
     pub(crate) name: LitStr,
     pub(crate) description: LitStr,
     pub(crate) input: Type,
+    /// Variants of `Capability` the tool needs, none if the attribute is left out
+    pub(crate) capabilities: Vec<Ident>,
 }
 
 #[derive(EnumString, Display)]
@@ -18,6 +22,8 @@ pub(crate) enum ToolAttributeKeys {
     Description,
     #[strum(serialize = "input")]
     Input,
+    #[strum(serialize = "capabilities")]
+    Capabilities,
     Unknown(String),
 }
 
@@ -27,6 +33,7 @@ impl From<Ident> for ToolAttributeKeys {
             "name" => Self::Name,
             "description" => Self::Description,
             "input" => Self::Input,
+            "capabilities" => Self::Capabilities,
             other => Self::Unknown(other.to_string()),
         }
     }
@@ -37,6 +44,7 @@ impl Parse for ToolAttributes {
         let mut name = None;
         let mut description = None;
         let mut args = None;
+        let mut capabilities = Vec::new();
         while !input.is_empty() {
             let key: Ident = input.parse()?;
             let key_span = key.span();
@@ -54,6 +62,13 @@ impl Parse for ToolAttributes {
                 ToolAttributeKeys::Input => {
                     args = Some(input.parse::<Type>()?);
                 }
+                ToolAttributeKeys::Capabilities => {
+                    let content;
+                    bracketed!(content in input);
+                    capabilities = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?
+                        .into_iter()
+                        .collect();
+                }
                 ToolAttributeKeys::Unknown(other) => {
                     return Err(syn::Error::new(
                         key_span,
@@ -84,6 +99,7 @@ impl Parse for ToolAttributes {
                     format!("Missing attribute: {}", ToolAttributeKeys::Input),
                 )
             })?,
+            capabilities,
         })
     }
 }
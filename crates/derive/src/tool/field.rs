@@ -10,12 +10,15 @@ pub(crate) enum Choice {
     Number(LitInt),
 }
 
-#[allow(clippy::inherent_to_string)]
 impl Choice {
-    pub(crate) fn to_string(&self) -> String {
+    /// The choice as a JSON value, keeping numeric choices numeric
+    pub(crate) fn to_json(&self) -> serde_json::Value {
         match self {
-            Self::Number(n) => n.to_string(),
-            Self::String(s) => s.value(),
+            Self::Number(n) => n
+                .base10_parse::<i64>()
+                .map(serde_json::Value::from)
+                .unwrap_or_else(|_| n.to_string().into()),
+            Self::String(s) => s.value().into(),
         }
     }
 }
@@ -24,7 +24,7 @@ pub(crate) struct InputToolProperty {
     #[serde(rename = "type")]
     _type: String,
     #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
-    _enum: Option<Vec<String>>,
+    _enum: Option<Vec<serde_json::Value>>,
 }
 
 #[derive(Debug, Serialize, Default)]
@@ -141,7 +141,7 @@ impl InputParser {
                     .map_or_else(|| None, |f| Some(f.value())),
                 _enum: property.choice.map_or_else(
                     || None,
-                    |f| Some(f.iter().map(|f| f.to_string()).collect::<Vec<String>>()),
+                    |f| Some(f.iter().map(|f| f.to_json()).collect::<Vec<_>>()),
                 ),
                 _type: json_type.to_string(),
             })
@@ -20,6 +20,15 @@ impl ToolParser {
         let tool_name_literal = tool_attrs.name.clone();
         let tool_description = tool_attrs.description;
         let args_type = tool_attrs.input;
+        // Tools without the attribute keep the default of needing no capability
+        let capabilities = (!tool_attrs.capabilities.is_empty()).then(|| {
+            let capabilities = tool_attrs.capabilities;
+            quote! {
+                fn capabilities(&self) -> Vec<Capability> {
+                    vec![#(Capability::#capabilities),*]
+                }
+            }
+        });
 
         let expanded = quote! {
             #input_struct
@@ -40,6 +49,7 @@ impl ToolParser {
                     serde_json::from_str(params_str)
                         .expect("Failed to parse parameters schema")
                 }
+                #capabilities
             }
 
             impl std::fmt::Debug for #struct_name {
@@ -6,7 +6,7 @@ use crate::{
     builder::{LLMBackend, LLMBuilder},
     chat::{
         ChatMessage, ChatProvider, ChatResponse, ChatRole, MessageType, StructuredOutputFormat,
-        Tool, ToolChoice,
+        Tool, ToolChoice, Usage,
     },
     completion::{CompletionProvider, CompletionRequest, CompletionResponse},
     embedding::EmbeddingProvider,
@@ -134,6 +134,14 @@ struct ImageSource<'a> {
 #[derive(Deserialize, Debug)]
 struct AnthropicCompleteResponse {
     content: Vec<AnthropicContent>,
+    usage: Option<AnthropicUsage>,
+}
+
+/// Token usage within an Anthropic API response.
+#[derive(Deserialize, Debug)]
+struct AnthropicUsage {
+    input_tokens: u64,
+    output_tokens: u64,
 }
 
 /// Content block within an Anthropic API response.
@@ -244,6 +252,13 @@ impl ChatResponse for AnthropicCompleteResponse {
             v => Some(v),
         }
     }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage.as_ref().map(|u| Usage {
+            input_tokens: u.input_tokens,
+            output_tokens: u.output_tokens,
+        })
+    }
 }
 
 impl Anthropic {
@@ -321,90 +336,96 @@ impl ChatProvider for Anthropic {
             return Err(LLMError::AuthError("Missing Anthropic API key".to_string()));
         }
 
-        let anthropic_messages: Vec<AnthropicMessage> = messages
+        let anthropic_messages = messages
             .iter()
-            .map(|m| AnthropicMessage {
-                role: match m.role {
-                    ChatRole::User => "user",
-                    ChatRole::Tool => "tool",
-                    ChatRole::Assistant => "assistant",
-                    ChatRole::System => "system",
-                },
-                content: match &m.message_type {
-                    MessageType::Text => vec![MessageContent {
-                        message_type: Some("text"),
-                        text: Some(&m.content),
-                        image_url: None,
-                        source: None,
-                        tool_use_id: None,
-                        tool_input: None,
-                        tool_name: None,
-                        tool_result_id: None,
-                        tool_output: None,
-                    }],
-                    MessageType::Pdf(_) => unimplemented!(),
-                    MessageType::Image((image_mime, raw_bytes)) => {
-                        vec![MessageContent {
-                            message_type: Some("image"),
-                            text: None,
+            .map(|m| -> Result<AnthropicMessage, LLMError> {
+                Ok(AnthropicMessage {
+                    role: match m.role {
+                        ChatRole::User => "user",
+                        ChatRole::Tool => "tool",
+                        ChatRole::Assistant => "assistant",
+                        ChatRole::System => "system",
+                    },
+                    content: match &m.message_type {
+                        MessageType::Text => vec![MessageContent {
+                            message_type: Some("text"),
+                            text: Some(&m.content),
                             image_url: None,
-                            source: Some(ImageSource {
-                                source_type: "base64",
-                                media_type: image_mime.mime_type(),
-                                data: BASE64.encode(raw_bytes),
-                            }),
+                            source: None,
                             tool_use_id: None,
                             tool_input: None,
                             tool_name: None,
                             tool_result_id: None,
                             tool_output: None,
-                        }]
-                    }
-                    MessageType::ImageURL(ref url) => vec![MessageContent {
-                        message_type: Some("image_url"),
-                        text: None,
-                        image_url: Some(ImageUrlContent { url }),
-                        source: None,
-                        tool_use_id: None,
-                        tool_input: None,
-                        tool_name: None,
-                        tool_result_id: None,
-                        tool_output: None,
-                    }],
-                    MessageType::ToolUse(calls) => calls
-                        .iter()
-                        .map(|c| MessageContent {
-                            message_type: Some("tool_use"),
-                            text: None,
-                            image_url: None,
-                            source: None,
-                            tool_use_id: Some(c.id.clone()),
-                            tool_input: Some(
-                                serde_json::from_str(&c.function.arguments)
-                                    .unwrap_or(c.function.arguments.clone().into()),
-                            ),
-                            tool_name: Some(c.function.name.clone()),
-                            tool_result_id: None,
-                            tool_output: None,
-                        })
-                        .collect(),
-                    MessageType::ToolResult(responses) => responses
-                        .iter()
-                        .map(|r| MessageContent {
-                            message_type: Some("tool_result"),
+                        }],
+                        MessageType::Pdf(_) => {
+                            return Err(LLMError::InvalidRequest(
+                                "Anthropic backend does not support PDF messages".to_string(),
+                            ))
+                        }
+                        MessageType::Image((image_mime, raw_bytes)) => {
+                            vec![MessageContent {
+                                message_type: Some("image"),
+                                text: None,
+                                image_url: None,
+                                source: Some(ImageSource {
+                                    source_type: "base64",
+                                    media_type: image_mime.mime_type(),
+                                    data: BASE64.encode(raw_bytes),
+                                }),
+                                tool_use_id: None,
+                                tool_input: None,
+                                tool_name: None,
+                                tool_result_id: None,
+                                tool_output: None,
+                            }]
+                        }
+                        MessageType::ImageURL(ref url) => vec![MessageContent {
+                            message_type: Some("image_url"),
                             text: None,
-                            image_url: None,
+                            image_url: Some(ImageUrlContent { url }),
                             source: None,
                             tool_use_id: None,
                             tool_input: None,
                             tool_name: None,
-                            tool_result_id: Some(r.id.clone()),
-                            tool_output: Some(r.function.arguments.clone()),
-                        })
-                        .collect(),
-                },
+                            tool_result_id: None,
+                            tool_output: None,
+                        }],
+                        MessageType::ToolUse(calls) => calls
+                            .iter()
+                            .map(|c| MessageContent {
+                                message_type: Some("tool_use"),
+                                text: None,
+                                image_url: None,
+                                source: None,
+                                tool_use_id: Some(c.id.clone()),
+                                tool_input: Some(
+                                    serde_json::from_str(&c.function.arguments)
+                                        .unwrap_or(c.function.arguments.clone().into()),
+                                ),
+                                tool_name: Some(c.function.name.clone()),
+                                tool_result_id: None,
+                                tool_output: None,
+                            })
+                            .collect(),
+                        MessageType::ToolResult(responses) => responses
+                            .iter()
+                            .map(|r| MessageContent {
+                                message_type: Some("tool_result"),
+                                text: None,
+                                image_url: None,
+                                source: None,
+                                tool_use_id: None,
+                                tool_input: None,
+                                tool_name: None,
+                                tool_result_id: Some(r.id.clone()),
+                                tool_output: Some(r.function.arguments.clone()),
+                            })
+                            .collect(),
+                    },
+                })
             })
-            .collect();
+            .collect::<Result<Vec<_>, _>>()?;
 
         let maybe_tool_slice: Option<&[Tool]> = tools.or(self.tools.as_deref());
         let anthropic_tools = maybe_tool_slice.map(|slice| {
@@ -530,59 +551,65 @@ impl ChatProvider for Anthropic {
             return Err(LLMError::AuthError("Missing Anthropic API key".to_string()));
         }
 
-        let anthropic_messages: Vec<AnthropicMessage> = messages
+        let anthropic_messages = messages
             .iter()
-            .map(|m| AnthropicMessage {
-                role: match m.role {
-                    ChatRole::User => "user",
-                    ChatRole::Tool => "tool",
-                    ChatRole::Assistant => "assistant",
-                    ChatRole::System => "system",
-                },
-                content: match &m.message_type {
-                    MessageType::Text => vec![MessageContent {
-                        message_type: Some("text"),
-                        text: Some(&m.content),
-                        image_url: None,
-                        source: None,
-                        tool_use_id: None,
-                        tool_input: None,
-                        tool_name: None,
-                        tool_result_id: None,
-                        tool_output: None,
-                    }],
-                    MessageType::Pdf(_) => unimplemented!(),
-                    MessageType::Image((image_mime, raw_bytes)) => {
-                        vec![MessageContent {
-                            message_type: Some("image"),
-                            text: None,
+            .map(|m| -> Result<AnthropicMessage, LLMError> {
+                Ok(AnthropicMessage {
+                    role: match m.role {
+                        ChatRole::User => "user",
+                        ChatRole::Tool => "tool",
+                        ChatRole::Assistant => "assistant",
+                        ChatRole::System => "system",
+                    },
+                    content: match &m.message_type {
+                        MessageType::Text => vec![MessageContent {
+                            message_type: Some("text"),
+                            text: Some(&m.content),
                             image_url: None,
-                            source: Some(ImageSource {
-                                source_type: "base64",
-                                media_type: image_mime.mime_type(),
-                                data: BASE64.encode(raw_bytes),
-                            }),
+                            source: None,
                             tool_use_id: None,
                             tool_input: None,
                             tool_name: None,
                             tool_result_id: None,
                             tool_output: None,
-                        }]
-                    }
-                    _ => vec![MessageContent {
-                        message_type: Some("text"),
-                        text: Some(&m.content),
-                        image_url: None,
-                        source: None,
-                        tool_use_id: None,
-                        tool_input: None,
-                        tool_name: None,
-                        tool_result_id: None,
-                        tool_output: None,
-                    }],
-                },
+                        }],
+                        MessageType::Pdf(_) => {
+                            return Err(LLMError::InvalidRequest(
+                                "Anthropic backend does not support PDF messages".to_string(),
+                            ))
+                        }
+                        MessageType::Image((image_mime, raw_bytes)) => {
+                            vec![MessageContent {
+                                message_type: Some("image"),
+                                text: None,
+                                image_url: None,
+                                source: Some(ImageSource {
+                                    source_type: "base64",
+                                    media_type: image_mime.mime_type(),
+                                    data: BASE64.encode(raw_bytes),
+                                }),
+                                tool_use_id: None,
+                                tool_input: None,
+                                tool_name: None,
+                                tool_result_id: None,
+                                tool_output: None,
+                            }]
+                        }
+                        _ => vec![MessageContent {
+                            message_type: Some("text"),
+                            text: Some(&m.content),
+                            image_url: None,
+                            source: None,
+                            tool_use_id: None,
+                            tool_input: None,
+                            tool_name: None,
+                            tool_result_id: None,
+                            tool_output: None,
+                        }],
+                    },
+                })
             })
-            .collect();
+            .collect::<Result<Vec<_>, _>>()?;
 
         let req_body = AnthropicCompleteRequest {
             messages: anthropic_messages,
@@ -17,9 +17,11 @@ use crate::{
     LLMProvider,
 };
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use either::*;
 use reqwest::{Client, Url};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::sync::Arc;
 
 /// Client for interacting with Azure OpenAI's API.
@@ -64,9 +66,11 @@ struct AzureOpenAIChatMessage<'a> {
     tool_call_id: Option<String>,
 }
 
-impl<'a> From<&'a ChatMessage> for AzureOpenAIChatMessage<'a> {
-    fn from(chat_msg: &'a ChatMessage) -> Self {
-        Self {
+impl<'a> TryFrom<&'a ChatMessage> for AzureOpenAIChatMessage<'a> {
+    type Error = LLMError;
+
+    fn try_from(chat_msg: &'a ChatMessage) -> Result<Self, Self::Error> {
+        Ok(Self {
             role: match chat_msg.role {
                 ChatRole::User => "user",
                 ChatRole::Assistant => "assistant",
@@ -76,20 +80,37 @@ impl<'a> From<&'a ChatMessage> for AzureOpenAIChatMessage<'a> {
             tool_call_id: None,
             content: match &chat_msg.message_type {
                 MessageType::Text => Some(Right(chat_msg.content.clone())),
-                // Image case is handled separately above
-                MessageType::Image(_) => unreachable!(),
-                MessageType::Pdf(_) => unimplemented!(),
-                MessageType::ImageURL(url) => {
-                    // Clone the URL to create an owned version
-
+                // Raw images are sent inline as a data URL
+                MessageType::Image((image_mime, raw_bytes)) => {
+                    let data_url = format!(
+                        "data:{};base64,{}",
+                        image_mime.mime_type(),
+                        BASE64.encode(raw_bytes)
+                    );
                     Some(Left(vec![AzureMessageContent {
                         message_type: Some("image_url"),
                         text: None,
-                        image_url: Some(ImageUrlContent { url }),
+                        image_url: Some(ImageUrlContent {
+                            url: Cow::Owned(data_url),
+                        }),
                         tool_output: None,
                         tool_call_id: None,
                     }]))
                 }
+                MessageType::Pdf(_) => {
+                    return Err(LLMError::InvalidRequest(
+                        "Azure OpenAI backend does not support PDF messages".to_string(),
+                    ))
+                }
+                MessageType::ImageURL(url) => Some(Left(vec![AzureMessageContent {
+                    message_type: Some("image_url"),
+                    text: None,
+                    image_url: Some(ImageUrlContent {
+                        url: Cow::Borrowed(url),
+                    }),
+                    tool_output: None,
+                    tool_call_id: None,
+                }])),
                 MessageType::ToolUse(_) => None,
                 MessageType::ToolResult(_) => None,
             },
@@ -101,7 +122,7 @@ impl<'a> From<&'a ChatMessage> for AzureOpenAIChatMessage<'a> {
                 }
                 _ => None,
             },
-        }
+        })
     }
 }
 
@@ -155,7 +176,7 @@ struct AzureMessageContent<'a> {
 /// Individual image message in an OpenAI chat conversation.
 #[derive(Serialize, Debug)]
 struct ImageUrlContent<'a> {
-    url: &'a str,
+    url: Cow<'a, str>,
 }
 
 #[derive(Serialize)]
@@ -425,7 +446,7 @@ impl ChatProvider for AzureOpenAI {
                     );
                 }
             } else {
-                openai_msgs.push(msg.into())
+                openai_msgs.push(msg.try_into()?)
             }
         }
 
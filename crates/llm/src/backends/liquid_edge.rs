@@ -451,6 +451,10 @@ impl LLMProvider for LiquidEdge {
     fn tools(&self) -> Option<&[crate::chat::Tool]> {
         None // Tools not supported yet
     }
+
+    fn supports_tool_calls(&self) -> bool {
+        false
+    }
 }
 
 impl LLMBuilder<LiquidEdge> {
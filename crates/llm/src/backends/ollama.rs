@@ -4,7 +4,9 @@
 
 use crate::{
     builder::LLMBuilder,
-    chat::{ChatMessage, ChatProvider, ChatResponse, ChatRole, StructuredOutputFormat, Tool},
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, ChatRole, StructuredOutputFormat, Tool, Usage,
+    },
     completion::{CompletionProvider, CompletionRequest, CompletionResponse},
     embedding::EmbeddingProvider,
     error::LLMError,
@@ -69,6 +71,8 @@ struct OllamaResponse {
     content: Option<String>,
     response: Option<String>,
     message: Option<OllamaChatResponseMessage>,
+    prompt_eval_count: Option<u64>,
+    eval_count: Option<u64>,
 }
 
 impl std::fmt::Display for OllamaResponse {
@@ -125,6 +129,16 @@ impl ChatResponse for OllamaResponse {
             })
         })
     }
+
+    fn usage(&self) -> Option<Usage> {
+        match (self.prompt_eval_count, self.eval_count) {
+            (None, None) => None,
+            (input, output) => Some(Usage {
+                input_tokens: input.unwrap_or_default(),
+                output_tokens: output.unwrap_or_default(),
+            }),
+        }
+    }
 }
 
 /// Message content within an Ollama chat API response.
@@ -14,16 +14,18 @@ use crate::{
 };
 use crate::{
     builder::LLMBuilder,
-    chat::{ChatResponse, ToolChoice},
+    chat::{ChatResponse, ToolChoice, Usage},
     FunctionCall, ToolCall,
 };
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use chrono::{DateTime, Utc};
 use either::*;
 use futures::stream::Stream;
 use reqwest::{Client, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::borrow::Cow;
 use std::sync::Arc;
 
 /// Client for interacting with OpenAI's API.
@@ -105,7 +107,7 @@ struct MessageContent<'a> {
 /// Individual image message in an OpenAI chat conversation.
 #[derive(Serialize, Debug)]
 struct ImageUrlContent<'a> {
-    url: &'a str,
+    url: Cow<'a, str>,
 }
 
 #[derive(Serialize)]
@@ -168,6 +170,14 @@ impl std::fmt::Display for FunctionCall {
 #[derive(Deserialize, Debug)]
 struct OpenAIChatResponse {
     choices: Vec<OpenAIChatChoice>,
+    usage: Option<OpenAIUsage>,
+}
+
+/// Token usage within an OpenAI chat API response.
+#[derive(Deserialize, Debug)]
+struct OpenAIUsage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
 }
 
 /// Individual choice within an OpenAI chat API response.
@@ -300,6 +310,13 @@ impl ChatResponse for OpenAIChatResponse {
             .first()
             .and_then(|c| c.message.tool_calls.clone())
     }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage.as_ref().map(|u| Usage {
+            input_tokens: u.prompt_tokens,
+            output_tokens: u.completion_tokens,
+        })
+    }
 }
 
 impl std::fmt::Display for OpenAIChatResponse {
@@ -474,9 +491,6 @@ impl ChatProvider for OpenAI {
             return Err(LLMError::AuthError("Missing OpenAI API key".to_string()));
         }
 
-        // Clone the messages to have an owned mutable vector.
-        let messages = messages.to_vec();
-
         let mut openai_msgs: Vec<OpenAIChatMessage> = vec![];
 
         for msg in messages {
@@ -493,7 +507,7 @@ impl ChatProvider for OpenAI {
                     );
                 }
             } else {
-                openai_msgs.push(chat_message_to_api_message(msg))
+                openai_msgs.push(chat_message_to_api_message(msg)?)
             }
         }
 
@@ -643,7 +657,6 @@ impl ChatProvider for OpenAI {
             return Err(LLMError::AuthError("Missing OpenAI API key".to_string()));
         }
 
-        let messages = messages.to_vec();
         let mut openai_msgs: Vec<OpenAIChatMessage> = vec![];
 
         for msg in messages {
@@ -657,7 +670,7 @@ impl ChatProvider for OpenAI {
                     });
                 }
             } else {
-                openai_msgs.push(chat_message_to_api_message(msg))
+                openai_msgs.push(chat_message_to_api_message(msg)?)
             }
         }
 
@@ -720,10 +733,9 @@ impl ChatProvider for OpenAI {
     }
 }
 
-// Create an owned OpenAIChatMessage that doesn't borrow from any temporary variables
-fn chat_message_to_api_message(chat_msg: ChatMessage) -> OpenAIChatMessage<'static> {
-    // For other message types, create an owned OpenAIChatMessage
-    OpenAIChatMessage {
+/// OpenAI message of a chat message, borrowing its strings
+fn chat_message_to_api_message(chat_msg: &ChatMessage) -> Result<OpenAIChatMessage<'_>, LLMError> {
+    Ok(OpenAIChatMessage {
         role: match chat_msg.role {
             ChatRole::User => "user",
             ChatRole::Assistant => "assistant",
@@ -733,56 +745,57 @@ fn chat_message_to_api_message(chat_msg: ChatMessage) -> OpenAIChatMessage<'stat
         tool_call_id: None,
         content: match &chat_msg.message_type {
             MessageType::Text => Some(Right(chat_msg.content.clone())),
-            // Image case is handled separately above
-            MessageType::Image(_) => unreachable!(),
-            MessageType::Pdf(_) => unimplemented!(),
-            MessageType::ImageURL(url) => {
-                // Clone the URL to create an owned version
-                let owned_url = url.clone();
-                // Leak the string to get a 'static reference
-                let url_str = Box::leak(owned_url.into_boxed_str());
+            // Raw images are sent inline as a data URL
+            MessageType::Image((image_mime, raw_bytes)) => {
+                let data_url = format!(
+                    "data:{};base64,{}",
+                    image_mime.mime_type(),
+                    BASE64.encode(raw_bytes)
+                );
                 Some(Left(vec![MessageContent {
                     message_type: Some("image_url"),
                     text: None,
-                    image_url: Some(ImageUrlContent { url: url_str }),
+                    image_url: Some(ImageUrlContent {
+                        url: Cow::Owned(data_url),
+                    }),
                     tool_output: None,
                     tool_call_id: None,
                 }]))
             }
+            MessageType::Pdf(_) => {
+                return Err(LLMError::InvalidRequest(
+                    "OpenAI backend does not support PDF messages".to_string(),
+                ))
+            }
+            MessageType::ImageURL(url) => Some(Left(vec![MessageContent {
+                message_type: Some("image_url"),
+                text: None,
+                image_url: Some(ImageUrlContent {
+                    url: Cow::Borrowed(url),
+                }),
+                tool_output: None,
+                tool_call_id: None,
+            }])),
             MessageType::ToolUse(_) => None,
             MessageType::ToolResult(_) => None,
         },
         tool_calls: match &chat_msg.message_type {
-            MessageType::ToolUse(calls) => {
-                let owned_calls: Vec<OpenAIFunctionCall<'static>> = calls
+            MessageType::ToolUse(calls) => Some(
+                calls
                     .iter()
-                    .map(|c| {
-                        let owned_id = c.id.clone();
-                        let owned_name = c.function.name.clone();
-                        let owned_args = c.function.arguments.clone();
-
-                        // Need to leak these strings to create 'static references
-                        // This is a deliberate choice to solve the lifetime issue
-                        // The small memory leak is acceptable in this context
-                        let id_str = Box::leak(owned_id.into_boxed_str());
-                        let name_str = Box::leak(owned_name.into_boxed_str());
-                        let args_str = Box::leak(owned_args.into_boxed_str());
-
-                        OpenAIFunctionCall {
-                            id: id_str,
-                            content_type: "function",
-                            function: OpenAIFunctionPayload {
-                                name: name_str,
-                                arguments: args_str,
-                            },
-                        }
+                    .map(|c| OpenAIFunctionCall {
+                        id: &c.id,
+                        content_type: "function",
+                        function: OpenAIFunctionPayload {
+                            name: &c.function.name,
+                            arguments: &c.function.arguments,
+                        },
                     })
-                    .collect();
-                Some(owned_calls)
-            }
+                    .collect(),
+            ),
             _ => None,
         },
-    }
+    })
 }
 
 #[async_trait]
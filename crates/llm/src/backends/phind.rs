@@ -305,7 +305,11 @@ impl EmbeddingProvider for Phind {
 impl ModelsProvider for Phind {}
 
 /// Implementation of the LLMProvider trait for Phind.
-impl LLMProvider for Phind {}
+impl LLMProvider for Phind {
+    fn supports_tool_calls(&self) -> bool {
+        false
+    }
+}
 
 impl LLMBuilder<Phind> {
     pub fn build(self) -> Result<Arc<Phind>, LLMError> {
@@ -241,12 +241,25 @@ impl Serialize for ToolChoice {
     }
 }
 
+/// Tokens consumed by a single request, as reported by the provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Tokens of the prompt
+    pub input_tokens: u64,
+    /// Tokens generated by the model
+    pub output_tokens: u64,
+}
+
 pub trait ChatResponse: std::fmt::Debug + std::fmt::Display + Send + Sync {
     fn text(&self) -> Option<String>;
     fn tool_calls(&self) -> Option<Vec<ToolCall>>;
     fn thinking(&self) -> Option<String> {
         None
     }
+    /// Token usage of the request, if the provider reports it
+    fn usage(&self) -> Option<Usage> {
+        None
+    }
 }
 
 /// Trait for providers that support chat-style interactions.
@@ -57,6 +57,11 @@ pub trait LLMProvider:
     fn tools(&self) -> Option<&[Tool]> {
         None
     }
+
+    /// Whether the provider accepts tool definitions and returns structured tool calls
+    fn supports_tool_calls(&self) -> bool {
+        true
+    }
 }
 
 /// Tool call represents a function call that an LLM wants to make.
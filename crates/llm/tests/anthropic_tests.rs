@@ -147,6 +147,17 @@ mod anthropic_test_cases {
         }
     }
 
+    #[tokio::test]
+    async fn test_chat_rejects_pdf() {
+        let client = create_test_anthropic();
+        let messages = vec![ChatMessage::user().pdf(b"%PDF-1.4".to_vec()).build()];
+
+        match client.chat(&messages, None).await {
+            Err(LLMError::InvalidRequest(msg)) => assert!(msg.contains("PDF")),
+            other => panic!("Expected InvalidRequest, got {:?}", other.map(|r| r.text())),
+        }
+    }
+
     #[test]
     fn test_streaming_configuration() {
         let client = LLMBuilder::<Anthropic>::new()
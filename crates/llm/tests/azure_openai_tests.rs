@@ -241,6 +241,17 @@ mod azure_openai_test_cases {
         }
     }
 
+    #[tokio::test]
+    async fn test_chat_rejects_pdf() {
+        let client = create_test_azure_openai();
+        let messages = vec![ChatMessage::user().pdf(b"%PDF-1.4".to_vec()).build()];
+
+        match client.chat(&messages, None).await {
+            Err(LLMError::InvalidRequest(msg)) => assert!(msg.contains("PDF")),
+            other => panic!("Expected InvalidRequest, got {:?}", other.map(|r| r.text())),
+        }
+    }
+
     #[test]
     fn test_timeout_configuration() {
         let client = LLMBuilder::<AzureOpenAI>::new()
@@ -344,6 +344,7 @@ mod liquid_edge_test_cases {
 #[cfg(not(feature = "liquid_edge"))]
 mod no_liquid_edge_tests {
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_liquid_edge_feature_disabled() {
         // Test that runs when liquid_edge feature is not enabled
         // This ensures the test file doesn't break when the feature is disabled
@@ -143,4 +143,15 @@ mod openai_test_cases {
             _ => panic!("Expected AuthError"),
         }
     }
+
+    #[tokio::test]
+    async fn test_chat_rejects_pdf() {
+        let client = create_test_openai();
+        let messages = vec![ChatMessage::user().pdf(b"%PDF-1.4".to_vec()).build()];
+
+        match client.chat(&messages, None).await {
+            Err(LLMError::InvalidRequest(msg)) => assert!(msg.contains("PDF")),
+            other => panic!("Expected InvalidRequest, got {:?}", other.map(|r| r.text())),
+        }
+    }
 }
@@ -1,12 +1,14 @@
 use async_trait::async_trait;
 use autoagents_llm::{
-    chat::{ChatMessage, ChatProvider, ChatResponse, StructuredOutputFormat},
+    chat::{ChatMessage, ChatProvider, ChatResponse, StructuredOutputFormat, Usage},
     completion::{CompletionProvider, CompletionRequest, CompletionResponse},
     embedding::EmbeddingProvider,
     error::LLMError,
     models::ModelsProvider,
     LLMProvider, ToolCall,
 };
+use std::collections::VecDeque;
+use std::sync::Mutex;
 
 // Mock LLM Provider
 pub struct MockLLMProvider;
@@ -21,6 +23,8 @@ impl ChatProvider for MockLLMProvider {
     ) -> Result<Box<dyn ChatResponse>, LLMError> {
         Ok(Box::new(MockChatResponse {
             text: Some("Mock response".to_string()),
+            tool_calls: None,
+            usage: None,
         }))
     }
 }
@@ -52,6 +56,8 @@ impl LLMProvider for MockLLMProvider {}
 
 struct MockChatResponse {
     text: Option<String>,
+    tool_calls: Option<Vec<ToolCall>>,
+    usage: Option<Usage>,
 }
 
 impl ChatResponse for MockChatResponse {
@@ -60,7 +66,11 @@ impl ChatResponse for MockChatResponse {
     }
 
     fn tool_calls(&self) -> Option<Vec<ToolCall>> {
-        None
+        self.tool_calls.clone()
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage
     }
 }
 
@@ -75,3 +85,150 @@ impl std::fmt::Display for MockChatResponse {
         write!(f, "{}", self.text.as_deref().unwrap_or(""))
     }
 }
+
+/// A response returned by [`ScriptedLLMProvider`]
+#[derive(Debug, Clone)]
+pub enum ScriptedResponse {
+    /// A plain text response
+    Text(String),
+    /// A response requesting tool calls
+    ToolCalls(Vec<ToolCall>),
+    /// A provider error
+    Error(String),
+}
+
+/// LLM provider that replays a fixed sequence of responses and records every request
+#[derive(Default)]
+pub struct ScriptedLLMProvider {
+    responses: Mutex<VecDeque<ScriptedResponse>>,
+    requests: Mutex<Vec<Vec<ChatMessage>>>,
+    embedder: Option<fn(&str) -> Vec<f32>>,
+    usage: Option<Usage>,
+    without_tool_calls: bool,
+}
+
+impl ScriptedLLMProvider {
+    pub fn new(responses: Vec<ScriptedResponse>) -> Self {
+        Self {
+            responses: Mutex::new(responses.into()),
+            requests: Mutex::new(Vec::new()),
+            embedder: None,
+            usage: None,
+            without_tool_calls: false,
+        }
+    }
+
+    /// Report `usage` with every response
+    pub fn with_usage(mut self, usage: Usage) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Compute embeddings with `embedder` instead of returning a constant vector
+    pub fn with_embedder(mut self, embedder: fn(&str) -> Vec<f32>) -> Self {
+        self.embedder = Some(embedder);
+        self
+    }
+
+    /// Report that the provider has no native tool calling
+    pub fn without_tool_calls(mut self) -> Self {
+        self.without_tool_calls = true;
+        self
+    }
+
+    /// Build a provider that only returns text responses
+    pub fn with_texts<S: Into<String>>(texts: Vec<S>) -> Self {
+        Self::new(
+            texts
+                .into_iter()
+                .map(|t| ScriptedResponse::Text(t.into()))
+                .collect(),
+        )
+    }
+
+    /// Messages sent with every chat request so far
+    pub fn requests(&self) -> Vec<Vec<ChatMessage>> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Number of responses not yet consumed
+    pub fn remaining(&self) -> usize {
+        self.responses.lock().unwrap().len()
+    }
+}
+
+/// Build a function tool call for scripted responses
+pub fn tool_call(id: &str, name: &str, arguments: &str) -> ToolCall {
+    ToolCall {
+        id: id.to_string(),
+        call_type: "function".to_string(),
+        function: autoagents_llm::FunctionCall {
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+        },
+    }
+}
+
+#[async_trait]
+impl ChatProvider for ScriptedLLMProvider {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        _tools: Option<&[autoagents_llm::chat::Tool]>,
+        _json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.requests.lock().unwrap().push(messages.to_vec());
+        let next = self.responses.lock().unwrap().pop_front();
+        match next {
+            Some(ScriptedResponse::Text(text)) => Ok(Box::new(MockChatResponse {
+                text: Some(text),
+                tool_calls: None,
+                usage: self.usage,
+            })),
+            Some(ScriptedResponse::ToolCalls(calls)) => Ok(Box::new(MockChatResponse {
+                text: None,
+                tool_calls: Some(calls),
+                usage: self.usage,
+            })),
+            Some(ScriptedResponse::Error(e)) => Err(LLMError::ProviderError(e)),
+            None => Err(LLMError::ProviderError(
+                "No scripted response left".to_string(),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for ScriptedLLMProvider {
+    async fn complete(
+        &self,
+        _req: &CompletionRequest,
+        _json_schema: Option<StructuredOutputFormat>,
+    ) -> Result<CompletionResponse, LLMError> {
+        Ok(CompletionResponse {
+            text: "Mock completion".to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for ScriptedLLMProvider {
+    async fn embed(&self, text: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        Ok(text
+            .iter()
+            .map(|t| match self.embedder {
+                Some(embedder) => embedder(t),
+                None => vec![0.1, 0.2, 0.3],
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for ScriptedLLMProvider {}
+
+impl LLMProvider for ScriptedLLMProvider {
+    fn supports_tool_calls(&self) -> bool {
+        !self.without_tool_calls
+    }
+}
@@ -38,7 +38,10 @@ impl AgentExecutor for Agent1 {
     type Error = Error;
 
     fn config(&self) -> ExecutorConfig {
-        ExecutorConfig { max_turns: 10 }
+        ExecutorConfig {
+            max_turns: 10,
+            ..Default::default()
+        }
     }
 
     async fn execute(
@@ -84,7 +87,10 @@ impl AgentExecutor for Agent2 {
     type Error = Error;
 
     fn config(&self) -> ExecutorConfig {
-        ExecutorConfig { max_turns: 10 }
+        ExecutorConfig {
+            max_turns: 10,
+            ..Default::default()
+        }
     }
 
     async fn execute(
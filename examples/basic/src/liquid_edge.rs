@@ -165,6 +165,7 @@ fn handle_events(mut event_stream: ReceiverStream<Event>) {
                 Event::TurnStarted {
                     turn_number,
                     max_turns,
+                    ..
                 } => {
                     println!(
                         "{}",
@@ -174,6 +175,7 @@ fn handle_events(mut event_stream: ReceiverStream<Event>) {
                 Event::TurnCompleted {
                     turn_number,
                     final_turn,
+                    ..
                 } => {
                     println!(
                         "{}",
@@ -150,6 +150,7 @@ fn handle_events(mut event_stream: ReceiverStream<Event>) {
                 Event::TurnStarted {
                     turn_number,
                     max_turns,
+                    ..
                 } => {
                     println!(
                         "{}",
@@ -159,6 +160,7 @@ fn handle_events(mut event_stream: ReceiverStream<Event>) {
                 Event::TurnCompleted {
                     turn_number,
                     final_turn,
+                    ..
                 } => {
                     println!(
                         "{}",
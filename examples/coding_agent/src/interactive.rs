@@ -153,6 +153,7 @@ fn handle_events(mut event_stream: ReceiverStream<Event>) {
                 Event::TurnStarted {
                     turn_number,
                     max_turns,
+                    ..
                 } => {
                     println!(
                         "{}",
@@ -162,6 +163,7 @@ fn handle_events(mut event_stream: ReceiverStream<Event>) {
                 Event::TurnCompleted {
                     turn_number,
                     final_turn,
+                    ..
                 } => {
                     println!(
                         "{}",
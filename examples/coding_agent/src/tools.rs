@@ -1,4 +1,4 @@
-use autoagents::core::tool::{ToolCallError, ToolInputT, ToolRuntime, ToolT};
+use autoagents::core::tool::{Capability, ToolCallError, ToolInputT, ToolRuntime, ToolT};
 use autoagents_derive::{tool, ToolInput};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
@@ -18,6 +18,7 @@ pub struct FileSearchArgs {
     name = "FileSearchTool",
     description = "Search for files matching a glob pattern in the project",
     input = FileSearchArgs,
+    capabilities = [FilesystemRead],
 )]
 pub struct FileSearchTool {}
 
@@ -73,6 +74,7 @@ pub struct GrepArgs {
     name = "GrepTool",
     description = "Search for content in files using regex patterns",
     input = GrepArgs,
+    capabilities = [FilesystemRead],
 )]
 pub struct GrepTool {}
 
@@ -154,6 +156,7 @@ pub struct ReadFileArgs {
     name = "ReadFileTool",
     description = "Read contents of a file, optionally specifying line range",
     input = ReadFileArgs,
+    capabilities = [FilesystemRead],
 )]
 pub struct ReadFileTool {}
 
@@ -188,6 +191,7 @@ pub struct WriteFileArgs {
     name = "WriteFileTool",
     description = "Write content to a file (create or overwrite)",
     input = WriteFileArgs,
+    capabilities = [FilesystemWrite],
 )]
 pub struct WriteFileTool {}
 
@@ -228,6 +232,7 @@ pub struct DeleteFileArgs {
     name = "DeleteFileTool",
     description = "Delete a file from the filesystem",
     input = DeleteFileArgs,
+    capabilities = [FilesystemWrite],
 )]
 pub struct DeleteFileTool {}
 
@@ -261,6 +266,7 @@ pub struct ListDirectoryArgs {
     name = "ListDirectoryTool",
     description = "List contents of a directory",
     input = ListDirectoryArgs,
+    capabilities = [FilesystemRead],
 )]
 pub struct ListDirectoryTool {}
 
@@ -333,6 +339,7 @@ pub struct AnalyzeCodeArgs {
     name = "AnalyzeCodeTool",
     description = "Analyze code structure, complexity, or dependencies",
     input = AnalyzeCodeArgs,
+    capabilities = [FilesystemRead],
 )]
 pub struct AnalyzeCodeTool {}
 